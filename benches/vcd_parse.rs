@@ -1,35 +1,105 @@
-#![allow(unused)]
-
 use anyhow::Result;
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
-use rvcd::wave::vcd_parser::Vcd;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use rvcd::wave::filter::WaveFilter;
+use rvcd::wave::vcd_parser::{Vcd, VCD_PARALLEL_CHUNK_SIZE};
 use rvcd::wave::WaveLoader;
-use std::fs::File;
-use tracing::{info, warn};
+use std::fmt::Write;
+
+/// Vcd file to load instead of the generated one, e.g. a dump of your own design
+const BENCH_VCD_ENV: &str = "RVCD_BENCH_VCD";
+
+/// Vcd code of signal `index`
+fn vcd_code(mut index: usize) -> String {
+    let mut code = String::new();
+    loop {
+        code.push((b'!' + (index % 94) as u8) as char);
+        index /= 94;
+        if index == 0 {
+            break code;
+        }
+    }
+}
 
-fn optimize_vcd_parser(path: &str) -> Result<()> {
-    info!("optimize_vcd_parser({})", path);
-    if let Ok(mut input) = File::open(path) {
-        Vcd::load(&mut input, |_, _| {}, None)?;
-    } else {
-        warn!("file not found: {}", path);
+/// Generate a vcd of about `size` bytes with a clock, 1-bit wires and 32-bit buses
+/// changing pseudo-randomly
+fn vcd_generate(size: usize) -> Vec<u8> {
+    const WIRES: usize = 32;
+    const BUSES: usize = 32;
+    let mut text = String::from("$timescale 1ns $end\n$scope module top $end\n");
+    writeln!(text, "$var wire 1 {} clk $end", vcd_code(0)).unwrap();
+    for i in 0..WIRES {
+        writeln!(text, "$var wire 1 {} w{i} $end", vcd_code(1 + i)).unwrap();
+    }
+    for i in 0..BUSES {
+        writeln!(text, "$var reg 32 {} b{i} $end", vcd_code(1 + WIRES + i)).unwrap();
     }
+    text.push_str("$upscope $end\n$enddefinitions $end\n");
+    let mut seed = 0x2545f4914f6cdd1du64;
+    let mut random = || {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        seed
+    };
+    let mut timestamp = 0u64;
+    while text.len() < size {
+        writeln!(text, "#{timestamp}\n{}{}", (timestamp / 5) % 2, vcd_code(0)).unwrap();
+        for _ in 0..4 {
+            let r = random();
+            let i = r as usize % (WIRES + BUSES);
+            match i < WIRES {
+                true => writeln!(text, "{}{}", (r >> 32) & 1, vcd_code(1 + i)),
+                false => writeln!(text, "b{:b} {}", (r >> 32) as u32, vcd_code(1 + i)),
+            }
+            .unwrap();
+        }
+        timestamp += 5;
+    }
+    text.into_bytes()
+}
+
+fn load_serial(data: &[u8]) -> Result<()> {
+    Vcd.load(&mut &data[..], &|_, _| {}, None)?;
+    Ok(())
+}
+
+fn load_parallel(data: &[u8], threads: usize) -> Result<()> {
+    Vcd::load_parallel(
+        &mut &data[..],
+        |_, _| {},
+        None,
+        threads,
+        VCD_PARALLEL_CHUNK_SIZE,
+        &WaveFilter::default(),
+    )?;
     Ok(())
 }
 
 pub fn criterion_benchmark(c: &mut Criterion) {
-    let files = [
-        // "data/testbench.vcd",
-        // "data/cpu_ila_commit.vcd",
-        "/home/chiro/programs/scaleda-sample-project/.sim/Icarus-Run iverilog simulation/tb_waterfall_waveform.vcd"
-    ];
-    for file in files {
-        let id = format!("load {file}");
-        println!("id: {id}");
-        c.bench_function(id.as_ref(), |b| {
-            b.iter(|| black_box(optimize_vcd_parser(file)))
+    let (name, data) = match std::env::var(BENCH_VCD_ENV) {
+        Ok(path) => {
+            let data = std::fs::read(&path).unwrap_or_else(|e| panic!("read {path}: {e}"));
+            (path, data)
+        }
+        Err(_) => ("generated".to_string(), vcd_generate(32 * 1024 * 1024)),
+    };
+    println!("loading {} vcd of {} bytes", name, data.len());
+    let cores = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1);
+    let mut threads_list = vec![1, 2, 4, cores];
+    threads_list.sort();
+    threads_list.dedup();
+    let mut group = c.benchmark_group(format!("load {name}"));
+    group.sample_size(10);
+    group.bench_function("serial", |b| b.iter(|| black_box(load_serial(&data))));
+    // compare with serial parser above
+    for threads in threads_list.iter() {
+        group.bench_with_input(BenchmarkId::new("parallel", threads), threads, |b, t| {
+            b.iter(|| black_box(load_parallel(&data, *t)))
         });
     }
+    group.finish();
 }
 
 criterion_group!(benches, criterion_benchmark);
@@ -11,6 +11,8 @@ use crate::run_mode::RunMode;
 use crate::rvcd::State;
 use crate::utils::sleep_ms;
 use crate::verilog::VerilogGotoSource;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::wave::filter::WaveFilter;
 use crate::Rvcd;
 use eframe::emath::Align;
 use eframe::glow::Context;
@@ -71,6 +73,10 @@ pub struct RvcdApp {
     pub code_editor: CodeEditorType,
     #[cfg(not(target_arch = "wasm32"))]
     pub default_source_dir: String,
+    /// Load filter and time window given from command line, applied to all windows
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    pub default_load_filter: WaveFilter,
     #[serde(skip)]
     pub global_frame: &'static mut FrameMutex<Option<Arc<ColorImage>>>,
     #[serde(skip)]
@@ -110,6 +116,8 @@ impl Default for RvcdApp {
             code_editor: Default::default(),
             #[cfg(not(target_arch = "wasm32"))]
             default_source_dir: "".to_string(),
+            #[cfg(not(target_arch = "wasm32"))]
+            default_load_filter: Default::default(),
             #[allow(static_mut_refs)]
             global_frame: unsafe { FRAME.assume_init_mut() },
             extra_events: None,
@@ -125,6 +133,7 @@ impl RvcdApp {
         #[cfg(not(target_arch = "wasm32"))] manager_tx: mpsc::Sender<RvcdManagerMessage>,
         #[cfg(not(target_arch = "wasm32"))] exit_tx: mpsc::Sender<RvcdExitMessage>,
         #[cfg(not(target_arch = "wasm32"))] default_source_dir: Option<String>,
+        #[cfg(not(target_arch = "wasm32"))] default_load_filter: WaveFilter,
     ) -> Self {
         // load chinese font
         let mut fonts = FontDefinitions::default();
@@ -148,6 +157,10 @@ impl RvcdApp {
         if let Some(s) = default_source_dir {
             def.default_source_dir = s;
         }
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            def.default_load_filter = default_load_filter;
+        }
         if def.locale.is_empty() {
             // detect locate
             // TODO: detect on windows
@@ -176,6 +189,8 @@ impl RvcdApp {
             .apps
             .into_iter()
             .map(|mut a| {
+                #[cfg(not(target_arch = "wasm32"))]
+                a.set_load_filter(&def.default_load_filter);
                 a.init();
                 a
             })
@@ -260,6 +275,8 @@ impl RvcdApp {
     fn new_window(&mut self, maximize: bool) -> usize {
         let id = self.new_id();
         let mut n = Rvcd::new(id);
+        #[cfg(not(target_arch = "wasm32"))]
+        n.set_load_filter(&self.default_load_filter);
         n.init();
         if let Some(tx) = self.loop_tx.clone() {
             n.set_upper_tx(tx);
@@ -857,6 +874,20 @@ impl eframe::App for RvcdApp {
                             }
                         }
                     }
+                    RvcdRpcMessage::OpenWaveStream(source) => {
+                        let app_id =
+                            match self.apps.iter().find(|app| {
+                                Some(app.id) == self.app_now_id && app.state == State::Idle
+                            }) {
+                                Some(app) => app.id,
+                                None => self.new_window(self.app_now_id.is_none()),
+                            };
+                        if let Some(app) = self.apps.iter_mut().find(|app| app.id == app_id) {
+                            info!("send app<{}> stream {}", app.id, source);
+                            app.handle_rpc_message(message.clone());
+                            handled_app.push(app.id);
+                        }
+                    }
                     RvcdRpcMessage::OpenSourceFile(path) => {
                         for app in &mut self.apps {
                             app.handle_rpc_message(RvcdRpcMessage::OpenSourceFile(
@@ -0,0 +1,601 @@
+use crate::radix::Radix;
+use crate::view::export::WaveImage;
+use crate::view::session::WaveSession;
+use crate::view::signal::SignalView;
+use crate::view::vector::VectorFormat;
+use crate::view::WaveView;
+use crate::wave::filter::{WaveFilter, WaveTime};
+use crate::wave::loader::WaveLoaders;
+use crate::wave::vcd_writer::vcd_write;
+use crate::wave::{Wave, WaveDataValue, WaveTreeNode};
+use anyhow::{anyhow, Context, Result};
+use clap::{Args, Subcommand};
+use serde_json::{json, Value};
+use std::io::{BufWriter, Write};
+use tracing::{info, warn};
+
+/// Commands run without gui
+#[derive(Subcommand, Debug)]
+pub enum RvcdCommand {
+    /// Write selected signals and time window of a wave file to vcd
+    Export {
+        /// Wave file to read
+        input: String,
+        /// Vcd file to write, `-` for stdout
+        #[arg(short, long, default_value = "-")]
+        output: String,
+        #[command(flatten)]
+        selection: RvcdSelection,
+    },
+    /// Paint signals of a wave file like the wave panel, as svg or pdf image
+    Render {
+        /// Wave file to read
+        input: String,
+        /// Svg or pdf file to write, format by extension
+        #[arg(short, long)]
+        output: String,
+        /// Paint signals, range, cursors and settings of a view file saved by the viewer or
+        /// a gtkwave `.gtkw` save file instead of selected signals, `--filter` and
+        /// `--signals` are ignored then
+        #[arg(long)]
+        view: Option<String>,
+        /// Page width in points
+        #[arg(long, default_value = "1200")]
+        width: f32,
+        /// Paint in dark theme
+        #[arg(long)]
+        dark: bool,
+        #[command(flatten)]
+        selection: RvcdSelection,
+    },
+    /// Print timescale, time range and signal count of a wave file
+    Info {
+        /// Wave file to read
+        input: String,
+        #[command(flatten)]
+        format: RvcdFormat,
+    },
+    /// Print scopes and signals of a wave file with types and widths
+    List {
+        /// Wave file to read
+        input: String,
+        #[command(flatten)]
+        format: RvcdFormat,
+    },
+    /// Print value of a signal at a time
+    Value {
+        /// Wave file to read
+        input: String,
+        /// Signal path like `top.cpu.pc`
+        path: String,
+        /// Time like `1200us`, timestamp in file without unit
+        time: String,
+        #[command(flatten)]
+        format: RvcdFormat,
+    },
+    /// Print value changes of a signal, between two times if given
+    Changes {
+        /// Wave file to read
+        input: String,
+        /// Signal path like `top.cpu.pc`
+        path: String,
+        /// Start time like `1200us`, the last value before it is printed at start
+        from: Option<String>,
+        /// End time like `1300us`
+        to: Option<String>,
+        #[command(flatten)]
+        format: RvcdFormat,
+    },
+}
+
+/// How to print command output
+#[derive(Args, Debug)]
+pub struct RvcdFormat {
+    /// Print json instead of text
+    #[arg(long)]
+    pub json: bool,
+    /// Radix of values, `bin`, `oct`, `dec` or `hex`
+    #[arg(long, default_value = "hex", value_parser = cli_radix)]
+    pub radix: Radix,
+}
+
+fn cli_radix(text: &str) -> Result<Radix> {
+    match text.to_ascii_lowercase().as_str() {
+        "bin" | "2" => Ok(Radix::Bin),
+        "oct" | "8" => Ok(Radix::Oct),
+        "dec" | "10" => Ok(Radix::Dec),
+        "hex" | "16" => Ok(Radix::Hex),
+        _ => Err(anyhow!("unknown radix {text}, expect bin, oct, dec or hex")),
+    }
+}
+
+/// Signals and time window to work on
+#[derive(Args, Debug, Default)]
+pub struct RvcdSelection {
+    /// Matched scopes or signals only, glob like `top.cpu.*` or `/regex/`
+    #[arg(long)]
+    pub filter: Vec<String>,
+    /// Signals listed in file only, one signal path per line
+    #[arg(long)]
+    pub signals: Option<String>,
+    /// Value changes after this time only, like `1200us`, the last value before it is kept
+    #[arg(long)]
+    pub start: Option<String>,
+    /// Value changes before this time only, like `1300us`
+    #[arg(long)]
+    pub end: Option<String>,
+}
+
+impl RvcdSelection {
+    pub fn filter(&self) -> Result<WaveFilter> {
+        let mut patterns = self.filter.clone();
+        if let Some(file) = &self.signals {
+            let text = std::fs::read_to_string(file)
+                .with_context(|| format!("cannot read signal list {file}"))?;
+            patterns.extend(
+                text.lines()
+                    .map(|s| s.trim().to_string())
+                    .filter(|s| !s.is_empty()),
+            );
+        }
+        let mut filter = WaveFilter::parse(&patterns.join(", "))?;
+        filter.set_window(
+            self.start.as_deref().unwrap_or_default(),
+            self.end.as_deref().unwrap_or_default(),
+        )?;
+        Ok(filter)
+    }
+}
+
+/// Load value changes of wave file `path` selected by `filter`, with name of its format
+pub fn cli_load(path: &str, filter: &WaveFilter) -> Result<(Wave, &'static str)> {
+    let file = std::fs::File::open(path).with_context(|| format!("cannot open {path}"))?;
+    let loaders = WaveLoaders::default();
+    let mut opened = loaders.open(path, file)?;
+    let format = opened.loader.name();
+    info!("loading {} file {}", format, path);
+    let (wave, _) = opened
+        .loader
+        .load_source(&mut *opened.source, &|_, _| {}, filter)?;
+    Ok((wave, format))
+}
+
+/// Filter keeping value changes of signal `path` between `start` and `end`
+fn cli_filter_signal(path: &str, start: &str, end: &str) -> Result<WaveFilter> {
+    let mut filter = WaveFilter {
+        patterns: vec![path.to_string()],
+        ..Default::default()
+    };
+    filter.set_window(start, end)?;
+    Ok(filter)
+}
+
+/// Id of signal or alias `path` joined by `.`
+fn cli_signal(wave: &Wave, path: &str) -> Result<u64> {
+    wave.info
+        .all_signal_paths()
+        .find(|(_, p)| p.join(".") == path)
+        .map(|(id, _)| id)
+        .ok_or_else(|| anyhow!("signal {path} not found"))
+}
+
+/// Value in radix, or enum literal name
+fn cli_value(wave: &Wave, id: u64, value: &WaveDataValue, radix: Radix) -> String {
+    match wave.info.enum_literal(id, value) {
+        Some(literal) => literal.to_string(),
+        None => value.as_radix(radix),
+    }
+}
+
+fn cli_info(wave: &Wave, format: &str, json: bool, out: &mut dyn Write) -> Result<()> {
+    let info = &wave.info;
+    let signals = info.all_signal_paths().count();
+    let timescale = format!("{}{}", info.timescale.0, info.timescale.1);
+    if json {
+        writeln!(
+            out,
+            "{}",
+            json!({
+                "format": format,
+                "timescale": timescale,
+                "range": [info.range.0, info.range.1],
+                "signals": signals,
+                "headers": info.headers,
+            })
+        )?;
+        return Ok(());
+    }
+    writeln!(out, "format: {format}")?;
+    writeln!(out, "timescale: {timescale}")?;
+    writeln!(out, "range: #{}~#{}", info.range.0, info.range.1)?;
+    writeln!(out, "signals: {signals}")?;
+    let mut headers = info.headers.iter().collect::<Vec<_>>();
+    headers.sort();
+    for (key, value) in headers {
+        writeln!(out, "{key}: {value}")?;
+    }
+    Ok(())
+}
+
+/// Scopes and signals under `node` as json objects
+fn cli_list_json(node: &trees::Node<WaveTreeNode>, path: &[String]) -> Vec<Value> {
+    node.iter()
+        .filter_map(|child| match child.data() {
+            WaveTreeNode::WaveScope(scope) => {
+                let path = [path, &[scope.name.to_string()]].concat();
+                Some(json!({
+                    "name": scope.name,
+                    "type": scope.typ.to_string(),
+                    "children": cli_list_json(child, &path),
+                }))
+            }
+            WaveTreeNode::WaveVar(var) => {
+                let path = [path, &[var.name.to_string()]].concat();
+                Some(json!({
+                    "name": var.name,
+                    "path": path.join("."),
+                    "type": var.type_name(),
+                    "width": var.width,
+                }))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Scopes and signals under `node` as lines indented by depth
+fn cli_list_text(
+    node: &trees::Node<WaveTreeNode>,
+    depth: usize,
+    out: &mut dyn Write,
+) -> Result<()> {
+    let indent = "  ".repeat(depth);
+    for child in node.iter() {
+        match child.data() {
+            WaveTreeNode::WaveScope(scope) => {
+                writeln!(out, "{indent}{} {}", scope.name, scope.typ)?;
+                cli_list_text(child, depth + 1, out)?;
+            }
+            WaveTreeNode::WaveVar(var) => writeln!(
+                out,
+                "{indent}{} {} {}",
+                var.name,
+                var.type_name(),
+                var.width
+            )?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Paint `view` or signals selected by `selection` of wave file `input` to image `output`
+fn cli_render(
+    input: &str,
+    output: &str,
+    view: Option<&str>,
+    image: WaveImage,
+    selection: &RvcdSelection,
+) -> Result<()> {
+    let mut filter = selection.filter()?;
+    // only signal paths are used before loading, times are read again in loaded timescale
+    if let Some(path) = view {
+        filter.patterns = WaveSession::read(path, Default::default())?
+            .filter()
+            .patterns;
+    }
+    let (wave, _) = cli_load(input, &filter)?;
+    let info = &wave.info;
+    let session = view
+        .map(|path| WaveSession::read(path, info.timescale.1))
+        .transpose()?;
+    let mut view = WaveView::default();
+    match session {
+        Some(session) => {
+            for path in session.apply(&mut view, info)? {
+                warn!("signal {} of view not found in {}", path, input);
+            }
+        }
+        None => {
+            let selected = filter.select(info)?;
+            let mut ids = info
+                .code_signal_info
+                .keys()
+                .copied()
+                .filter(|id| selected.as_ref().map(|s| s.contains(id)).unwrap_or(true))
+                .collect::<Vec<_>>();
+            ids.sort();
+            view.signals = ids
+                .into_iter()
+                .map(|id| SignalView::from_id(id, info))
+                .collect();
+        }
+    }
+    if let Some(window) = filter.window(&info.timescale) {
+        let (start, end) = window.range(info.range);
+        view.range = (start as f64, end as f64);
+    }
+    let mut writer = cli_output(output)?;
+    image.write(&view, &wave, &mut writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Run `command` and print its output to `out`
+pub fn run_to(command: RvcdCommand, out: &mut dyn Write) -> Result<()> {
+    match command {
+        RvcdCommand::Export {
+            input,
+            output,
+            selection,
+        } => {
+            let filter = selection.filter()?;
+            let (wave, _) = cli_load(&input, &filter)?;
+            match output.as_str() {
+                "-" => vcd_write(&wave, &filter, out)?,
+                output => {
+                    let mut writer = cli_output(output)?;
+                    vcd_write(&wave, &filter, &mut writer)?;
+                    writer.flush()?;
+                }
+            }
+        }
+        RvcdCommand::Render {
+            input,
+            output,
+            view,
+            width,
+            dark,
+            selection,
+        } => {
+            let format = VectorFormat::from_path(&output)
+                .ok_or_else(|| anyhow!("unknown image format of {output}, expect svg or pdf"))?;
+            let image = WaveImage {
+                format,
+                width,
+                dark,
+            };
+            cli_render(&input, &output, view.as_deref(), image, &selection)?;
+        }
+        RvcdCommand::Info { input, format } => {
+            let (wave, name) = cli_load(&input, &WaveFilter::header_only())?;
+            cli_info(&wave, name, format.json, out)?;
+        }
+        RvcdCommand::List { input, format } => {
+            let (wave, _) = cli_load(&input, &WaveFilter::header_only())?;
+            match format.json {
+                true => writeln!(
+                    out,
+                    "{}",
+                    Value::Array(cli_list_json(wave.info.tree.root(), &[]))
+                )?,
+                false => cli_list_text(wave.info.tree.root(), 0, out)?,
+            }
+        }
+        RvcdCommand::Value {
+            input,
+            path,
+            time,
+            format,
+        } => {
+            let (wave, _) = cli_load(&input, &cli_filter_signal(&path, "", &time)?)?;
+            let id = cli_signal(&wave, &path)?;
+            let pos = WaveTime::parse(&time)?.to_pos(&wave.info.timescale);
+            let value = wave
+                .find_value(id, pos)
+                .map(|item| cli_value(&wave, id, &item.value, format.radix.clone()));
+            match format.json {
+                true => writeln!(
+                    out,
+                    "{}",
+                    json!({"path": path, "time": pos, "value": value})
+                )?,
+                false => writeln!(out, "{}", value.unwrap_or_else(|| "-".to_string()))?,
+            }
+        }
+        RvcdCommand::Changes {
+            input,
+            path,
+            from,
+            to,
+            format,
+        } => {
+            let filter = cli_filter_signal(
+                &path,
+                from.as_deref().unwrap_or_default(),
+                to.as_deref().unwrap_or_default(),
+            )?;
+            let (wave, _) = cli_load(&input, &filter)?;
+            let id = cli_signal(&wave, &path)?;
+            let changes = wave
+                .data
+                .get(&id)
+                .map(|column| {
+                    column
+                        .iter()
+                        .map(|item| {
+                            let value = cli_value(&wave, id, &item.value, format.radix.clone());
+                            (item.timestamp, value)
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default();
+            match format.json {
+                true => writeln!(
+                    out,
+                    "{}",
+                    Value::Array(
+                        changes
+                            .into_iter()
+                            .map(|(time, value)| json!({"time": time, "value": value}))
+                            .collect()
+                    )
+                )?,
+                false => {
+                    for (time, value) in changes {
+                        writeln!(out, "#{time} {value}")?;
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Writer of `path`, `-` for stdout
+fn cli_output(path: &str) -> Result<Box<dyn Write>> {
+    Ok(match path {
+        "-" => Box::new(BufWriter::new(std::io::stdout().lock())),
+        path => Box::new(BufWriter::new(
+            std::fs::File::create(path).with_context(|| format!("cannot create {path}"))?,
+        )),
+    })
+}
+
+/// Run `command` and print its output to stdout
+pub fn run(command: RvcdCommand) -> Result<()> {
+    let mut out = cli_output("-")?;
+    run_to(command, &mut out)?;
+    out.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cli::{run_to, RvcdCommand, RvcdFormat, RvcdSelection};
+    use crate::radix::Radix;
+    use crate::view::session::{SessionSignal, WaveSession};
+    use crate::view::signal::SignalView;
+    use crate::view::WaveView;
+    use crate::wave::vcd_parser::Vcd;
+    use crate::wave::WaveLoader;
+    use anyhow::Result;
+
+    fn output(command: RvcdCommand) -> Result<String> {
+        let mut out = vec![];
+        run_to(command, &mut out)?;
+        Ok(String::from_utf8(out)?)
+    }
+
+    fn format(json: bool, radix: Radix) -> RvcdFormat {
+        RvcdFormat { json, radix }
+    }
+
+    #[test]
+    fn test_cli_commands() -> Result<()> {
+        let input = "data/counter.vcd".to_string();
+        let info = output(RvcdCommand::Info {
+            input: input.clone(),
+            format: format(true, Radix::Hex),
+        })?;
+        let info: serde_json::Value = serde_json::from_str(&info)?;
+        assert_eq!(info["timescale"], "1ns");
+        assert_eq!(info["range"], serde_json::json!([0, 45]));
+        assert_eq!(info["signals"], 4);
+        let list = output(RvcdCommand::List {
+            input: input.clone(),
+            format: format(false, Radix::Hex),
+        })?;
+        assert!(list.starts_with("top module\n  counter module\n    cnt reg 8\n"));
+        let value = output(RvcdCommand::Value {
+            input: input.clone(),
+            path: "top.counter.cnt".to_string(),
+            time: "27ns".to_string(),
+            format: format(false, Radix::Bin),
+        })?;
+        assert_eq!(value, "10\n");
+        let changes = output(RvcdCommand::Changes {
+            input: input.clone(),
+            path: "top.counter.cnt".to_string(),
+            from: Some("11".to_string()),
+            to: Some("25".to_string()),
+            format: format(false, Radix::Dec),
+        })?;
+        assert_eq!(changes, "#11 0\n#15 1\n#25 2\n");
+        assert!(output(RvcdCommand::Value {
+            input,
+            path: "top.counter".to_string(),
+            time: "0".to_string(),
+            format: format(false, Radix::Hex),
+        })
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_cli_render() -> Result<()> {
+        let input = "data/counter.vcd".to_string();
+        let dir = std::env::temp_dir();
+        let svg = dir
+            .join("rvcd_test_render.svg")
+            .to_string_lossy()
+            .to_string();
+        output(RvcdCommand::Render {
+            input: input.clone(),
+            output: svg.clone(),
+            view: None,
+            width: 800.0,
+            dark: false,
+            selection: RvcdSelection {
+                filter: vec!["top.counter.*".to_string()],
+                start: Some("10".to_string()),
+                ..Default::default()
+            },
+        })?;
+        let text = std::fs::read_to_string(&svg)?;
+        assert!(text.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"800\""));
+        assert!(text.contains(">cnt[7:0]</text>"));
+        assert!(!text.contains(">clk</text>"));
+        // signals of saved view are painted
+        let wave = Vcd.load(&mut std::fs::File::open(&input)?, &|_, _| {}, None)?;
+        let view = WaveView {
+            signals: vec![SignalView::from_id(0, &wave.info)],
+            ..Default::default()
+        };
+        let mut session = WaveSession::from_view(&view, &wave.info, &input);
+        // signals missing in wave are skipped
+        session.signals.push(SessionSignal {
+            path: "top.missing".to_string(),
+            ..Default::default()
+        });
+        let view_path = dir.join("rvcd_test_render.view.json");
+        std::fs::write(&view_path, session.to_json()?)?;
+        let pdf = dir
+            .join("rvcd_test_render.pdf")
+            .to_string_lossy()
+            .to_string();
+        output(RvcdCommand::Render {
+            input: input.clone(),
+            output: pdf.clone(),
+            view: Some(view_path.to_string_lossy().to_string()),
+            width: 800.0,
+            dark: true,
+            selection: Default::default(),
+        })?;
+        let data = std::fs::read(&pdf)?;
+        let text = String::from_utf8_lossy(&data);
+        assert!(text.starts_with("%PDF-"));
+        assert!(text.contains("(c) Tj") && text.contains("(k) Tj"));
+        // gtkwave save files are imported
+        output(RvcdCommand::Render {
+            input: input.clone(),
+            output: svg.clone(),
+            view: Some("data/counter.gtkw".to_string()),
+            width: 800.0,
+            dark: false,
+            selection: Default::default(),
+        })?;
+        let text = std::fs::read_to_string(&svg)?;
+        assert!(text.contains(">en</text>") && text.contains(">rst</text>"));
+        assert!(output(RvcdCommand::Render {
+            input,
+            output: "image.png".to_string(),
+            view: None,
+            width: 800.0,
+            dark: false,
+            selection: Default::default(),
+        })
+        .is_err());
+        Ok(())
+    }
+}
@@ -1,6 +1,8 @@
 #![warn(clippy::all, rust_2018_idioms)]
 
 pub mod app;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod cli;
 pub mod files;
 pub mod frame_history;
 pub mod message;
@@ -10,6 +12,7 @@ pub mod rvcd;
 pub mod manager;
 pub mod service;
 pub mod size;
+pub mod stream;
 pub mod tree_view;
 pub mod utils;
 pub mod view;
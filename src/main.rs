@@ -5,6 +5,8 @@
 use anyhow::Result;
 #[cfg(not(target_arch = "wasm32"))]
 use clap::Parser;
+#[cfg(not(target_arch = "wasm32"))]
+use rvcd::cli::{RvcdCommand, RvcdSelection};
 use rvcd::app::RvcdApp;
 #[cfg(not(target_arch = "wasm32"))]
 use rvcd::manager::{RvcdRpcMessage, MANAGER_PORT};
@@ -22,15 +24,24 @@ static GLOBAL: jemallocator::Jemalloc = jemallocator::Jemalloc;
 #[cfg(not(target_arch = "wasm32"))]
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
+#[command(args_conflicts_with_subcommands = true)]
 struct RvcdArgs {
-    /// Files to open
+    #[command(subcommand)]
+    command: Option<RvcdCommand>,
+    /// Files to open, `-` to stream vcd from stdin
     file: Vec<String>,
+    /// Stream vcd from `-` (stdin), `tcp:[ADDR:]PORT` or a named pipe
+    #[arg(long)]
+    stream: Option<String>,
     /// Input sources
     #[arg(short)]
     input: Vec<String>,
     /// Default source path
     #[arg(short, long, default_value = "")]
     src: String,
+    /// Signals and time window to load
+    #[command(flatten)]
+    selection: RvcdSelection,
     /// Manager port
     #[arg(short, long, default_value_t = MANAGER_PORT)]
     port: u16,
@@ -52,10 +63,15 @@ fn main() -> Result<()> {
     use tonic::transport::Server;
     use tracing::error;
 
+    let args = RvcdArgs::parse();
+    if let Some(command) = args.command {
+        // keep stdout for command output
+        tracing_subscriber::fmt().with_writer(std::io::stderr).init();
+        return rvcd::cli::run(command);
+    }
+
     let rt = Builder::new_multi_thread().enable_all().build().unwrap();
     rt.block_on(async {
-        let args = RvcdArgs::parse();
-
         // Log to stdout (if you run with `RUST_LOG=debug`).
         tracing_subscriber::fmt::init();
 
@@ -79,6 +95,10 @@ fn main() -> Result<()> {
         let (exit_tx, exit_rx) = mpsc::channel();
         let exit_tx2 = exit_tx.clone();
         let src = args.src.clone();
+        let load_filter = args.selection.filter().unwrap_or_else(|e| {
+            error!("invalid load filter: {:#}", e);
+            Default::default()
+        });
         let gui = async move {
             eframe::run_native(
                 "Rvcd",
@@ -91,6 +111,7 @@ fn main() -> Result<()> {
                         manager_tx,
                         exit_tx2,
                         if src.is_empty() { None } else { Some(src) },
+                        load_filter,
                     ))
                 }),
             )
@@ -128,7 +149,16 @@ fn main() -> Result<()> {
             }
         };
         for file in args.file {
-            rpc_tx3.send(RvcdRpcMessage::OpenWaveFile(file)).unwrap();
+            if file == "-" {
+                rpc_tx3.send(RvcdRpcMessage::OpenWaveStream(file)).unwrap();
+            } else {
+                rpc_tx3.send(RvcdRpcMessage::OpenWaveFile(file)).unwrap();
+            }
+        }
+        if let Some(source) = args.stream {
+            rpc_tx3
+                .send(RvcdRpcMessage::OpenWaveStream(source))
+                .unwrap();
         }
         for source in args.input {
             rpc_tx3
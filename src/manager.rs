@@ -22,6 +22,8 @@ pub const UNIX_FB_PATH: &str = "/tmp/rvcd.socket";
 pub enum RvcdRpcMessage {
     GotoPath(RvcdSignalPath),
     OpenWaveFile(String),
+    /// Stream source, see [crate::stream::StreamSource]
+    OpenWaveStream(String),
     OpenSourceFile(String),
     OpenSourceDir(String),
     RequestFrame,
@@ -1,5 +1,9 @@
 use crate::verilog::{VerilogGotoSource, VerilogSource};
-use crate::wave::Wave;
+use crate::view::export::WaveImage;
+use crate::wave::filter::WaveFilter;
+use crate::wave::loader::WaveLoaders;
+use crate::wave::table_writer::WaveTable;
+use crate::wave::{Wave, WaveTail};
 use egui_toast::Toast;
 use rfd::FileHandle;
 use std::fmt::{Debug, Formatter};
@@ -11,14 +15,41 @@ pub enum RvcdMsg {
     FileLoadStart(String),
     FileLoadCancel,
     FileDrag(FileHandle),
-    FileOpenData(Arc<[u8]>),
+    /// File name and bytes of a file opened without path, e.g. dropped in browser
+    FileOpenData(String, Arc<[u8]>),
+    StreamOpen(String),
     LoadingProgress(f32, usize),
     ParsingProgress(f32, u64),
     FileOpenFailed(String),
     Reload,
     UpdateWave(Wave),
+    SetFollow(bool),
+    /// Whether to load and save parsed wave cache
+    SetWaveCache(bool),
+    /// Signals to keep value changes when loading files
+    SetLoadFilter(WaveFilter),
+    /// Loaders of wave formats, replaced when loader options change
+    SetLoaders(Arc<WaveLoaders>),
+    /// Write signals and time window of loaded wave selected by filter to vcd file path
+    ExportVcd(String, WaveFilter),
+    /// Write value change table of loaded wave to csv or json file path
+    ExportTable(String, WaveTable),
+    /// Paint wave panel to svg or pdf file path
+    ExportImage(String, WaveImage),
+    /// Save signals, cursors and settings of wave view to session file path
+    SaveView(String),
+    /// Replace wave view by session or gtkwave save file path, signals are matched by paths
+    LoadView(String),
+    /// Load value changes of signals not selected by load filter
+    LoadSignals(Vec<u64>),
+    /// Signals loaded on demand from file path, to merge into loaded wave
+    MergeWave(String, Wave),
+    AppendWave(WaveTail),
     Notification(Toast),
-    ServiceDataReady(Vec<u8>),
+    /// File name and bytes read by service, to parse in service loop
+    ServiceDataReady(String, Vec<u8>),
+    /// Wave parsed in loading thread, with bytes consumed if it is a vcd file
+    ServiceWaveReady(Option<(Wave, Option<u64>)>),
     StopService,
     UpdateSourceDir(String),
     UpdateSource(String),
@@ -37,8 +68,41 @@ impl Debug for RvcdMsg {
             RvcdMsg::FileOpenFailed(path) => write!(f, "RvcdMsg: FileOpenFailed {path}"),
             RvcdMsg::Reload => write!(f, "RvcdMsg: Reload"),
             RvcdMsg::UpdateWave(_) => write!(f, "RvcdMsg: UpdateWave"),
-            RvcdMsg::FileOpenData(v) => write!(f, "RvcdMsg: FileOpenData({} bytes)", v.len()),
+            RvcdMsg::SetFollow(follow) => write!(f, "RvcdMsg: SetFollow({follow})"),
+            RvcdMsg::SetWaveCache(cache) => write!(f, "RvcdMsg: SetWaveCache({cache})"),
+            RvcdMsg::SetLoadFilter(filter) => write!(f, "RvcdMsg: SetLoadFilter({filter:?})"),
+            RvcdMsg::SetLoaders(loaders) => {
+                let names = loaders.iter().map(|l| l.name()).collect::<Vec<_>>();
+                write!(f, "RvcdMsg: SetLoaders({names:?})")
+            }
+            RvcdMsg::ExportVcd(path, filter) => {
+                write!(f, "RvcdMsg: ExportVcd({path}, {filter:?})")
+            }
+            RvcdMsg::ExportTable(path, table) => {
+                write!(f, "RvcdMsg: ExportTable({path}, {:?})", table.format)
+            }
+            RvcdMsg::ExportImage(path, image) => {
+                write!(f, "RvcdMsg: ExportImage({path}, {:?})", image.format)
+            }
+            RvcdMsg::SaveView(path) => write!(f, "RvcdMsg: SaveView({path})"),
+            RvcdMsg::LoadView(path) => write!(f, "RvcdMsg: LoadView({path})"),
+            RvcdMsg::LoadSignals(ids) => write!(f, "RvcdMsg: LoadSignals({})", ids.len()),
+            RvcdMsg::MergeWave(path, wave) => {
+                write!(f, "RvcdMsg: MergeWave({path}, {} signals)", wave.data.len())
+            }
+            RvcdMsg::AppendWave(tail) => {
+                write!(
+                    f,
+                    "RvcdMsg: AppendWave({} signals, {:?})",
+                    tail.data.len(),
+                    tail.range
+                )
+            }
+            RvcdMsg::FileOpenData(name, v) => {
+                write!(f, "RvcdMsg: FileOpenData({name}, {} bytes)", v.len())
+            }
             RvcdMsg::FileDrag(_) => write!(f, "RvcdMsg: FileDrag"),
+            RvcdMsg::StreamOpen(source) => write!(f, "RvcdMsg: StreamOpen({source})"),
             RvcdMsg::LoadingProgress(p, sz) => {
                 write!(f, "RvcdMsg: LoadingProgress({}%, {} bytes)", p * 100.0, sz)
             }
@@ -47,8 +111,11 @@ impl Debug for RvcdMsg {
             }
             RvcdMsg::FileLoadStart(filepath) => write!(f, "RvcdMsg: FileLoadStart({filepath})"),
             RvcdMsg::FileLoadCancel => write!(f, "RvcdMsg: FileLoadCancel"),
-            RvcdMsg::ServiceDataReady(v) => {
-                write!(f, "RcdMsg: ServiceDataReady ({} bytes)", v.len())
+            RvcdMsg::ServiceDataReady(name, v) => {
+                write!(f, "RcdMsg: ServiceDataReady ({name}, {} bytes)", v.len())
+            }
+            RvcdMsg::ServiceWaveReady(loaded) => {
+                write!(f, "RvcdMsg: ServiceWaveReady(loaded: {})", loaded.is_some())
             }
             RvcdMsg::StopService => write!(f, "RvcdMsg: StopService"),
             RvcdMsg::UpdateSources(s) => write!(f, "RvcdMsg: UpdateSources({})", s.len()),
@@ -269,7 +269,7 @@ mod test {
             .unwrap();
             let item_value_v = match &item.value {
                 WaveDataValue::Comp(v) => v.as_slice(),
-                WaveDataValue::Raw(_) => &[],
+                _ => &[],
             };
             let item_value = BigUint::from_bytes_le(item_value_v);
             println!(
@@ -15,8 +15,23 @@ use crate::tree_view::{TreeAction, TreeView};
 use crate::utils::{execute, file_basename};
 #[cfg(not(target_arch = "wasm32"))]
 use crate::verilog::{parse_verilog_file, VerilogGotoSource, VerilogViewSource};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::view::cursor::WaveCursor;
 use crate::view::signal::SignalView;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::view::signal::SignalViewMode;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::view::{export::WaveImage, session::WaveSession, vector::VectorFormat};
 use crate::view::{WaveView, SIGNAL_LEAF_HEIGHT_DEFAULT};
+use crate::wave::csv_parser::{Csv, CsvOptions};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::wave::filter::WaveFilter;
+use crate::wave::filter::WaveTime;
+use crate::wave::loader::{WaveCompression, WaveLoaders};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::wave::table_writer::{TableColumn, TableFormat, TableRows, WaveTable};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::wave::vcd_writer::vcd_write;
 use crate::wave::{Wave, WaveSignalInfo, WaveTreeNode};
 use eframe::emath::Align;
 use egui::WidgetText;
@@ -30,6 +45,8 @@ use egui_toast::{Toast, ToastKind, ToastOptions, Toasts};
 use num_traits::Float;
 use regex::Regex;
 use rfd::FileHandle;
+#[allow(unused_imports)]
+use std::collections::HashSet;
 use std::fmt::{Debug, Display, Formatter};
 #[allow(unused_imports)]
 use std::path::PathBuf;
@@ -97,6 +114,56 @@ pub struct Rvcd {
 
     #[cfg(not(target_arch = "wasm32"))]
     pub source_dir: String,
+    /// Load parsed wave from cache when file not changed
+    #[cfg(not(target_arch = "wasm32"))]
+    pub wave_cache: bool,
+    /// Scope or signal patterns to load, empty to load all signals
+    #[cfg(not(target_arch = "wasm32"))]
+    pub load_filter: String,
+    /// Start and end of time window to load, empty to load from file start or to file end
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    pub load_window: (String, String),
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    pub load_window_open: bool,
+    /// Scope or signal patterns to export, empty to export displayed signals
+    #[cfg(not(target_arch = "wasm32"))]
+    pub export_filter: String,
+    /// Start and end of time window to export, empty to export from wave start or to wave end
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    pub export_window: (String, String),
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    pub export_open: bool,
+    /// File format of exported value change table
+    #[cfg(not(target_arch = "wasm32"))]
+    pub export_table_format: TableFormat,
+    /// Rows of exported value change table
+    #[cfg(not(target_arch = "wasm32"))]
+    pub export_table_rows: TableRows,
+    /// Ids of cursors bounding exported table, `None` for wave start or end
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    pub export_table_cursors: (Option<i32>, Option<i32>),
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    pub export_table_open: bool,
+    /// Format, width and theme of exported wave panel image
+    #[cfg(not(target_arch = "wasm32"))]
+    pub export_image: WaveImage,
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    pub export_image_open: bool,
+    /// How to read csv waves
+    pub csv_options: CsvOptions,
+    #[serde(skip)]
+    pub csv_options_open: bool,
+    /// Signals not selected by load filter and requested to load
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    pub signals_loading: HashSet<u64>,
     #[cfg(not(target_arch = "wasm32"))]
     #[serde(skip)]
     pub sources_update_started: bool,
@@ -159,6 +226,36 @@ impl Default for Rvcd {
             #[cfg(not(target_arch = "wasm32"))]
             source_dir: "".to_string(),
             #[cfg(not(target_arch = "wasm32"))]
+            wave_cache: true,
+            #[cfg(not(target_arch = "wasm32"))]
+            load_filter: "".to_string(),
+            #[cfg(not(target_arch = "wasm32"))]
+            load_window: Default::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            load_window_open: false,
+            #[cfg(not(target_arch = "wasm32"))]
+            export_filter: "".to_string(),
+            #[cfg(not(target_arch = "wasm32"))]
+            export_window: Default::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            export_open: false,
+            #[cfg(not(target_arch = "wasm32"))]
+            export_table_format: Default::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            export_table_rows: Default::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            export_table_cursors: (None, None),
+            #[cfg(not(target_arch = "wasm32"))]
+            export_table_open: false,
+            #[cfg(not(target_arch = "wasm32"))]
+            export_image: Default::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            export_image_open: false,
+            csv_options: Default::default(),
+            csv_options_open: false,
+            #[cfg(not(target_arch = "wasm32"))]
+            signals_loading: HashSet::new(),
+            #[cfg(not(target_arch = "wasm32"))]
             sources_update_started: false,
             #[cfg(not(target_arch = "wasm32"))]
             sources_updated: false,
@@ -194,6 +291,9 @@ impl Rvcd {
             tx: channel_resp_tx.clone(),
             rx: channel_req_rx,
         });
+        channel_req_tx
+            .send(RvcdMsg::SetLoaders(Arc::new(self.loaders())))
+            .unwrap();
         // auto open file
         // let filepath = "data/cpu_ila_commit.vcd";
         #[cfg(not(target_arch = "wasm32"))]
@@ -203,6 +303,13 @@ impl Rvcd {
                 "last file: {}; last source dir: {}",
                 filepath, self.source_dir
             );
+            channel_req_tx
+                .send(RvcdMsg::SetWaveCache(self.wave_cache))
+                .unwrap();
+            match self.load_filter() {
+                Ok(filter) => channel_req_tx.send(RvcdMsg::SetLoadFilter(filter)).unwrap(),
+                Err(e) => warn!("invalid load filter {}: {}", self.load_filter, e),
+            }
             if !filepath.is_empty() {
                 channel_req_tx
                     .send(RvcdMsg::FileOpen(rfd::FileHandle::from(
@@ -324,6 +431,11 @@ impl Rvcd {
             }
         }
 
+        #[cfg(not(target_arch = "wasm32"))]
+        if self.state == State::Working {
+            self.load_unloaded_signals();
+        }
+
         let ctx = ui.ctx();
         if self.state == State::Loading {
             egui::Window::new(t!("loading.title"))
@@ -340,25 +452,24 @@ impl Rvcd {
                             }
                         });
                     };
-                    if let RvcdMsg::LoadingProgress(..) = self.last_progress_msg {
-                        ui.label(t!(
-                            "loading.load_progress",
-                            percent = format!("{:.1}", self.load_progress.0 * 100.0).as_str(),
-                            bytes = FileSizeUnit::from_bytes(self.load_progress.1)
-                                .to_string()
-                                .as_str()
-                        ));
-                        ProgressBar::new(self.load_progress.0).ui(ui);
-                        handle_cancel(ui);
-                    } else {
+                    // files are parsed while reading, so show both progress
+                    ui.label(t!(
+                        "loading.load_progress",
+                        percent = format!("{:.1}", self.load_progress.0 * 100.0).as_str(),
+                        bytes = FileSizeUnit::from_bytes(self.load_progress.1)
+                            .to_string()
+                            .as_str()
+                    ));
+                    ProgressBar::new(self.load_progress.0).ui(ui);
+                    if self.parse_progress.0 > 0.0 {
                         ui.label(t!(
                             "loading.parse_progress",
                             percent = format!("{:.1}", self.parse_progress.0 * 100.0).as_str(),
-                            pos = self.load_progress.1.to_string().as_str()
+                            pos = self.parse_progress.1.to_string().as_str()
                         ));
                         ProgressBar::new(self.parse_progress.0).ui(ui);
-                        // handle_cancel(ui);
                     }
+                    handle_cancel(ui);
                 });
             ctx.request_repaint();
         }
@@ -461,6 +572,26 @@ impl Rvcd {
             }
         }
 
+        #[cfg(not(target_arch = "wasm32"))]
+        if self.load_window_open {
+            self.load_window_dialog(ctx);
+        }
+        if self.csv_options_open {
+            self.csv_options_dialog(ctx);
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if self.export_open {
+            self.export_vcd_dialog(ctx);
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if self.export_table_open {
+            self.export_table_dialog(ctx);
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if self.export_image_open {
+            self.export_image_dialog(ctx);
+        }
+
         self.toasts.show(ctx);
         // .show_with_anchor(ctx, ctx.available_rect().max - vec2(20.0, 10.0));
 
@@ -477,6 +608,547 @@ impl Rvcd {
             }
         }
     }
+    /// Load filter from signal patterns and time window
+    #[cfg(not(target_arch = "wasm32"))]
+    fn load_filter(&self) -> anyhow::Result<WaveFilter> {
+        let mut filter = WaveFilter::parse(&self.load_filter)?;
+        filter.set_window(&self.load_window.0, &self.load_window.1)?;
+        Ok(filter)
+    }
+    /// Take signal patterns and time window of `filter` given from command line,
+    /// parts not given keep current settings
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn set_load_filter(&mut self, filter: &WaveFilter) {
+        if !filter.patterns.is_empty() {
+            self.load_filter = filter.patterns.join(", ");
+        }
+        if filter.start.is_some() || filter.end.is_some() {
+            let text = |t: &Option<WaveTime>| t.map(|t| t.to_string()).unwrap_or_default();
+            self.load_window = (text(&filter.start), text(&filter.end));
+        }
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    fn load_window_dialog(&mut self, ctx: &egui::Context) {
+        let mut open = self.load_window_open;
+        egui::Window::new(t!("load_window.title"))
+            .id(Id::from(format!("load_window_rvcd_{}", self.id)))
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(t!("load_window.hint"));
+                egui::Grid::new(format!("load_window_grid_{}", self.id)).show(ui, |ui| {
+                    let (start, end) = &mut self.load_window;
+                    for (label, text) in [
+                        (t!("load_window.start"), start),
+                        (t!("load_window.end"), end),
+                    ] {
+                        ui.label(label);
+                        ui.text_edit_singleline(text);
+                        if !text.trim().is_empty() && WaveTime::parse(text).is_err() {
+                            ui.label(RichText::new("❌").color(Color32::RED));
+                        }
+                        ui.end_row();
+                    }
+                });
+                let (start, end) = &self.load_window;
+                if let Err(e) = WaveFilter::default().set_window(start, end) {
+                    ui.label(RichText::new(format!("❌ {}", e)).color(Color32::RED));
+                }
+                ui.horizontal(|ui| {
+                    let valid = self.load_filter().is_ok();
+                    let apply = ui
+                        .add_enabled(valid, egui::Button::new(t!("load_window.apply")))
+                        .clicked();
+                    let clear = ui.button(t!("load_window.clear")).clicked();
+                    if clear {
+                        self.load_window = Default::default();
+                    }
+                    if let (true, Ok(filter)) = (apply || clear, self.load_filter()) {
+                        if let Some(channel) = &self.channel {
+                            channel.tx.send(RvcdMsg::SetLoadFilter(filter)).unwrap();
+                        }
+                        if !self.filepath.is_empty() {
+                            self.reload();
+                        }
+                    }
+                });
+            });
+        self.load_window_open = open;
+    }
+    /// Signals and time window to export, displayed signals if no pattern given
+    #[cfg(not(target_arch = "wasm32"))]
+    fn export_selection(&self) -> anyhow::Result<WaveFilter> {
+        let mut filter = WaveFilter::parse(&self.export_filter)?;
+        if filter.patterns.is_empty() {
+            filter.ids = self.view.signals.iter().map(|s| s.s.id).collect();
+        }
+        filter.set_window(&self.export_window.0, &self.export_window.1)?;
+        Ok(filter)
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    fn export_vcd_dialog(&mut self, ctx: &egui::Context) {
+        let mut open = self.export_open;
+        egui::Window::new(t!("export_vcd.title"))
+            .id(Id::from(format!("export_vcd_rvcd_{}", self.id)))
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(t!("export_vcd.hint"));
+                egui::Grid::new(format!("export_vcd_grid_{}", self.id)).show(ui, |ui| {
+                    ui.label(t!("export_vcd.signals"));
+                    ui.text_edit_singleline(&mut self.export_filter)
+                        .on_hover_text(t!("export_vcd.signals_hover"));
+                    if WaveFilter::parse(&self.export_filter).is_err() {
+                        ui.label(RichText::new("❌").color(Color32::RED));
+                    }
+                    ui.end_row();
+                    let (start, end) = &mut self.export_window;
+                    for (label, text) in
+                        [(t!("export_vcd.start"), start), (t!("export_vcd.end"), end)]
+                    {
+                        ui.label(label);
+                        ui.text_edit_singleline(text);
+                        if !text.trim().is_empty() && WaveTime::parse(text).is_err() {
+                            ui.label(RichText::new("❌").color(Color32::RED));
+                        }
+                        ui.end_row();
+                    }
+                });
+                ui.horizontal(|ui| {
+                    if ui.button(t!("export_vcd.view_range")).clicked() {
+                        let (start, end) = self.view.range;
+                        self.export_window = (
+                            (start.max(0.0) as u64).to_string(),
+                            (end.max(0.0) as u64).to_string(),
+                        );
+                    }
+                    // nothing selected would export all signals
+                    let filter = self
+                        .export_selection()
+                        .ok()
+                        .filter(|filter| !filter.patterns.is_empty() || !filter.ids.is_empty());
+                    let export = ui
+                        .add_enabled(
+                            filter.is_some() && self.wave.is_some(),
+                            egui::Button::new(t!("export_vcd.export")),
+                        )
+                        .clicked();
+                    if let (true, Some(filter)) = (export, filter) {
+                        let task = rfd::AsyncFileDialog::new()
+                            .add_filter(t!("menu.format_file", format = "VCD"), &["vcd"])
+                            .set_file_name("export.vcd")
+                            .save_file();
+                        let loop_self = self.loop_self.clone();
+                        execute(async move {
+                            if let (Some(file), Some(loop_self)) = (task.await, loop_self) {
+                                let path = file.path().to_string_lossy().to_string();
+                                loop_self.send(RvcdMsg::ExportVcd(path, filter)).ok();
+                            }
+                        });
+                    }
+                });
+            });
+        self.export_open = open;
+    }
+    /// Valid cursors to bound exported table
+    #[cfg(not(target_arch = "wasm32"))]
+    fn export_table_cursor_list(&self) -> Vec<&WaveCursor> {
+        std::iter::once(&self.view.marker)
+            .chain(self.view.cursors.iter())
+            .filter(|cursor| cursor.valid)
+            .collect()
+    }
+    /// Table of displayed signals in their radix between selected cursors
+    #[cfg(not(target_arch = "wasm32"))]
+    fn export_table(&self) -> Option<WaveTable> {
+        let info = &self.wave.as_ref()?.info;
+        let cursors = self.export_table_cursor_list();
+        let pos = |id: Option<i32>, default: u64| {
+            id.and_then(|id| cursors.iter().find(|cursor| cursor.id == id))
+                .map(|cursor| cursor.pos)
+                .unwrap_or(default)
+        };
+        let start = pos(self.export_table_cursors.0, info.range.0);
+        let end = pos(self.export_table_cursors.1, info.range.1);
+        let columns = self
+            .view
+            .signals
+            .iter()
+            .map(|signal| TableColumn {
+                id: signal.s.id,
+                path: info
+                    .code_paths
+                    .get(&signal.s.id)
+                    .map(|path| path.join("."))
+                    .unwrap_or_else(|| signal.s.name.to_string()),
+                radix: self.view.get_radix(signal),
+                literals: signal.mode == SignalViewMode::Default,
+            })
+            .collect::<Vec<_>>();
+        Some(WaveTable {
+            columns,
+            rows: self.export_table_rows.clone(),
+            range: (start.min(end), start.max(end)),
+            format: self.export_table_format,
+        })
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    fn export_table_dialog(&mut self, ctx: &egui::Context) {
+        let mut open = self.export_table_open;
+        egui::Window::new(t!("export_table.title"))
+            .id(Id::from(format!("export_table_rvcd_{}", self.id)))
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(t!("export_table.hint"));
+                egui::Grid::new(format!("export_table_grid_{}", self.id)).show(ui, |ui| {
+                    ui.label(t!("export_table.format"));
+                    egui::ComboBox::from_id_source(format!("export_table_format_{}", self.id))
+                        .selected_text(self.export_table_format.to_string())
+                        .show_ui(ui, |ui| {
+                            for format in
+                                [TableFormat::Csv, TableFormat::Json, TableFormat::WaveDrom]
+                            {
+                                let text = format.to_string();
+                                ui.selectable_value(&mut self.export_table_format, format, text);
+                            }
+                        });
+                    ui.end_row();
+                    ui.label(t!("export_table.rows"));
+                    let clocks = self
+                        .view
+                        .signals
+                        .iter()
+                        .filter(|signal| signal.s.width <= 1)
+                        .map(|signal| (signal.s.id, signal.s.to_string()))
+                        .collect::<Vec<_>>();
+                    let rows = &mut self.export_table_rows;
+                    let rows_text = |rows: &TableRows| match rows {
+                        TableRows::Changes => t!("export_table.changes"),
+                        TableRows::Step(_) => t!("export_table.step"),
+                        TableRows::Edge(_) => t!("export_table.edge"),
+                    };
+                    egui::ComboBox::from_id_source(format!("export_table_rows_{}", self.id))
+                        .selected_text(rows_text(rows))
+                        .show_ui(ui, |ui| {
+                            let clock = clocks.first().map(|(id, _)| *id).unwrap_or_default();
+                            for option in [
+                                TableRows::Changes,
+                                TableRows::Step(1),
+                                TableRows::Edge(clock),
+                            ] {
+                                let selected = std::mem::discriminant(&*rows)
+                                    == std::mem::discriminant(&option);
+                                let text = rows_text(&option);
+                                if ui.selectable_label(selected, text).clicked() && !selected {
+                                    *rows = option;
+                                }
+                            }
+                        });
+                    match rows {
+                        TableRows::Changes => {}
+                        TableRows::Step(step) => {
+                            ui.add(egui::DragValue::new(step).clamp_range(1..=u64::MAX));
+                        }
+                        TableRows::Edge(clock) => {
+                            let name = clocks
+                                .iter()
+                                .find(|(id, _)| *id == *clock)
+                                .map(|(_, name)| name.to_string())
+                                .unwrap_or_default();
+                            egui::ComboBox::from_id_source(format!(
+                                "export_table_clock_{}",
+                                self.id
+                            ))
+                            .selected_text(name)
+                            .show_ui(ui, |ui| {
+                                for (id, name) in &clocks {
+                                    ui.selectable_value(clock, *id, name.as_str());
+                                }
+                            });
+                        }
+                    }
+                    ui.end_row();
+                    let cursors = self
+                        .export_table_cursor_list()
+                        .into_iter()
+                        .map(|cursor| (cursor.id, cursor.name.to_string()))
+                        .collect::<Vec<_>>();
+                    let (from, to) = &mut self.export_table_cursors;
+                    for (label, bound, default, id) in [
+                        (
+                            t!("export_table.from"),
+                            from,
+                            t!("export_table.wave_start"),
+                            "from",
+                        ),
+                        (t!("export_table.to"), to, t!("export_table.wave_end"), "to"),
+                    ] {
+                        ui.label(label);
+                        let text = bound
+                            .and_then(|bound| cursors.iter().find(|(id, _)| *id == bound))
+                            .map(|(_, name)| name.to_string())
+                            .unwrap_or_else(|| default.to_string());
+                        egui::ComboBox::from_id_source(format!("export_table_{id}_{}", self.id))
+                            .selected_text(text)
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(bound, None, default);
+                                for (id, name) in &cursors {
+                                    ui.selectable_value(bound, Some(*id), name.as_str());
+                                }
+                            });
+                        ui.end_row();
+                    }
+                });
+                let valid = match self.export_table_rows {
+                    TableRows::Edge(clock) => self.view.signals.iter().any(|s| s.s.id == clock),
+                    _ => !self.view.signals.is_empty(),
+                };
+                let export = ui
+                    .add_enabled(valid, egui::Button::new(t!("export_table.export")))
+                    .clicked();
+                if let (true, Some(table)) = (export, self.export_table()) {
+                    let format = table.format;
+                    let task = rfd::AsyncFileDialog::new()
+                        .add_filter(
+                            t!("menu.format_file", format = format.to_string()),
+                            &[format.extension()],
+                        )
+                        .set_file_name(&format!("export.{}", format.extension()))
+                        .save_file();
+                    let loop_self = self.loop_self.clone();
+                    execute(async move {
+                        if let (Some(file), Some(loop_self)) = (task.await, loop_self) {
+                            let path = file.path().to_string_lossy().to_string();
+                            loop_self.send(RvcdMsg::ExportTable(path, table)).ok();
+                        }
+                    });
+                }
+            });
+        self.export_table_open = open;
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    fn export_image_dialog(&mut self, ctx: &egui::Context) {
+        let mut open = self.export_image_open;
+        egui::Window::new(t!("export_image.title"))
+            .id(Id::from(format!("export_image_rvcd_{}", self.id)))
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(t!("export_image.hint"));
+                let image = &mut self.export_image;
+                egui::Grid::new(format!("export_image_grid_{}", self.id)).show(ui, |ui| {
+                    ui.label(t!("export_image.format"));
+                    egui::ComboBox::from_id_source(format!("export_image_format_{}", self.id))
+                        .selected_text(image.format.to_string())
+                        .show_ui(ui, |ui| {
+                            for format in [VectorFormat::Svg, VectorFormat::Pdf] {
+                                let text = format.to_string();
+                                ui.selectable_value(&mut image.format, format, text);
+                            }
+                        });
+                    ui.end_row();
+                    ui.label(t!("export_image.width"));
+                    ui.add(
+                        egui::DragValue::new(&mut image.width)
+                            .clamp_range(400.0..=20000.0)
+                            .speed(10.0)
+                            .suffix(" pt"),
+                    );
+                    ui.end_row();
+                    ui.label(t!("export_image.dark"));
+                    ui.checkbox(&mut image.dark, "");
+                    ui.end_row();
+                });
+                let export = ui
+                    .add_enabled(
+                        !self.view.signals.is_empty(),
+                        egui::Button::new(t!("export_image.export")),
+                    )
+                    .clicked();
+                if export {
+                    let image = self.export_image.clone();
+                    let format = image.format;
+                    let task = rfd::AsyncFileDialog::new()
+                        .add_filter(
+                            t!("menu.format_file", format = format.to_string()),
+                            &[format.extension()],
+                        )
+                        .set_file_name(&format!("wave.{}", format.extension()))
+                        .save_file();
+                    let loop_self = self.loop_self.clone();
+                    execute(async move {
+                        if let (Some(file), Some(loop_self)) = (task.await, loop_self) {
+                            let path = file.path().to_string_lossy().to_string();
+                            loop_self.send(RvcdMsg::ExportImage(path, image)).ok();
+                        }
+                    });
+                }
+            });
+        self.export_image_open = open;
+    }
+    /// Write loaded wave to file `path` by `write`, result is shown as toast
+    #[cfg(not(target_arch = "wasm32"))]
+    fn export_file(
+        &mut self,
+        path: &str,
+        write: impl FnOnce(&Wave, &mut dyn std::io::Write) -> anyhow::Result<()>,
+    ) {
+        use std::io::Write;
+        let Some(wave) = &self.wave else {
+            return;
+        };
+        let result = std::fs::File::create(path)
+            .map_err(anyhow::Error::from)
+            .and_then(|file| {
+                let mut writer = std::io::BufWriter::new(file);
+                write(wave, &mut writer)?;
+                writer.flush()?;
+                Ok(())
+            });
+        let (kind, text) = match result {
+            Ok(_) => (ToastKind::Info, t!("msg.export_done", file = path)),
+            Err(e) => {
+                warn!("export to {} failed: {}", path, e);
+                (
+                    ToastKind::Error,
+                    t!("msg.export_failed", file = path, error = e.to_string()),
+                )
+            }
+        };
+        self.toasts.add(Toast {
+            kind,
+            text: WidgetText::RichText(RichText::new(text)),
+            options: ToastOptions::default().duration_in_seconds(5.0),
+        });
+    }
+    /// Replace wave view by session or gtkwave save file `path`, signals not found in
+    /// loaded wave are shown as toast
+    #[cfg(not(target_arch = "wasm32"))]
+    fn load_view(&mut self, path: &str) {
+        let Some(wave) = &self.wave else {
+            return;
+        };
+        let result = WaveSession::read(path, wave.info.timescale.1)
+            .and_then(|session| session.apply(&mut self.view, &wave.info));
+        let (kind, text) = match result {
+            Ok(missing) if missing.is_empty() => {
+                (ToastKind::Info, t!("msg.view_loaded", file = path))
+            }
+            Ok(missing) => {
+                warn!("signals of view {} not found: {:?}", path, missing);
+                (
+                    ToastKind::Warning,
+                    t!(
+                        "msg.view_missing",
+                        count = missing.len().to_string(),
+                        signals = missing.join(", ")
+                    ),
+                )
+            }
+            Err(e) => {
+                warn!("load view {} failed: {}", path, e);
+                (
+                    ToastKind::Error,
+                    t!("msg.view_failed", file = path, error = e.to_string()),
+                )
+            }
+        };
+        self.toasts.add(Toast {
+            kind,
+            text: WidgetText::RichText(RichText::new(text)),
+            options: ToastOptions::default().duration_in_seconds(5.0),
+        });
+    }
+    /// Loaders of all formats, with csv read by [Rvcd::csv_options]
+    fn loaders(&self) -> WaveLoaders {
+        let mut loaders = WaveLoaders::default();
+        loaders.register(Box::new(Csv::new(self.csv_options.clone())));
+        loaders
+    }
+    fn csv_options_dialog(&mut self, ctx: &egui::Context) {
+        let mut open = self.csv_options_open;
+        egui::Window::new(t!("csv_options.title"))
+            .id(Id::from(format!("csv_options_rvcd_{}", self.id)))
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(t!("csv_options.hint"));
+                let options = &mut self.csv_options;
+                egui::Grid::new(format!("csv_options_grid_{}", self.id)).show(ui, |ui| {
+                    ui.label(t!("csv_options.time_column"));
+                    ui.text_edit_singleline(&mut options.time_column)
+                        .on_hover_text(t!("csv_options.time_column_hover"));
+                    ui.end_row();
+                    ui.label(t!("csv_options.period"));
+                    ui.text_edit_singleline(&mut options.period)
+                        .on_hover_text(t!("csv_options.period_hover"));
+                    if WaveTime::parse(&options.period).is_err() {
+                        ui.label(RichText::new("❌").color(Color32::RED));
+                    }
+                    ui.end_row();
+                    ui.label(t!("csv_options.radix"));
+                    egui::ComboBox::from_id_source(format!("csv_options_radix_{}", self.id))
+                        .selected_text(format!("{:?}", options.radix))
+                        .show_ui(ui, |ui| {
+                            use crate::radix::Radix::*;
+                            for r in [Hex, Oct, Dec, Bin] {
+                                let text = format!("{r:?}");
+                                ui.selectable_value(&mut options.radix, r, text);
+                            }
+                        });
+                    ui.end_row();
+                    ui.label(t!("csv_options.separators"));
+                    ui.text_edit_singleline(&mut options.separators);
+                    ui.end_row();
+                });
+                ui.horizontal(|ui| {
+                    let valid = WaveTime::parse(&self.csv_options.period).is_ok();
+                    let apply = ui
+                        .add_enabled(valid, egui::Button::new(t!("csv_options.apply")))
+                        .clicked();
+                    let reset = ui.button(t!("csv_options.reset")).clicked();
+                    if reset {
+                        self.csv_options = Default::default();
+                    }
+                    if apply || reset {
+                        if let Some(channel) = &self.channel {
+                            let loaders = Arc::new(self.loaders());
+                            channel.tx.send(RvcdMsg::SetLoaders(loaders)).unwrap();
+                        }
+                        if !self.filepath.is_empty() {
+                            self.reload();
+                        }
+                    }
+                });
+            });
+        self.csv_options_open = open;
+    }
+    /// Request value changes of displaying signals which are not selected by load filter
+    #[cfg(not(target_arch = "wasm32"))]
+    fn load_unloaded_signals(&mut self) {
+        let wave = match &self.wave {
+            Some(wave) if wave.selected.is_some() => wave,
+            _ => return,
+        };
+        let ids = self
+            .view
+            .signals
+            .iter()
+            .map(|s| s.s.id)
+            .filter(|id| !wave.is_loaded(*id) && !self.signals_loading.contains(id))
+            .collect::<HashSet<_>>();
+        if ids.is_empty() {
+            return;
+        }
+        self.signals_loading.extend(ids.iter().copied());
+        if let Some(channel) = &self.channel {
+            channel
+                .tx
+                .send(RvcdMsg::LoadSignals(ids.into_iter().collect()))
+                .unwrap();
+        }
+    }
     pub fn sidebar(&mut self, ui: &mut Ui) {
         // test if regex is valid
         let test_regex = Regex::new(if self.search_regex {
@@ -549,18 +1221,31 @@ impl Rvcd {
                             } else {
                                 None
                             };
+                            let wave = self.wave.as_ref();
                             self.signal_leaves
                                 .iter()
                                 .filter(|x| {
                                     if !self.search_tree {
+                                        // aliased signals also match paths of all aliases
+                                        let aliases = wave
+                                            .filter(|w| w.info.code_aliases.contains_key(&x.id))
+                                            .map(|w| {
+                                                w.info
+                                                    .signal_paths(x.id)
+                                                    .map(|p| p.join("."))
+                                                    .collect::<Vec<_>>()
+                                            })
+                                            .unwrap_or_default();
+                                        let mut names = std::iter::once(x.name.as_str())
+                                            .chain(aliases.iter().map(|s| s.as_str()));
                                         if self.search_regex {
                                             if let Some(re) = &re {
-                                                re.captures(x.name.as_str()).is_some()
+                                                names.any(|name| re.captures(name).is_some())
                                             } else {
                                                 false
                                             }
                                         } else {
-                                            x.name.contains(search_text)
+                                            names.any(|name| name.contains(search_text))
                                         }
                                     } else {
                                         true
@@ -596,7 +1281,7 @@ impl Rvcd {
                                     }
                                 };
                                 row.col(|ui| {
-                                    handle_draw_response(ui, signal.typ.to_string());
+                                    handle_draw_response(ui, signal.type_name());
                                 });
                                 row.col(|ui| {
                                     handle_draw_response(ui, signal.to_string());
@@ -619,6 +1304,7 @@ impl Rvcd {
                     match self.tree.ui(
                         ui,
                         wave.info.tree.root(),
+                        &wave.info,
                         if self.search_tree {
                             self.search_text.as_str()
                         } else {
@@ -686,13 +1372,42 @@ impl Rvcd {
                 info!("ui recv wave: {}", wave);
                 self.wave = Some(wave);
                 self.signal_leaves.clear();
+                #[cfg(not(target_arch = "wasm32"))]
+                self.signals_loading.clear();
                 if let Some(wave) = &self.wave {
                     self.view.signals_clean_unavailable(&wave.info);
                 }
                 // FIXME: update range
                 self.state = State::Working;
             }
+            RvcdMsg::SetFollow(follow) => {
+                // re-direct this to service side
+                if let Some(channel) = &self.channel {
+                    channel.tx.send(RvcdMsg::SetFollow(follow)).unwrap();
+                }
+            }
+            RvcdMsg::AppendWave(tail) => {
+                if let Some(wave) = &mut self.wave {
+                    if let Err(e) = wave.append(tail) {
+                        warn!("append followed wave failed: {}", e);
+                    }
+                    if self.view.follow_scroll {
+                        self.view.scroll_to_end(&wave.info);
+                    }
+                }
+            }
+            RvcdMsg::MergeWave(_path, _loaded) => {
+                #[cfg(not(target_arch = "wasm32"))]
+                {
+                    // file may be changed since signals requested
+                    if let (true, Some(wave)) = (_path == self.filepath, &mut self.wave) {
+                        wave.merge(_loaded);
+                        self.signals_loading.retain(|id| !wave.is_loaded(*id));
+                    }
+                }
+            }
             RvcdMsg::FileOpen(_file) => {}
+            RvcdMsg::StreamOpen(_source) => {}
             RvcdMsg::Reload => {
                 self.reload();
             }
@@ -721,10 +1436,10 @@ impl Rvcd {
                 }
                 self.reset();
             }
-            RvcdMsg::FileOpenData(data) => {
+            RvcdMsg::FileOpenData(name, data) => {
                 // re-direct this to service side
                 if let Some(channel) = &self.channel {
-                    channel.tx.send(RvcdMsg::FileOpenData(data)).unwrap();
+                    channel.tx.send(RvcdMsg::FileOpenData(name, data)).unwrap();
                 }
             }
             RvcdMsg::FileDrag(file) => {
@@ -761,12 +1476,50 @@ impl Rvcd {
                 self.signal_leaves.clear();
                 if self.state == State::Idle {
                     self.load_progress = (0.0, 0);
+                    self.parse_progress = (0.0, 0);
                     self.state = State::Loading;
                 }
             }
             RvcdMsg::FileLoadCancel => {}
-            RvcdMsg::ServiceDataReady(_) => {}
+            RvcdMsg::ServiceDataReady(..) => {}
+            RvcdMsg::ServiceWaveReady(_) => {}
             RvcdMsg::StopService => {}
+            RvcdMsg::SetWaveCache(_) => {}
+            RvcdMsg::SetLoadFilter(_) => {}
+            RvcdMsg::SetLoaders(_) => {}
+            RvcdMsg::ExportVcd(_path, _filter) => {
+                #[cfg(not(target_arch = "wasm32"))]
+                self.export_file(&_path, |wave, writer| vcd_write(wave, &_filter, writer));
+            }
+            RvcdMsg::ExportTable(_path, _table) => {
+                #[cfg(not(target_arch = "wasm32"))]
+                self.export_file(&_path, |wave, writer| _table.write(wave, writer));
+            }
+            RvcdMsg::ExportImage(_path, _image) => {
+                #[cfg(not(target_arch = "wasm32"))]
+                {
+                    let view = self.view.clone();
+                    self.export_file(&_path, |wave, writer| _image.write(&view, wave, writer));
+                }
+            }
+            RvcdMsg::SaveView(_path) => {
+                #[cfg(not(target_arch = "wasm32"))]
+                {
+                    use std::io::Write;
+                    let view = self.view.clone();
+                    let file = self.filepath.clone();
+                    self.export_file(&_path, |wave, writer| {
+                        let session = WaveSession::from_view(&view, &wave.info, &file);
+                        writer.write_all(session.to_json()?.as_bytes())?;
+                        Ok(())
+                    });
+                }
+            }
+            RvcdMsg::LoadView(_path) => {
+                #[cfg(not(target_arch = "wasm32"))]
+                self.load_view(&_path);
+            }
+            RvcdMsg::LoadSignals(_) => {}
             RvcdMsg::UpdateSourceDir(_path) => {
                 #[cfg(not(target_arch = "wasm32"))]
                 {
@@ -880,6 +1633,11 @@ impl Rvcd {
                 }
                 return true;
             }
+            RvcdRpcMessage::OpenWaveStream(source) => {
+                if let Some(channel) = &self.channel {
+                    channel.tx.send(RvcdMsg::StreamOpen(source)).unwrap();
+                }
+            }
             RvcdRpcMessage::OpenSourceFile(file) => {
                 if let Some(loop_self) = &self.loop_self {
                     loop_self.send(RvcdMsg::UpdateSource(file)).unwrap();
@@ -930,9 +1688,18 @@ impl Rvcd {
             // #[cfg(not(target_arch = "wasm32"))]
             if ui.button(t!("menu.open")).clicked() {
                 if let Some(channel) = &self.channel {
-                    let task = rfd::AsyncFileDialog::new()
-                        .add_filter(t!("menu.vcd_file"), &["vcd"])
-                        .pick_file();
+                    let loaders = self.loaders();
+                    let mut extensions = loaders.extensions();
+                    extensions.extend(WaveCompression::ALL.map(|c| c.extension()));
+                    let mut dialog =
+                        rfd::AsyncFileDialog::new().add_filter(t!("menu.wave_file"), &extensions);
+                    for loader in loaders.iter() {
+                        dialog = dialog.add_filter(
+                            t!("menu.format_file", format = loader.name()),
+                            loader.extensions(),
+                        );
+                    }
+                    let task = dialog.pick_file();
                     let sender = channel.tx.clone();
                     execute(async move {
                         let file = task.await;
@@ -967,6 +1734,88 @@ impl Rvcd {
                 }
                 ui.close_menu();
             }
+            #[cfg(not(target_arch = "wasm32"))]
+            if ui
+                .checkbox(&mut self.wave_cache, t!("menu.wave_cache"))
+                .changed()
+            {
+                if let Some(channel) = &self.channel {
+                    channel
+                        .tx
+                        .send(RvcdMsg::SetWaveCache(self.wave_cache))
+                        .unwrap();
+                }
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            ui.horizontal(|ui| {
+                ui.label(t!("menu.load_filter"));
+                let response = ui
+                    .text_edit_singleline(&mut self.load_filter)
+                    .on_hover_text(t!("menu.load_filter_hover"));
+                match self.load_filter() {
+                    Ok(filter) => {
+                        if let (true, Some(channel)) = (response.changed(), &self.channel) {
+                            channel.tx.send(RvcdMsg::SetLoadFilter(filter)).unwrap();
+                        }
+                    }
+                    Err(_) => {
+                        ui.label(RichText::new("❌").color(Color32::RED));
+                    }
+                }
+            });
+            #[cfg(not(target_arch = "wasm32"))]
+            if ui.button(t!("menu.load_window")).clicked() {
+                self.load_window_open = true;
+                ui.close_menu();
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            ui.add_enabled_ui(self.state == State::Working, |ui| {
+                if ui.button(t!("menu.export_vcd")).clicked() {
+                    self.export_open = true;
+                    ui.close_menu();
+                }
+                if ui.button(t!("menu.export_table")).clicked() {
+                    self.export_table_open = true;
+                    ui.close_menu();
+                }
+                if ui.button(t!("menu.export_image")).clicked() {
+                    self.export_image_open = true;
+                    ui.close_menu();
+                }
+                ui.separator();
+                if ui.button(t!("menu.save_view")).clicked() {
+                    let task = rfd::AsyncFileDialog::new()
+                        .add_filter(t!("menu.view_file"), &["json"])
+                        .set_file_name("wave.view.json")
+                        .save_file();
+                    let loop_self = self.loop_self.clone();
+                    execute(async move {
+                        if let (Some(file), Some(loop_self)) = (task.await, loop_self) {
+                            let path = file.path().to_string_lossy().to_string();
+                            loop_self.send(RvcdMsg::SaveView(path)).ok();
+                        }
+                    });
+                    ui.close_menu();
+                }
+                if ui.button(t!("menu.load_view")).clicked() {
+                    let task = rfd::AsyncFileDialog::new()
+                        .add_filter(t!("menu.view_file"), &["json", "gtkw"])
+                        .add_filter(t!("menu.format_file", format = "GTKWave"), &["gtkw"])
+                        .pick_file();
+                    let loop_self = self.loop_self.clone();
+                    execute(async move {
+                        if let (Some(file), Some(loop_self)) = (task.await, loop_self) {
+                            let path = file.path().to_string_lossy().to_string();
+                            loop_self.send(RvcdMsg::LoadView(path)).ok();
+                        }
+                    });
+                    ui.close_menu();
+                }
+            });
+            if ui.button(t!("menu.csv_options")).clicked() {
+                self.csv_options_open = true;
+                ui.close_menu();
+            }
             ui.add_enabled_ui(self.state == State::Working, |ui| {
                 if ui.button(t!("menu.close")).clicked() {
                     ui.close_menu();
@@ -1021,11 +1870,17 @@ impl Rvcd {
                 }
             }
         } else if let Some(data) = &dropped_file.bytes {
-            self.message_handler(RvcdMsg::FileOpenData(data.clone()));
+            self.message_handler(RvcdMsg::FileOpenData(
+                dropped_file.name.clone(),
+                data.clone(),
+            ));
         }
         #[cfg(target_arch = "wasm32")]
         if let Some(data) = &dropped_file.bytes {
-            self.message_handler(RvcdMsg::FileOpenData(data.clone()));
+            self.message_handler(RvcdMsg::FileOpenData(
+                dropped_file.name.clone(),
+                data.clone(),
+            ));
         }
         //     });
         // }
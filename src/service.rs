@@ -1,110 +1,375 @@
 use crate::message::{RvcdChannel, RvcdMsg};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::stream::StreamSource;
 use crate::utils::{execute, sleep_ms};
-use crate::wave::vcd_parser::Vcd;
-use crate::wave::{WaveLoader, WavePreLoader};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::wave::cache::{wave_cache_load, wave_cache_save, WaveCacheKey, WAVE_CACHE_MIN_SIZE};
+use crate::wave::filter::WaveFilter;
+use crate::wave::loader::WaveLoaders;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::wave::vcd_parser::{vcd_lines_end, Vcd, VcdStream};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::wave::Wave;
 use anyhow::Result;
 #[allow(unused_imports)]
-use std::io::{BufReader, Cursor, Read};
+use std::io::{Cursor, Read};
 use std::sync::{mpsc, Arc, Mutex};
 use tracing::{debug, error, info};
 
+/// Interval to check whether followed file grows
+#[cfg(not(target_arch = "wasm32"))]
+pub const FOLLOW_INTERVAL_MS: u64 = 500;
+
+/// Buffer size to read vcd streams
+#[cfg(not(target_arch = "wasm32"))]
+pub const STREAM_BUF_SIZE: usize = 1024 * 64;
+
+/// Loaded vcd file that may still be written
+#[cfg(not(target_arch = "wasm32"))]
+pub struct FollowFile {
+    pub path: String,
+    /// Bytes consumed from file start, the last timestamp block is kept to be read again
+    pub position: u64,
+    /// Bytes parsed from file start, complete lines after `position` included
+    pub parsed: u64,
+    /// Last timestamp in consumed bytes
+    pub timestamp: u64,
+    pub checked: std::time::Instant,
+}
+
+/// Bytes read between [RvcdMsg::LoadingProgress] notifications
+#[cfg(not(target_arch = "wasm32"))]
+pub const LOAD_NOTIFY_SIZE: u64 = 1024 * 256;
+
+/// File reader that notifies read bytes and checks cancel flag, for parsers to read directly
+#[cfg(not(target_arch = "wasm32"))]
+pub struct ProgressReader<R> {
+    inner: R,
+    total: u64,
+    /// Bytes read, or position after seeking
+    pub count: u64,
+    notified: u64,
+    tx: mpsc::Sender<RvcdMsg>,
+    cancel: Arc<Mutex<bool>>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<R> ProgressReader<R> {
+    pub fn new(inner: R, total: u64, tx: mpsc::Sender<RvcdMsg>, cancel: Arc<Mutex<bool>>) -> Self {
+        Self {
+            inner,
+            total,
+            count: 0,
+            notified: 0,
+            tx,
+            cancel,
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<R: Read> Read for ProgressReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let sz = self.inner.read(buf)?;
+        self.count += sz as u64;
+        if self.count >= self.notified + LOAD_NOTIFY_SIZE || sz == 0 {
+            self.notified = self.count;
+            let progress = self.count as f32 / self.total.max(1) as f32;
+            self.tx
+                .send(RvcdMsg::LoadingProgress(progress, self.count as usize))
+                .unwrap();
+            let mut canceled = self.cancel.lock().unwrap();
+            if *canceled {
+                info!("cancel flag detected, false");
+                *canceled = false;
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::Other,
+                    "loading canceled",
+                ));
+            }
+        }
+        Ok(sz)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<R: std::io::Seek> std::io::Seek for ProgressReader<R> {
+    fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+        self.count = self.inner.seek(pos)?;
+        self.notified = self.count;
+        Ok(self.count)
+    }
+}
+
 pub struct Service {
     pub channel: RvcdChannel,
     pub self_loop: RvcdChannel,
     pub cancel: Arc<Mutex<bool>>,
     pub loading: Arc<Mutex<bool>>,
+    /// Whether to parse data appended to loaded file
+    pub follow: bool,
+    /// Whether to load parsed wave from cache and save it after parsing
+    pub wave_cache: bool,
+    /// Signals to keep value changes when loading files
+    pub load_filter: WaveFilter,
+    /// Loaders of supported formats, selected by file content and extension
+    pub loaders: Arc<WaveLoaders>,
+    /// Path of file loading or loaded
+    #[cfg(not(target_arch = "wasm32"))]
+    pub path: Option<String>,
+    #[cfg(not(target_arch = "wasm32"))]
+    pub follow_file: Option<FollowFile>,
 }
 
 unsafe impl Send for Service {}
 
 impl Service {
-    fn parse_data_send(&self, data: Vec<u8>) -> bool {
-        let (last_timestamp, reader) = Vcd::last_timestamp(BufReader::new(Cursor::new(data)));
-        let reader = reader.unwrap();
-        if let Ok(wave) = Vcd::load(
-            &mut reader.into_inner(),
-            |percent, pos| {
-                self.channel
-                    .tx
-                    .send(RvcdMsg::ParsingProgress(percent, pos))
-                    .unwrap();
-            },
-            last_timestamp,
-        ) {
-            info!("service load wave: {}", wave);
-            self.channel.tx.send(RvcdMsg::UpdateWave(wave)).unwrap();
-            true
-        } else {
-            false
+    /// Parse bytes of file `name` keeping signals and time window of load filter, format is
+    /// detected by magic bytes or extension of `name`
+    fn parse_data_send(&self, name: &str, data: Vec<u8>) -> bool {
+        let progress_handler = |percent, pos| {
+            self.channel
+                .tx
+                .send(RvcdMsg::ParsingProgress(percent, pos))
+                .unwrap();
+        };
+        let wave = self
+            .loaders
+            .open(name, Cursor::new(data))
+            .and_then(|mut opened| {
+                opened
+                    .loader
+                    .load_source(&mut *opened.source, &progress_handler, &self.load_filter)
+            })
+            .map(|(wave, _)| wave);
+        match wave {
+            Ok(wave) => {
+                info!("service load wave: {}", wave);
+                self.channel.tx.send(RvcdMsg::UpdateWave(wave)).unwrap();
+                true
+            }
+            Err(e) => {
+                error!("parse data failed: {}", e);
+                false
+            }
         }
     }
     #[cfg(not(target_arch = "wasm32"))]
-    fn load_data_loop(
+    fn load_file_loop(
         path: String,
         tx: mpsc::Sender<RvcdMsg>,
         loop_tx: mpsc::Sender<RvcdMsg>,
         cancel: Arc<Mutex<bool>>,
+        wave_cache: bool,
+        filter: WaveFilter,
+        loaders: Arc<WaveLoaders>,
     ) {
-        let data = Self::load_data(path, tx, cancel);
-        loop_tx.send(RvcdMsg::ServiceDataReady(data)).unwrap();
+        let loaded = match Self::load_file_cached(&path, tx, cancel, wave_cache, &filter, &loaders)
+        {
+            Ok(loaded) => Some(loaded),
+            Err(e) => {
+                error!("load file {} failed: {}", path, e);
+                None
+            }
+        };
+        loop_tx.send(RvcdMsg::ServiceWaveReady(loaded)).unwrap();
     }
+    /// Parse file again keeping only signals in `filter`, then send them to ui to merge
+    /// into loaded wave
     #[cfg(not(target_arch = "wasm32"))]
-    fn load_data(path: String, tx: mpsc::Sender<RvcdMsg>, cancel: Arc<Mutex<bool>>) -> Vec<u8> {
-        let file = std::fs::File::open(path);
-        match file {
-            Ok(file) => {
-                // TODO: cancel progress that loading to memory first
-                let total_sz = file.metadata().unwrap().len();
-                let mut reader = BufReader::new(file);
-                if total_sz != 0 {
-                    const BUF_SIZE: usize = 1024 * 256;
-                    // const BUF_SIZE: usize = 8;
-                    let mut data = vec![0u8; total_sz as usize];
-                    let mut buf = [0u8; BUF_SIZE];
-                    let mut count = 0;
-                    let mut canceled = false;
-                    info!("start reading file");
-                    let time_start = std::time::Instant::now();
-                    while let Ok(sz) = reader.read(&mut buf) {
-                        if sz == 0 {
-                            break;
-                        }
-                        data[count..(count + sz)].copy_from_slice(&buf[0..sz]);
-                        count += sz;
-                        let progress = count as f32 / total_sz as f32;
-                        tx.send(RvcdMsg::LoadingProgress(progress, count)).unwrap();
-                        match cancel.lock() {
-                            Ok(mut r) => {
-                                if *r {
-                                    info!("cancel flag detected, false");
-                                    canceled = true;
-                                    *r = false;
-                                    break;
-                                }
-                            }
-                            Err(_e) => {
-                                canceled = true;
-                                error!("{}", _e);
-                            }
-                        }
-                        // sleep_ms(1000).await;
-                        // std::thread::sleep(std::time::Duration::from_millis(1000));
-                    }
-                    let time_stop = std::time::Instant::now();
-                    let duration = time_stop - time_start;
-                    info!("stop reading file, used {} ms", duration.as_millis());
-                    if !canceled {
-                        data
-                    } else {
-                        vec![]
-                    }
-                } else {
-                    vec![]
+    fn load_signals_loop(
+        path: String,
+        filter: WaveFilter,
+        tx: mpsc::Sender<RvcdMsg>,
+        loaders: Arc<WaveLoaders>,
+    ) {
+        info!(
+            "loading {} signals on demand from {}",
+            filter.ids.len(),
+            path
+        );
+        // keep receiver alive, progress of loading signals is not shown
+        let (progress_tx, _progress_rx) = mpsc::channel();
+        let cancel = Arc::new(Mutex::new(false));
+        match Self::load_file(&path, progress_tx, cancel, &filter, &loaders) {
+            Ok((wave, _)) => tx.send(RvcdMsg::MergeWave(path, wave)).unwrap(),
+            Err(e) => error!("load signals from {} failed: {}", path, e),
+        }
+    }
+    /// Load wave from cache if file not changed since cached, or parse it and save cache
+    #[cfg(not(target_arch = "wasm32"))]
+    fn load_file_cached(
+        path: &str,
+        tx: mpsc::Sender<RvcdMsg>,
+        cancel: Arc<Mutex<bool>>,
+        wave_cache: bool,
+        filter: &WaveFilter,
+        loaders: &WaveLoaders,
+    ) -> Result<(Wave, Option<u64>)> {
+        // partly loaded waves are not cached, nor csv waves depending on loader options
+        let csv = loaders.by_extension(path).map(|l| l.name()) == Some("CSV");
+        let key = match wave_cache && filter.is_empty() && !csv {
+            true => WaveCacheKey::new(path)
+                .ok()
+                .filter(|key| key.size >= WAVE_CACHE_MIN_SIZE),
+            false => None,
+        };
+        if let Some(key) = &key {
+            let time_start = std::time::Instant::now();
+            match wave_cache_load(&key.cache_path(), key) {
+                Ok(Some(loaded)) => {
+                    info!(
+                        "load wave from cache {:?}, used {} ms",
+                        key.cache_path(),
+                        time_start.elapsed().as_millis()
+                    );
+                    return Ok(loaded);
                 }
+                Ok(None) => {}
+                Err(e) => error!("load wave cache failed: {}", e),
+            }
+        }
+        let (wave, consumed) = Self::load_file(path, tx, cancel, filter, loaders)?;
+        if let Some(key) = &key {
+            if let Err(e) = wave_cache_save(&key.cache_path(), key, &wave, consumed) {
+                error!("save wave cache failed: {}", e);
+            }
+        }
+        Ok((wave, consumed))
+    }
+    /// Parse file while reading it, so that file data is never copied to memory as a whole.
+    /// Returns wave and bytes consumed if file can be followed
+    #[cfg(not(target_arch = "wasm32"))]
+    fn load_file(
+        path: &str,
+        tx: mpsc::Sender<RvcdMsg>,
+        cancel: Arc<Mutex<bool>>,
+        filter: &WaveFilter,
+        loaders: &WaveLoaders,
+    ) -> Result<(Wave, Option<u64>)> {
+        let file = std::fs::File::open(path)?;
+        let total_sz = file.metadata()?.len();
+        // progress of compressed file is counted in compressed bytes
+        let reader = ProgressReader::new(file, total_sz, tx.clone(), cancel);
+        let mut opened = loaders.open(path, reader)?;
+        let progress_handler = move |percent, pos| {
+            tx.send(RvcdMsg::ParsingProgress(percent, pos)).unwrap();
+        };
+        match opened.compression {
+            Some(compression) => info!(
+                "start loading {} {} file",
+                compression,
+                opened.loader.name()
+            ),
+            None => info!("start loading {} file", opened.loader.name()),
+        }
+        let time_start = std::time::Instant::now();
+        let (wave, consumed) =
+            opened
+                .loader
+                .load_source(&mut *opened.source, &progress_handler, filter)?;
+        let duration = std::time::Instant::now() - time_start;
+        info!("stop loading file, used {} ms", duration.as_millis());
+        // data appended to compressed file cannot be parsed alone
+        Ok((wave, consumed.filter(|_| opened.compression.is_none())))
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    fn load_stream_loop(source: StreamSource, tx: mpsc::Sender<RvcdMsg>) {
+        if let Err(e) = Self::load_stream(&source, &tx) {
+            error!("load stream {} failed: {}", source, e);
+            tx.send(RvcdMsg::FileOpenFailed(source.to_string()))
+                .unwrap();
+        }
+    }
+    /// Parse vcd stream until closed, updating the open wave as data received
+    #[cfg(not(target_arch = "wasm32"))]
+    fn load_stream(source: &StreamSource, tx: &mpsc::Sender<RvcdMsg>) -> Result<()> {
+        let mut reader = source.open()?;
+        let mut stream = VcdStream::default();
+        let mut buf = vec![0u8; STREAM_BUF_SIZE];
+        let mut count = 0;
+        loop {
+            let sz = reader.read(&mut buf)?;
+            if sz == 0 {
+                break;
+            }
+            count += sz;
+            stream.feed(&buf[..sz]);
+            if !stream.header_parsed() {
+                tx.send(RvcdMsg::LoadingProgress(0.0, count)).unwrap();
             }
-            Err(_) => {
-                vec![]
+            if let Some(wave) = stream.header()? {
+                info!("stream header received: {}", wave);
+                tx.send(RvcdMsg::UpdateWave(wave)).unwrap();
+            }
+            if let Some(tail) = stream.tail()? {
+                tx.send(RvcdMsg::AppendWave(tail)).unwrap();
+            }
+        }
+        info!("stream {} closed, {} bytes received", source, count);
+        if let Some(tail) = stream.finish()? {
+            tx.send(RvcdMsg::AppendWave(tail)).unwrap();
+        }
+        Ok(())
+    }
+    /// Parse complete timestamps appended to followed file and send them to ui
+    #[cfg(not(target_arch = "wasm32"))]
+    fn follow_tick(&mut self) {
+        use std::io::{Seek, SeekFrom};
+        if !self.follow || *self.loading.lock().unwrap() {
+            return;
+        }
+        let follow = match &mut self.follow_file {
+            Some(follow) => follow,
+            None => return,
+        };
+        if follow.checked.elapsed() < std::time::Duration::from_millis(FOLLOW_INTERVAL_MS) {
+            return;
+        }
+        follow.checked = std::time::Instant::now();
+        let mut file = match std::fs::File::open(&follow.path) {
+            Ok(file) => file,
+            Err(_) => return,
+        };
+        let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+        if size < follow.position {
+            info!("followed file truncated, reload {}", follow.path);
+            self.follow_file = None;
+            self.channel.tx.send(RvcdMsg::Reload).unwrap();
+            return;
+        }
+        if size == follow.position {
+            return;
+        }
+        let mut data = vec![];
+        if let Err(e) = file
+            .seek(SeekFrom::Start(follow.position))
+            .and_then(|_| file.read_to_end(&mut data))
+        {
+            error!("read followed file failed: {}", e);
+            return;
+        }
+        // parse complete lines, the last timestamp block may be partly written
+        let (line_end, tail_end) = match vcd_lines_end(&data) {
+            Some(end) => end,
+            None => return,
+        };
+        let parsed = follow.position + line_end as u64;
+        if parsed == follow.parsed {
+            return;
+        }
+        // bytes failed to parse are kept and read again on next tick, the last block is
+        // read again as well, its changes replace the ones appended before, see [Wave::append]
+        match Vcd::load_tail(&mut &data[..line_end], follow.timestamp) {
+            Ok(tail) => {
+                follow.position += tail_end as u64;
+                follow.parsed = parsed;
+                if let Some((_, stop)) = tail.range {
+                    follow.timestamp = stop;
+                }
+                self.channel.tx.send(RvcdMsg::AppendWave(tail)).unwrap();
             }
+            Err(e) => error!("parse followed file failed: {}", e),
         }
     }
     async fn handle_message(&mut self, msg: RvcdMsg) -> Result<bool> {
@@ -143,11 +408,17 @@ impl Service {
                     #[cfg(not(target_arch = "wasm32"))]
                     let data: Option<Vec<u8>> = {
                         let path = file.path().to_str().unwrap().to_string();
+                        self.path = Some(path.to_string());
                         let tx = self.channel.tx.clone();
                         let loop_tx = self.self_loop.tx.clone();
                         let cancel = self.cancel.clone();
+                        let wave_cache = self.wave_cache;
+                        let filter = self.load_filter.clone();
+                        let loaders = self.loaders.clone();
                         let _th = std::thread::spawn(move || {
-                            Self::load_data_loop(path, tx, loop_tx, cancel)
+                            Self::load_file_loop(
+                                path, tx, loop_tx, cancel, wave_cache, filter, loaders,
+                            )
                         });
                         *self.loading.lock().unwrap() = true;
                         None
@@ -155,7 +426,7 @@ impl Service {
                     if let Some(data) = data {
                         self.self_loop
                             .tx
-                            .send(RvcdMsg::ServiceDataReady(data))
+                            .send(RvcdMsg::ServiceDataReady(file.file_name(), data))
                             .unwrap();
                     }
                 } else {
@@ -170,9 +441,14 @@ impl Service {
                     }
                 }
             }
-            RvcdMsg::FileOpenData(data) => {
+            RvcdMsg::FileOpenData(name, data) => {
+                #[cfg(not(target_arch = "wasm32"))]
+                {
+                    self.path = None;
+                    self.follow_file = None;
+                }
                 // TODO: reduce this data clone
-                if !self.parse_data_send(data.to_vec()) {
+                if !self.parse_data_send(&name, data.to_vec()) {
                     self.channel
                         .tx
                         .send(RvcdMsg::FileOpenFailed("".to_string()))
@@ -187,11 +463,39 @@ impl Service {
                     }
                 }
             }
-            RvcdMsg::ServiceDataReady(data) => {
+            RvcdMsg::ServiceWaveReady(loaded) => {
+                *self.loading.lock().unwrap() = false;
+                match loaded {
+                    Some((wave, _consumed)) => {
+                        info!("service load wave: {}", wave);
+                        #[cfg(not(target_arch = "wasm32"))]
+                        {
+                            // only vcd files can be followed
+                            self.follow_file = match (&self.path, _consumed) {
+                                (Some(path), Some(position)) => Some(FollowFile {
+                                    path: path.to_string(),
+                                    position,
+                                    parsed: position,
+                                    timestamp: wave.info.range.1,
+                                    checked: std::time::Instant::now(),
+                                }),
+                                _ => None,
+                            };
+                        }
+                        self.channel.tx.send(RvcdMsg::UpdateWave(wave)).unwrap();
+                    }
+                    None => self
+                        .channel
+                        .tx
+                        .send(RvcdMsg::FileOpenFailed("".to_string()))
+                        .unwrap(),
+                }
+            }
+            RvcdMsg::ServiceDataReady(name, data) => {
                 *self.loading.lock().unwrap() = false;
                 info!("start parsing data");
                 let time_start = std::time::Instant::now();
-                if !self.parse_data_send(data) {
+                if !self.parse_data_send(&name, data) {
                     self.channel
                         .tx
                         .send(RvcdMsg::FileOpenFailed("".to_string()))
@@ -201,6 +505,43 @@ impl Service {
                 let duration = time_stop - time_start;
                 info!("stop parsing data, used {} ms", duration.as_millis());
             }
+            RvcdMsg::StreamOpen(_source) => {
+                #[cfg(not(target_arch = "wasm32"))]
+                {
+                    let source = StreamSource::parse(&_source);
+                    info!("loading stream: {}", source);
+                    self.path = None;
+                    self.follow_file = None;
+                    self.channel
+                        .tx
+                        .send(RvcdMsg::FileLoadStart(source.to_string()))
+                        .unwrap();
+                    let tx = self.channel.tx.clone();
+                    let _th = std::thread::spawn(move || Self::load_stream_loop(source, tx));
+                }
+            }
+            RvcdMsg::SetFollow(follow) => self.follow = follow,
+            RvcdMsg::SetWaveCache(cache) => self.wave_cache = cache,
+            RvcdMsg::SetLoadFilter(filter) => self.load_filter = filter,
+            RvcdMsg::SetLoaders(loaders) => self.loaders = loaders,
+            RvcdMsg::LoadSignals(_ids) => {
+                #[cfg(not(target_arch = "wasm32"))]
+                {
+                    if let Some(path) = self.path.clone() {
+                        // keep the same time window as loaded wave
+                        let filter = WaveFilter {
+                            start: self.load_filter.start,
+                            end: self.load_filter.end,
+                            ..WaveFilter::from_ids(_ids)
+                        };
+                        let tx = self.channel.tx.clone();
+                        let loaders = self.loaders.clone();
+                        let _th = std::thread::spawn(move || {
+                            Self::load_signals_loop(path, filter, tx, loaders)
+                        });
+                    }
+                }
+            }
             RvcdMsg::StopService => return Ok(true),
             RvcdMsg::UpdateSourceDir(_path) => {
                 #[cfg(not(target_arch = "wasm32"))]
@@ -238,6 +579,14 @@ impl Service {
             self_loop,
             cancel: Arc::new(Mutex::new(false)),
             loading: Arc::new(Mutex::new(false)),
+            follow: false,
+            wave_cache: true,
+            load_filter: Default::default(),
+            loaders: Default::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            path: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            follow_file: None,
         }
     }
 
@@ -284,6 +633,8 @@ impl Service {
                     };
                 }
             }
+            #[cfg(not(target_arch = "wasm32"))]
+            self.follow_tick();
         }
         #[cfg(not(target_arch = "wasm32"))]
         info!("[thread-{:?}] service stopped", std::thread::current().id());
@@ -0,0 +1,77 @@
+use anyhow::Result;
+use std::fmt::{Display, Formatter};
+use std::io::Read;
+use tracing::info;
+
+/// Default address to listen when only port is given in `tcp:PORT`
+pub const STREAM_TCP_HOST: &str = "127.0.0.1";
+
+/// Source of vcd text streamed by a running simulator
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StreamSource {
+    /// `-` or `stdin`, e.g. `sim | rvcd -`
+    Stdin,
+    /// `tcp:PORT` or `tcp:ADDR:PORT`, listen and accept one simulator connection
+    Tcp(String),
+    /// Any other path, e.g. a named pipe created by `mkfifo`
+    Pipe(String),
+}
+
+impl StreamSource {
+    pub fn parse(source: &str) -> Self {
+        match source {
+            "-" | "stdin" => Self::Stdin,
+            _ => match source.strip_prefix("tcp:") {
+                Some(addr) if addr.contains(':') => Self::Tcp(addr.to_string()),
+                Some(port) => Self::Tcp(format!("{STREAM_TCP_HOST}:{port}")),
+                None => Self::Pipe(source.to_string()),
+            },
+        }
+    }
+    /// Open source, blocks until pipe writer or tcp client is connected
+    pub fn open(&self) -> Result<Box<dyn Read + Send>> {
+        Ok(match self {
+            Self::Stdin => Box::new(std::io::stdin()),
+            Self::Tcp(addr) => {
+                let listener = std::net::TcpListener::bind(addr)?;
+                info!("waiting for vcd stream at {}", addr);
+                let (stream, peer) = listener.accept()?;
+                info!("vcd stream connected from {}", peer);
+                Box::new(stream)
+            }
+            Self::Pipe(path) => Box::new(std::fs::File::open(path)?),
+        })
+    }
+}
+
+impl Display for StreamSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Stdin => write!(f, "stdin"),
+            Self::Tcp(addr) => write!(f, "tcp:{addr}"),
+            Self::Pipe(path) => write!(f, "{path}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::stream::StreamSource;
+
+    #[test]
+    fn test_stream_source() {
+        assert_eq!(StreamSource::parse("-"), StreamSource::Stdin);
+        assert_eq!(
+            StreamSource::parse("tcp:5412"),
+            StreamSource::Tcp("127.0.0.1:5412".to_string())
+        );
+        assert_eq!(
+            StreamSource::parse("tcp:0.0.0.0:5412").to_string(),
+            "tcp:0.0.0.0:5412"
+        );
+        assert_eq!(
+            StreamSource::parse("/tmp/sim.fifo"),
+            StreamSource::Pipe("/tmp/sim.fifo".to_string())
+        );
+    }
+}
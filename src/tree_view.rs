@@ -1,6 +1,6 @@
 use crate::utils::get_text_size;
 use crate::view::{BG_MULTIPLY, SIGNAL_TREE_HEIGHT_DEFAULT, TEXT_BG_MULTIPLY};
-use crate::wave::{WaveScopeType, WaveTreeNode};
+use crate::wave::{WaveInfo, WaveScopeType, WaveTreeNode};
 use egui::{vec2, Align2, CollapsingHeader, Color32, PointerButton, Response, Sense, Ui};
 use regex::Regex;
 use trees::Node;
@@ -36,6 +36,7 @@ impl TreeView {
         &mut self,
         ui: &mut Ui,
         tree: &Node<WaveTreeNode>,
+        info: &WaveInfo,
         search_text: &str,
         is_regex: bool,
     ) -> TreeAction {
@@ -124,7 +125,7 @@ impl TreeView {
                 let text = node.to_string();
                 let text_right = match node {
                     WaveTreeNode::WaveScope(s) => s.typ.to_string(),
-                    WaveTreeNode::WaveVar(s) => s.typ.to_string(),
+                    WaveTreeNode::WaveVar(s) => s.type_name(),
                     _ => "".to_string(),
                 };
                 let text_size = get_text_size(ui, text.as_str(), Default::default());
@@ -136,6 +137,21 @@ impl TreeView {
                     ),
                     Sense::click_and_drag(),
                 );
+                // list all paths of aliased signals
+                let response = match node {
+                    WaveTreeNode::WaveVar(v) if info.code_aliases.contains_key(&v.id) => {
+                        let paths = info
+                            .signal_paths(v.id)
+                            .map(|p| p.join("."))
+                            .collect::<Vec<_>>();
+                        response.on_hover_text(format!(
+                            "{}\n{}",
+                            t!("sst.signal.aliases"),
+                            paths.join("\n")
+                        ))
+                    }
+                    _ => response,
+                };
                 let on_hover = ui.rect_contains_pointer(response.rect);
                 let mut text_color = if on_hover {
                     ui.visuals().strong_text_color()
@@ -205,7 +221,7 @@ impl TreeView {
             match tree.data() {
                 WaveTreeNode::WaveRoot => tree
                     .iter()
-                    .map(|child| self.ui(ui, child, search_text, is_regex))
+                    .map(|child| self.ui(ui, child, info, search_text, is_regex))
                     .find(|a| *a != TreeAction::None)
                     .unwrap_or(TreeAction::None),
                 data => {
@@ -213,7 +229,7 @@ impl TreeView {
                         .default_open(true)
                         .show(ui, |ui| {
                             tree.iter()
-                                .map(|child| self.ui(ui, child, search_text, is_regex))
+                                .map(|child| self.ui(ui, child, info, search_text, is_regex))
                                 .find(|a| *a != TreeAction::None)
                         });
                     if scope.header_response.clicked_by(PointerButton::Primary) {
@@ -0,0 +1,198 @@
+use crate::utils::get_text_size;
+use crate::view::vector::{vector_write, VectorFormat};
+use crate::view::{WaveView, BG_MULTIPLY, SIGNAL_HEIGHT_DEFAULT, UI_WIDTH_OFFSET};
+use crate::wave::Wave;
+use anyhow::Result;
+use egui::epaint::ClippedShape;
+use egui::{
+    pos2, vec2, Align2, CentralPanel, Color32, Context, FontId, Frame, Pos2, RawInput, Rect, Ui,
+    Vec2, Visuals,
+};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::ops::RangeInclusive;
+
+/// Image of wave panel exported as vector graphics
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct WaveImage {
+    pub format: VectorFormat,
+    /// Page width in points, the wave column takes what names and values leave
+    pub width: f32,
+    /// Paint in dark theme, light theme reads better on paper
+    pub dark: bool,
+}
+
+impl Default for WaveImage {
+    fn default() -> Self {
+        Self {
+            format: Default::default(),
+            width: 1200.0,
+            dark: false,
+        }
+    }
+}
+
+impl WaveImage {
+    /// Paint `view` of `wave` and write it to `writer`
+    pub fn write(&self, view: &WaveView, wave: &Wave, writer: &mut dyn Write) -> Result<()> {
+        let visuals = match self.dark {
+            true => Visuals::dark(),
+            false => Visuals::light(),
+        };
+        let (shapes, size) = view.paint_image(wave, self.width, visuals);
+        vector_write(&shapes, size, self.format, writer)
+    }
+}
+
+impl WaveView {
+    /// Paint wave panel headlessly on a page `width` points wide and as high as all signals,
+    /// returns painted shapes and page size
+    pub fn paint_image(
+        &self,
+        wave: &Wave,
+        width: f32,
+        visuals: Visuals,
+    ) -> (Vec<ClippedShape>, Vec2) {
+        let height = SIGNAL_HEIGHT_DEFAULT + self.signals.iter().map(|s| s.height).sum::<f32>();
+        let size = vec2(width, height);
+        let mut view = self.clone();
+        view.tx = None;
+        view.marker_temp.valid = false;
+        let ctx = Context::default();
+        ctx.set_visuals(visuals);
+        let input = RawInput {
+            screen_rect: Some(Rect::from_min_size(Pos2::ZERO, size)),
+            ..Default::default()
+        };
+        let output = ctx.run(input, |ctx| {
+            CentralPanel::default()
+                .frame(Frame::none().fill(ctx.style().visuals.panel_fill))
+                .show(ctx, |ui| view.paint_static(ui, wave));
+        });
+        (output.shapes, size)
+    }
+    /// Paint time bar, signal names, values at marker, waves, spans and cursors like
+    /// [WaveView::panel] does, without toolbar, scrolling and interaction
+    fn paint_static(&mut self, ui: &mut Ui, wave: &Wave) {
+        let info = &wave.info;
+        if self.range.0 == 0.0 && self.range.1 == 0.0 {
+            self.range = (info.range.0 as f64, info.range.1 as f64);
+        }
+        let rect = ui.max_rect();
+        const DEFAULT_MIN_SIGNAL_WIDTH: f32 = 150.0;
+        let name_width = f32::max(
+            self.signals
+                .iter()
+                .map(|x| get_text_size(ui, x.s.to_string().as_str(), Default::default()).x)
+                .reduce(f32::max)
+                .unwrap_or(0.0),
+            DEFAULT_MIN_SIGNAL_WIDTH,
+        );
+        let value_font = FontId::monospace(self.signal_font_size);
+        let marker_pos = Some(self.marker.pos)
+            .filter(|p| self.marker.valid && (info.range.0..=info.range.1).contains(p));
+        let values = self
+            .signals
+            .iter()
+            .map(|s| {
+                marker_pos
+                    .and_then(|p| wave.find_value(s.s.id, p))
+                    .map(|v| self.value_text(s, &v.value, info))
+                    .unwrap_or_default()
+            })
+            .collect::<Vec<_>>();
+        const DEFAULT_MIN_VALUE_WIDTH: f32 = 32.0;
+        let value_width = f32::max(
+            values
+                .iter()
+                .map(|s| get_text_size(ui, s, value_font.clone()).x)
+                .reduce(f32::max)
+                .unwrap_or(0.0),
+            DEFAULT_MIN_VALUE_WIDTH,
+        );
+        // columns are spaced like table in panel
+        let value_left = rect.left() + name_width + UI_WIDTH_OFFSET / 2.0;
+        let wave_left = rect.left() + name_width + value_width + UI_WIDTH_OFFSET;
+        self.wave_width = rect.right() - wave_left;
+        let wave_x = RangeInclusive::new(wave_left, rect.right());
+        let header = Rect::from_min_size(rect.min, vec2(rect.width(), SIGNAL_HEIGHT_DEFAULT));
+        let painter = ui.painter().clone();
+        let strong_color = ui.visuals().strong_text_color();
+        painter.text(
+            header.left_center(),
+            Align2::LEFT_CENTER,
+            t!(
+                "view.time_label",
+                left = info.range.0.to_string().as_str(),
+                right = info.range.1.to_string().as_str(),
+                timescale = info.timescale.0.to_string().as_str(),
+                timescale_unit = info.timescale.1.to_string().as_str()
+            ),
+            Default::default(),
+            strong_color,
+        );
+        painter.text(
+            pos2(value_left, header.center().y),
+            Align2::LEFT_CENTER,
+            t!("view.value"),
+            Default::default(),
+            strong_color,
+        );
+        ui.allocate_ui_at_rect(
+            Rect::from_x_y_ranges(wave_x.clone(), header.y_range()),
+            |ui| {
+                ui.set_clip_rect(ui.max_rect());
+                self.time_bar(ui, info, wave_left);
+            },
+        );
+        let mut top = header.bottom();
+        for (index, (signal, value)) in self.signals.iter().zip(values).enumerate() {
+            let row = Rect::from_x_y_ranges(rect.x_range(), top..=top + signal.height);
+            top = row.bottom();
+            if index % 2 == 1 {
+                painter.rect_filled(row, 0.0, ui.visuals().faint_bg_color);
+            }
+            if self.highlight_signals.contains(&signal.s.id) {
+                painter.rect_filled(
+                    Rect::from_x_y_ranges(rect.left()..=wave_left, row.y_range()),
+                    0.0,
+                    Color32::YELLOW.linear_multiply(BG_MULTIPLY),
+                );
+            }
+            painter.text(
+                pos2(rect.left() + name_width, row.center().y),
+                Align2::RIGHT_CENTER,
+                signal.s.to_string(),
+                Default::default(),
+                ui.visuals().text_color(),
+            );
+            painter.text(
+                pos2(value_left, row.center().y),
+                Align2::LEFT_CENTER,
+                value,
+                value_font.clone(),
+                strong_color,
+            );
+            if let Some(data) = wave.data.get(&signal.s.id) {
+                ui.allocate_ui_at_rect(
+                    Rect::from_x_y_ranges(wave_x.clone(), row.y_range()),
+                    |ui| {
+                        ui.set_clip_rect(ui.max_rect());
+                        self.ui_signal_wave(signal, data, info, ui);
+                    },
+                );
+            }
+        }
+        for span in &self.spans {
+            if let (Some(a), Some(b)) = (self.cursors_get(span.0), self.cursors_get(span.1)) {
+                self.paint_span(ui, wave_left, info, None, a, b);
+            }
+        }
+        if self.marker.valid {
+            self.paint_cursor(ui, wave_left, info, &self.marker);
+        }
+        for cursor in &self.cursors {
+            self.paint_cursor(ui, wave_left, info, cursor);
+        }
+    }
+}
@@ -0,0 +1,243 @@
+use crate::radix::Radix;
+use crate::view::session::{SessionCursor, SessionSignal, WaveSession};
+use crate::view::signal::{AnalogDisplayType, SignalViewMode};
+use crate::view::SIGNAL_HEIGHT_DEFAULT;
+use crate::wave::WaveTimescaleUnit;
+use anyhow::{anyhow, Result};
+use egui::Color32;
+
+// trace flags of gtkwave, see `analyzer.h` of gtkwave
+const TR_HEX: u64 = 0x2;
+const TR_DEC: u64 = 0x4;
+const TR_BIN: u64 = 0x8;
+const TR_OCT: u64 = 0x10;
+const TR_SIGNED: u64 = 0x400;
+const TR_ANALOG_STEP: u64 = 0x8000;
+const TR_ANALOG_INTERPOLATED: u64 = 0x10000;
+const TR_ANALOG_BLANK_STRETCH: u64 = 0x20000;
+
+/// Wave column width in pixels when window size is not saved
+const GTKW_WAVE_WIDTH_DEFAULT: f64 = 800.0;
+
+/// Trace colors of gtkwave indexed by `[color]`, 0 is the normal color
+fn gtkw_color(index: usize) -> Option<Color32> {
+    [
+        Color32::RED,
+        Color32::from_rgb(0xff, 0xa5, 0x00),
+        Color32::YELLOW,
+        Color32::GREEN,
+        Color32::from_rgb(0x40, 0x80, 0xff),
+        Color32::from_rgb(0x4b, 0x00, 0x82),
+        Color32::from_rgb(0xee, 0x82, 0xee),
+    ]
+    .get(index.checked_sub(1)?)
+    .copied()
+}
+
+/// Display mode of trace `flags`, signed values are shown as unsigned decimal as
+/// [Radix] has no sign
+fn gtkw_mode(flags: u64) -> SignalViewMode {
+    if flags & TR_ANALOG_STEP != 0 {
+        SignalViewMode::Analog(AnalogDisplayType::Step)
+    } else if flags & TR_ANALOG_INTERPOLATED != 0 {
+        SignalViewMode::Analog(AnalogDisplayType::Interpolated)
+    } else if flags & (TR_DEC | TR_SIGNED) != 0 {
+        SignalViewMode::Number(Radix::Dec)
+    } else if flags & TR_HEX != 0 {
+        SignalViewMode::Number(Radix::Hex)
+    } else if flags & TR_BIN != 0 {
+        SignalViewMode::Number(Radix::Bin)
+    } else if flags & TR_OCT != 0 {
+        SignalViewMode::Number(Radix::Oct)
+    } else {
+        SignalViewMode::Default
+    }
+}
+
+/// Signal path of a gtkwave trace name, e.g. `top.cnt` of `+{alias} top.cnt[7:0]`
+fn gtkw_path(name: &str) -> &str {
+    let name = match name.strip_prefix("+{").and_then(|n| n.split_once('}')) {
+        Some((_alias, name)) => name.trim(),
+        None => name,
+    };
+    // gtkwave appends bit range to vectors, which are not part of paths here
+    match name.rsplit_once('[') {
+        Some((path, range))
+            if range
+                .strip_suffix(']')
+                .and_then(|r| r.split_once(':'))
+                .map(|(msb, lsb)| msb.parse::<u64>().is_ok() && lsb.parse::<u64>().is_ok())
+                .unwrap_or(false) =>
+        {
+            path
+        }
+        _ => name,
+    }
+}
+
+impl WaveSession {
+    /// Import gtkwave save file `text` of a wave with times in `unit`.
+    /// Blank and comment traces are skipped as the view has no separators, blank traces
+    /// stretching analog traces add to their heights
+    pub fn from_gtkw(text: &str, unit: WaveTimescaleUnit) -> Result<Self> {
+        let time = |t: i64| Some(t).filter(|t| *t >= 0).map(|t| format!("{t}{unit}"));
+        let mut session = WaveSession::default();
+        let mut flags = 0;
+        let mut color = None;
+        let mut start = 0;
+        let mut zoom = None;
+        let mut markers = vec![];
+        let mut names = vec![];
+        let (mut width, mut signals_width, mut sst_width) = (None, 0.0, 0.0);
+        let mut sst_expanded = false;
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            let error = || anyhow!("invalid gtkwave save at line {}: {}", index + 1, line);
+            let number = |s: Option<&str>| -> Result<f64> {
+                s.and_then(|s| s.trim().parse().ok()).ok_or_else(error)
+            };
+            if let Some(tag) = line.strip_prefix('[') {
+                let (tag, value) = tag.split_once(']').ok_or_else(error)?;
+                let value = value.trim();
+                match tag {
+                    "dumpfile" => session.file = value.trim_matches('"').to_string(),
+                    "timestart" => start = number(Some(value))? as i64,
+                    "size" => width = Some(number(value.split_whitespace().next())?),
+                    "signals_width" => signals_width = number(Some(value))?,
+                    "sst_width" => sst_width = number(Some(value))?,
+                    "sst_expanded" => sst_expanded = value == "1",
+                    "color" => color = gtkw_color(number(Some(value))? as usize),
+                    "markername" => {
+                        let letter = value.chars().next().ok_or_else(error)?;
+                        names.push((letter, value[letter.len_utf8()..].trim().to_string()));
+                    }
+                    _ => {}
+                }
+            } else if let Some(flag) = line.strip_prefix('@') {
+                flags = u64::from_str_radix(flag, 16).map_err(|_| error())?;
+            } else if let Some(values) = line.strip_prefix('*') {
+                // zoom, marker and named markers `A` to `Z`, -1 for unused ones
+                let mut values = values.split_whitespace();
+                zoom = Some(number(values.next())?);
+                let mut times = values.map(|v| number(Some(v)).map(|t| t as i64));
+                session.marker = times.next().transpose()?.and_then(time);
+                markers = times.collect::<Result<Vec<_>>>()?;
+            } else if line.starts_with('-') {
+                if flags & TR_ANALOG_BLANK_STRETCH != 0 {
+                    if let Some(signal) = session.signals.last_mut() {
+                        signal.height += SIGNAL_HEIGHT_DEFAULT;
+                    }
+                }
+            } else if !line.is_empty() && !line.starts_with(['^', '%']) {
+                // concatenated vectors `#{name} bits..` are kept to be reported as missing
+                session.signals.push(SessionSignal {
+                    path: gtkw_path(line).to_string(),
+                    mode: gtkw_mode(flags),
+                    color: color.unwrap_or(Color32::GREEN).to_hex(),
+                    ..Default::default()
+                });
+            }
+        }
+        session.cursors = markers
+            .into_iter()
+            .zip('A'..='Z')
+            .enumerate()
+            .filter_map(|(id, (t, letter))| {
+                let name = names
+                    .iter()
+                    .find(|(l, name)| *l == letter && !name.is_empty())
+                    .map(|(_, name)| name.clone())
+                    .unwrap_or_else(|| letter.to_string());
+                Some(SessionCursor {
+                    id: id as i32,
+                    name,
+                    time: time(t)?,
+                })
+            })
+            .collect();
+        // visible range is wave column width in pixels by `2^-zoom` time per pixel
+        session.range = zoom.map(|zoom| {
+            let sst_width = if sst_expanded { sst_width } else { 0.0 };
+            let pixels = width
+                .map(|w| w - signals_width - sst_width)
+                .filter(|w| *w > 0.0)
+                .unwrap_or(GTKW_WAVE_WIDTH_DEFAULT);
+            let end = start.max(0) + (f64::powf(2.0, -zoom) * pixels) as i64;
+            (format!("{}{unit}", start.max(0)), format!("{end}{unit}"))
+        });
+        Ok(session)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::radix::Radix;
+    use crate::view::session::WaveSession;
+    use crate::view::signal::{AnalogDisplayType, SignalViewMode};
+    use crate::view::WaveView;
+    use crate::wave::vcd_parser::Vcd;
+    use crate::wave::{WaveLoader, WaveTimescaleUnit};
+    use anyhow::Result;
+    use egui::Color32;
+
+    #[test]
+    fn test_gtkw_session() -> Result<()> {
+        let text = std::fs::read_to_string("data/counter.gtkw")?;
+        let session = WaveSession::from_gtkw(&text, WaveTimescaleUnit::NS)?;
+        assert_eq!(session.file, "data/counter.vcd");
+        let paths = session
+            .signals
+            .iter()
+            .map(|s| s.path.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            vec![
+                "top.clk",
+                "top.rst",
+                "top.counter.cnt",
+                "top.counter.cnt",
+                "top.counter.cnt",
+                "top.counter.en",
+                "top.counter.done"
+            ]
+        );
+        let modes = session
+            .signals
+            .iter()
+            .map(|s| s.mode.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(modes[0], SignalViewMode::Number(Radix::Bin));
+        assert_eq!(modes[2], SignalViewMode::Number(Radix::Hex));
+        assert_eq!(modes[3], SignalViewMode::Number(Radix::Dec));
+        assert_eq!(modes[4], SignalViewMode::Analog(AnalogDisplayType::Step));
+        assert_eq!(session.signals[4].height, 90.0);
+        assert_eq!(session.signals[0].color, Color32::GREEN.to_hex());
+        assert_eq!(session.signals[1].color, Color32::RED.to_hex());
+        assert_eq!(session.signals[2].color, Color32::GREEN.to_hex());
+        // wave column is 1000 - 150 - 200 pixels of 16ns
+        assert_eq!(
+            session.range,
+            Some(("10ns".to_string(), "10410ns".to_string()))
+        );
+        assert_eq!(session.marker.as_deref(), Some("25ns"));
+        assert_eq!(session.cursors.len(), 2);
+        assert_eq!(
+            (session.cursors[0].id, session.cursors[0].name.as_str()),
+            (0, "A")
+        );
+        assert_eq!(session.cursors[1].name, "start of count");
+        assert_eq!(session.cursors[1].time, "40ns");
+
+        let mut file = std::fs::File::open("data/counter.vcd")?;
+        let wave = Vcd.load(&mut file, &|_, _| {}, None)?;
+        let mut view = WaveView::default();
+        let missing = session.apply(&mut view, &wave.info)?;
+        assert_eq!(missing, vec!["top.counter.done"]);
+        assert_eq!(view.signals.len(), 6);
+        assert_eq!(view.range, (10.0, 10410.0));
+        assert_eq!(view.cursors[1].pos, 40);
+        assert!(WaveSession::from_gtkw("@zz\ntop.clk", WaveTimescaleUnit::NS).is_err());
+        Ok(())
+    }
+}
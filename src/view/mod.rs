@@ -1,7 +1,11 @@
 pub mod cursor;
+pub mod export;
+pub mod gtkw;
+pub mod session;
 pub mod signal;
 pub mod time_bar;
 pub mod ui;
+pub mod vector;
 
 use crate::message::RvcdMsg;
 use crate::radix::Radix;
@@ -22,6 +26,14 @@ pub const MIN_SIGNAL_WIDTH: f32 = -100.0;
 pub const BG_MULTIPLY: f32 = 0.05;
 pub const TEXT_BG_MULTIPLY: f32 = 0.4;
 pub const CURSOR_NEAREST: f32 = 20.0;
+/// Spacing of hatch lines in areas where values are not recorded
+pub const NOT_RECORDED_HATCH: f32 = 6.0;
+/// Color of port values driven by device under test in extended vcd
+pub const PORT_OUTPUT_COLOR: Color32 = Color32::from_rgb(0xff, 0xa0, 0x40);
+/// Color of port values driven by both test fixture and device under test
+pub const PORT_INOUT_COLOR: Color32 = Color32::from_rgb(0xc0, 0x80, 0xff);
+/// Port values driven weaker than `strong` are dimmed by this
+pub const PORT_WEAK_MULTIPLY: f32 = 0.5;
 // pub const UI_WIDTH_OFFSET: f32 = 8.0;
 pub const UI_WIDTH_OFFSET: f32 = 16.0;
 pub const ZOOM_SIZE_MIN: f32 = 12.0;
@@ -81,6 +93,11 @@ pub struct WaveView {
     pub limit_range_left: bool,
     pub use_top_margin: bool,
     pub round_pointer: bool,
+    /// Whether to follow data appended to the loaded file
+    #[serde(skip)]
+    pub follow: bool,
+    /// Keep newest time in view when following file
+    pub follow_scroll: bool,
     #[serde(skip)]
     pub last_pointer_state: ResponsePointerState,
     #[serde(skip)]
@@ -119,6 +136,8 @@ impl Default for WaveView {
             limit_range_left: true,
             use_top_margin: true,
             round_pointer: true,
+            follow: false,
+            follow_scroll: true,
             last_pointer_state: Default::default(),
             range_seek_started: false,
             value_width_max: 0.0,
@@ -157,6 +176,16 @@ impl WaveView {
         debug!("signals: {} => {}", self.signals.len(), signals.len());
         self.signals = signals;
     }
+    /// Move view range to show the newest time, keeps view width
+    pub fn scroll_to_end(&mut self, info: &WaveInfo) {
+        let end = info.range.1 as f64;
+        let width = self.range.1 - self.range.0;
+        if width <= 0.0 {
+            self.range = (info.range.0 as f64, end);
+        } else if self.range.1 < end {
+            self.range = (end - width, end);
+        }
+    }
     /// Convert paint pos to wave position
     /// * `x`: x position to wave panel
     pub fn x_to_pos(&self, x: f32) -> u64 {
@@ -0,0 +1,289 @@
+use crate::radix::Radix;
+use crate::view::cursor::WaveCursor;
+use crate::view::signal::{SignalView, SignalViewAlign, SignalViewMode};
+use crate::view::{WaveView, SIGNAL_HEIGHT_DEFAULT};
+use crate::wave::filter::{WaveFilter, WaveTime};
+use crate::wave::{WaveInfo, WaveTimescaleUnit};
+use anyhow::{anyhow, Context, Result};
+use egui::Color32;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Signal of a saved view, referenced by hierarchical path so that it applies to
+/// another dump of the same design
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct SessionSignal {
+    /// Path joined by `.`, like `top.cpu.pc`
+    pub path: String,
+    pub height: f32,
+    pub mode: SignalViewMode,
+    /// Color in `#rrggbbaa`
+    pub color: String,
+}
+
+impl Default for SessionSignal {
+    fn default() -> Self {
+        Self {
+            path: String::new(),
+            height: SIGNAL_HEIGHT_DEFAULT,
+            mode: Default::default(),
+            color: Color32::GREEN.to_hex(),
+        }
+    }
+}
+
+/// Cursor of a saved view at a time like `120ns`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SessionCursor {
+    pub id: i32,
+    pub name: String,
+    pub time: String,
+}
+
+/// Wave view saved as a human-readable file, times carry units so that a view applies to
+/// dumps with other timescales
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct WaveSession {
+    /// Wave file the view was saved from, for reference only
+    pub file: String,
+    pub signals: Vec<SessionSignal>,
+    /// Viewer range, empty to fit the whole wave
+    pub range: Option<(String, String)>,
+    pub default_radix: Radix,
+    pub align: SignalViewAlign,
+    pub background: bool,
+    pub show_text: bool,
+    pub signal_font_size: f32,
+    pub marker: Option<String>,
+    pub cursors: Vec<SessionCursor>,
+    /// Spans between cursors by cursor ids
+    pub spans: Vec<(i32, i32)>,
+}
+
+impl Default for WaveSession {
+    fn default() -> Self {
+        let view = WaveView::default();
+        Self {
+            file: String::new(),
+            signals: vec![],
+            range: None,
+            default_radix: view.default_radix,
+            align: view.align,
+            background: view.background,
+            show_text: view.show_text,
+            signal_font_size: view.signal_font_size,
+            marker: None,
+            cursors: vec![],
+            spans: vec![],
+        }
+    }
+}
+
+impl WaveSession {
+    /// Save `view` of a wave with `info` loaded from `file`
+    pub fn from_view(view: &WaveView, info: &WaveInfo, file: &str) -> Self {
+        let time = |pos: u64| view.pos_to_time(&info.timescale, pos);
+        let range = Some(view.range)
+            .filter(|r| r.0 != 0.0 || r.1 != 0.0)
+            .map(|(left, right)| {
+                let pos = |x: f64| x.max(0.0).round() as u64;
+                (time(pos(left)), time(pos(right)))
+            });
+        Self {
+            file: file.to_string(),
+            signals: view
+                .signals
+                .iter()
+                .filter_map(|s| {
+                    Some(SessionSignal {
+                        path: info.code_paths.get(&s.s.id)?.join("."),
+                        height: s.height,
+                        mode: s.mode.clone(),
+                        color: s.color.to_hex(),
+                    })
+                })
+                .collect(),
+            range,
+            default_radix: view.default_radix.clone(),
+            align: view.align.clone(),
+            background: view.background,
+            show_text: view.show_text,
+            signal_font_size: view.signal_font_size,
+            marker: Some(&view.marker).filter(|m| m.valid).map(|m| time(m.pos)),
+            cursors: view
+                .cursors
+                .iter()
+                .filter(|c| c.valid)
+                .map(|c| SessionCursor {
+                    id: c.id,
+                    name: c.name.clone(),
+                    time: time(c.pos),
+                })
+                .collect(),
+            spans: view.spans.clone(),
+        }
+    }
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+    pub fn from_json(text: &str) -> Result<Self> {
+        Ok(serde_json::from_str(text)?)
+    }
+    /// Read session file `path`, `.gtkw` files are imported by [WaveSession::from_gtkw]
+    /// with times in `unit`
+    pub fn read(path: &str, unit: WaveTimescaleUnit) -> Result<Self> {
+        let text =
+            std::fs::read_to_string(path).with_context(|| format!("cannot read view {path}"))?;
+        match path.to_ascii_lowercase().ends_with(".gtkw") {
+            true => Self::from_gtkw(&text, unit),
+            false => Self::from_json(&text),
+        }
+        .with_context(|| format!("invalid view {path}"))
+    }
+    /// Filter keeping value changes of signals in this view only
+    pub fn filter(&self) -> WaveFilter {
+        WaveFilter {
+            patterns: self
+                .signals
+                .iter()
+                .map(|s| format!("/^{}$/", regex::escape(&s.path)))
+                .collect(),
+            ..Default::default()
+        }
+    }
+    /// Replace signals, cursors, range and display settings of `view` with this session,
+    /// signals are matched by paths and aliases in `info`, returns paths not found
+    pub fn apply(&self, view: &mut WaveView, info: &WaveInfo) -> Result<Vec<String>> {
+        let pos =
+            |text: &str| -> Result<u64> { Ok(WaveTime::parse(text)?.to_pos(&info.timescale)) };
+        let ids = info
+            .all_signal_paths()
+            .map(|(id, path)| (path.join("."), id))
+            .collect::<HashMap<_, _>>();
+        let mut signals = vec![];
+        let mut missing = vec![];
+        for signal in &self.signals {
+            let color = Color32::from_hex(&signal.color)
+                .map_err(|_| anyhow!("invalid color {} of {}", signal.color, signal.path))?;
+            match ids.get(&signal.path) {
+                Some(id) => signals.push(SignalView {
+                    height: signal.height,
+                    mode: signal.mode.clone(),
+                    color,
+                    ..SignalView::from_id(*id, info)
+                }),
+                None => missing.push(signal.path.clone()),
+            }
+        }
+        let range = match &self.range {
+            Some((left, right)) => (pos(left)? as f64, pos(right)? as f64),
+            None => (info.range.0 as f64, info.range.1 as f64),
+        };
+        let marker = match &self.marker {
+            Some(time) => WaveCursor {
+                valid: true,
+                ..WaveCursor::new(-1, pos(time)?)
+            },
+            None => WaveCursor::new(-1, 0),
+        };
+        let cursors = self
+            .cursors
+            .iter()
+            .map(|c| {
+                Ok(WaveCursor {
+                    id: c.id,
+                    pos: pos(&c.time)?,
+                    name: c.name.clone(),
+                    valid: true,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        view.signals = signals;
+        view.range = range;
+        view.default_radix = self.default_radix.clone();
+        view.align = self.align.clone();
+        view.background = self.background;
+        view.show_text = self.show_text;
+        view.signal_font_size = self.signal_font_size;
+        view.marker = marker;
+        view.cursors = cursors;
+        view.spans = self.spans.clone();
+        view.highlight_signals.clear();
+        Ok(missing)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::radix::Radix;
+    use crate::view::cursor::WaveCursor;
+    use crate::view::session::WaveSession;
+    use crate::view::signal::{SignalView, SignalViewMode};
+    use crate::view::WaveView;
+    use crate::wave::vcd_parser::Vcd;
+    use crate::wave::WaveLoader;
+    use anyhow::Result;
+    use egui::Color32;
+
+    #[test]
+    fn test_wave_session() -> Result<()> {
+        let mut file = std::fs::File::open("data/counter.vcd")?;
+        let wave = Vcd.load(&mut file, &|_, _| {}, None)?;
+        let info = &wave.info;
+        let id = |path: &str| {
+            info.code_paths
+                .iter()
+                .find(|(_, p)| p.join(".") == path)
+                .map(|(id, _)| *id)
+                .unwrap()
+        };
+        let mut view = WaveView {
+            signals: vec![
+                SignalView {
+                    mode: SignalViewMode::Number(Radix::Dec),
+                    color: Color32::RED,
+                    height: 40.0,
+                    ..SignalView::from_id(id("top.counter.cnt"), info)
+                },
+                SignalView::from_id(id("top.clk"), info),
+            ],
+            range: (0.0, 400.0),
+            spans: vec![(0, 1)],
+            cursors: vec![WaveCursor::new(0, 100), WaveCursor::new(1, 200)],
+            ..Default::default()
+        };
+        view.marker = WaveCursor {
+            valid: true,
+            ..WaveCursor::new(-1, 150)
+        };
+        let mut session = WaveSession::from_view(&view, info, "data/counter.vcd");
+        assert_eq!(session.signals[0].path, "top.counter.cnt");
+        assert_eq!(session.signals[0].color, "#ff0000ff");
+        assert!(session.to_json()?.contains("top.clk"));
+        assert_eq!(WaveSession::from_json(&session.to_json()?)?, session);
+
+        let mut applied = WaveView::default();
+        assert!(session.apply(&mut applied, info)?.is_empty());
+        assert_eq!(applied.signals, view.signals);
+        assert_eq!(applied.range, view.range);
+        assert_eq!(applied.cursors, view.cursors);
+        assert_eq!(applied.marker.pos, 150);
+        assert!(applied.marker.valid);
+        assert_eq!(applied.spans, view.spans);
+
+        session.signals[1].path = "top.missing".to_string();
+        assert_eq!(session.apply(&mut applied, info)?, vec!["top.missing"]);
+        assert_eq!(applied.signals.len(), 1);
+        // partial files are filled by defaults
+        let partial = WaveSession::from_json(r#"{"signals": [{"path": "top.clk"}]}"#)?;
+        assert!(partial.apply(&mut applied, info)?.is_empty());
+        assert_eq!(
+            applied.signals,
+            vec![SignalView::from_id(id("top.clk"), info)]
+        );
+        assert_eq!(applied.range, (info.range.0 as f64, info.range.1 as f64));
+        Ok(())
+    }
+}
@@ -3,16 +3,22 @@ use crate::radix::Radix;
 use crate::utils::{execute, get_text_size};
 use crate::verilog::VerilogGotoSource;
 use crate::view::{
-    WaveView, BG_MULTIPLY, LINE_WIDTH, MIN_SIGNAL_WIDTH, SIGNAL_HEIGHT_DEFAULT, TEXT_ROUND_OFFSET,
+    WaveView, BG_MULTIPLY, LINE_WIDTH, MIN_SIGNAL_WIDTH, NOT_RECORDED_HATCH, PORT_INOUT_COLOR,
+    PORT_OUTPUT_COLOR, PORT_WEAK_MULTIPLY, SIGNAL_HEIGHT_DEFAULT, TEXT_ROUND_OFFSET,
+};
+use crate::wave::storage::WaveColumn;
+use crate::wave::{
+    WaveDataItem, WaveDataValue, WaveInfo, WavePortDirection, WavePortValue, WaveSignalInfo,
+    WireValue,
 };
-use crate::wave::{WaveDataItem, WaveDataValue, WaveInfo, WaveSignalInfo, WireValue};
 use egui::{
-    color_picker, pos2, vec2, Align, Align2, Color32, DragValue, FontId, Label, Layout, Rect,
-    Response, Sense, Ui, Widget,
+    color_picker, pos2, vec2, Align, Align2, Color32, DragValue, FontId, Label, Layout, Painter,
+    Rect, Response, Sense, Ui, Widget,
 };
 use num_bigint::BigUint;
 use num_traits::{One, ToPrimitive, Zero};
 use once_cell::sync::Lazy;
+use std::collections::HashSet;
 use std::fmt::{Display, Formatter};
 use std::ops::RangeInclusive;
 use tracing::info;
@@ -59,6 +65,20 @@ static SIGNAL_COLOR_BG_DEFAULT: Lazy<Color32> =
     // Lazy::new(|| Color32::GREEN.linear_multiply(BG_MULTIPLY));
     Lazy::new(|| Color32::GREEN);
 
+/// Color of port value, inputs use signal color, values weaker than `strong` are dimmed
+fn port_color(value: &WavePortValue, color: Color32) -> Color32 {
+    let color = match value.direction() {
+        Some(WavePortDirection::Output) => PORT_OUTPUT_COLOR,
+        Some(WavePortDirection::Inout) => PORT_INOUT_COLOR,
+        _ => color,
+    };
+    // strength 6 is `strong`, the default drive of gates
+    match value.strengths.0.max(value.strengths.1) < 6 {
+        true => color.linear_multiply(PORT_WEAK_MULTIPLY),
+        false => color,
+    }
+}
+
 #[derive(serde::Deserialize, serde::Serialize, Default, PartialEq, Debug, Clone)]
 pub struct SignalView {
     pub s: WaveSignalInfo,
@@ -84,28 +104,18 @@ impl WaveView {
     pub(crate) fn ui_signal_wave(
         &self,
         signal: &SignalView,
-        wave_data: &[WaveDataItem],
+        wave_data: &WaveColumn,
         info: &WaveInfo,
         ui: &mut Ui,
     ) -> Response {
         let (response, painter) =
             ui.allocate_painter(ui.available_size_before_wrap(), Sense::click_and_drag());
-        // let items = wave_data.iter().filter(|i| i.id == signal.s.id);
-        let items = wave_data.iter();
         // let start_pos = self.range.0.ceil() as u64;
         let start_pos = self.range.0 as u64;
-        let start_index = wave_data.binary_search_by_key(&start_pos, |x| x.timestamp);
-        let start_items = match start_index {
-            Ok(index) | Err(index) => {
-                // FIXME: skip less items failed
-                // if index >= 1 {
-                //     items.skip(index - 1)
-                // } else {
-                //     items.skip(0)
-                // }
-                items.skip(index.min(wave_data.len() - 1))
-            }
-        };
+        // FIXME: skip less items failed
+        // let start_index = wave_data.lower_bound(start_pos).saturating_sub(1);
+        let start_index = wave_data.lower_bound(start_pos);
+        let start_items = wave_data.iter_from(start_index.min(wave_data.len().saturating_sub(1)));
         let text_color = ui.visuals().strong_text_color();
         let signal_rect_raw = response.rect;
         // strange but works...
@@ -134,17 +144,32 @@ impl WaveView {
         //     response.rect.y_range(),
         //     (LINE_WIDTH, Color32::RED),
         // );
-        let mut item_last: Option<&WaveDataItem> = None;
+        let mut item_last: Option<WaveDataItem> = None;
         let mut ignore_x_start = -1.0;
         let mut ignore_has_x = false;
+        let is_enum = info.code_enum_literals.contains_key(&signal.s.id);
         let mut paint_signal = |item_now: &WaveDataItem, item_next: &WaveDataItem| {
-            let single: bool = match &item_now.value {
-                WaveDataValue::Comp(_) => {
-                    let d = Default::default();
-                    let s = info.code_signal_info.get(&signal.s.id).unwrap_or(&d);
-                    s.width == 1
-                }
-                WaveDataValue::Raw(v) => v.len() == 1,
+            // enums, reals and strings are painted as labelled bus
+            let is_label = is_enum
+                || matches!(
+                    item_now.value,
+                    WaveDataValue::Real(_) | WaveDataValue::String(_)
+                );
+            let single: bool = !is_label
+                && match &item_now.value {
+                    WaveDataValue::Comp(_) => {
+                        let d = Default::default();
+                        let s = info.code_signal_info.get(&signal.s.id).unwrap_or(&d);
+                        s.width == 1
+                    }
+                    WaveDataValue::Raw(v) => v.len() == 1,
+                    WaveDataValue::Port(v) => v.states.len() == 1,
+                    _ => false,
+                };
+            // ports driven by device under test are painted in another color
+            let color = match &item_now.value {
+                WaveDataValue::Port(v) => port_color(v, signal.color),
+                _ => signal.color,
             };
             let width = signal_rect.width();
             let height = signal_rect.height();
@@ -172,7 +197,7 @@ impl WaveView {
                 }
                 _ => {}
             };
-            let text = item_now.value.as_radix(self.get_radix(signal));
+            let text = self.value_text(signal, &item_now.value, info);
             if rect.width() > MIN_SIGNAL_WIDTH {
                 if ignore_x_start >= 0.0 {
                     // paint a rect as ignored data
@@ -213,60 +238,43 @@ impl WaveView {
                             false => WireValue::V0,
                         },
                         WaveDataValue::Raw(v) => v[0],
+                        WaveDataValue::Port(v) => WavePortValue::state_value(v.states[0]),
+                        _ => WireValue::X,
                     };
                     match value {
                         WireValue::V0 => {
-                            painter.hline(
-                                rect.x_range(),
-                                rect.bottom(),
-                                (LINE_WIDTH, signal.color.clone()),
-                            );
-                            painter.vline(
-                                rect.left(),
-                                rect.y_range(),
-                                (LINE_WIDTH, signal.color.clone()),
-                            );
+                            painter.hline(rect.x_range(), rect.bottom(), (LINE_WIDTH, color));
+                            painter.vline(rect.left(), rect.y_range(), (LINE_WIDTH, color));
                         }
                         WireValue::V1 => {
-                            painter.hline(
-                                rect.x_range(),
-                                rect.top(),
-                                (LINE_WIDTH, signal.color.clone()),
-                            );
-                            painter.vline(
-                                rect.left(),
-                                rect.y_range(),
-                                (LINE_WIDTH, signal.color.clone()),
-                            );
+                            painter.hline(rect.x_range(), rect.top(), (LINE_WIDTH, color));
+                            painter.vline(rect.left(), rect.y_range(), (LINE_WIDTH, color));
                         }
                         WireValue::X => paint_x(),
                         WireValue::Z => paint_z(),
                     };
                 } else {
                     let number: Option<BigUint> = (&item_now.value).into();
-                    if text.contains('x') {
+                    if !is_label && text.contains('x') {
                         paint_x();
-                    } else if text.contains('z') {
+                    } else if !is_label && text.contains('z') {
                         paint_z();
                     } else {
                         match number {
-                            Some(n) if n.is_zero() => {
-                                painter.hline(
-                                    rect.x_range(),
-                                    rect.bottom(),
-                                    (LINE_WIDTH, signal.color.clone()),
-                                );
+                            // enum literals are always painted with name
+                            Some(n) if n.is_zero() && !is_label => {
+                                painter.hline(rect.x_range(), rect.bottom(), (LINE_WIDTH, color));
                             }
                             _ => {
                                 painter.rect(
                                     rect,
                                     0.0,
                                     if self.background {
-                                        signal.color.linear_multiply(BG_MULTIPLY)
+                                        color.linear_multiply(BG_MULTIPLY)
                                     } else {
                                         Color32::TRANSPARENT
                                     },
-                                    (LINE_WIDTH, signal.color.clone()),
+                                    (LINE_WIDTH, color),
                                 );
                             }
                         }
@@ -334,7 +342,7 @@ impl WaveView {
                 if ignore_x_start < 0.0 {
                     ignore_x_start = rect.left();
                 }
-                if text.contains('x') || text.contains('z') {
+                if !is_label && (text.contains('x') || text.contains('z')) {
                     ignore_has_x = true;
                 }
             }
@@ -350,10 +358,10 @@ impl WaveView {
         for item in start_items {
             // let mut done = false;
             if is_analog {
-                paint_items.push(item);
+                paint_items.push(item.clone());
             }
-            if let Some(item_last) = item_last {
-                let value_rect = paint_signal(item_last, item);
+            if let Some(item_last) = &item_last {
+                let value_rect = paint_signal(item_last, &item);
                 if value_rect == Rect::NOTHING || value_rect.left() > response.rect.right() {
                     break;
                 }
@@ -387,7 +395,7 @@ impl WaveView {
         //     paint_it();
         //     paint_it();
         // } else {
-        if let Some(item_last) = item_last {
+        if let Some(item_last) = &item_last {
             let _ = paint_signal(
                 item_last,
                 &WaveDataItem {
@@ -415,37 +423,21 @@ impl WaveView {
             );
         }
         if is_analog {
-            // draw analog
+            // draw analog, numbers and reals have analog value
+            let analog_value = |value: &WaveDataValue| match value {
+                WaveDataValue::Comp(v) => BigUint::from_bytes_le(v).to_f64(),
+                WaveDataValue::Real(v) => Some(*v),
+                _ => None,
+            };
             let mut item_last_analog: Option<&WaveDataItem> = None;
-            let mut min_value = None;
-            let mut max_value = None;
+            let mut min_value: Option<f64> = None;
+            let mut max_value: Option<f64> = None;
             let mut analog_no_value = true;
             for item in &paint_items {
-                match &item.value {
-                    WaveDataValue::Comp(v) => {
-                        analog_no_value = false;
-                        let value = BigUint::from_bytes_le(&v);
-                        let handle = |min_max_value: Option<Vec<u8>>, is_less: bool| {
-                            if min_max_value.is_none() {
-                                Some(v.clone())
-                            } else {
-                                let min_max_v =
-                                    BigUint::from_bytes_le(&min_max_value.as_ref().unwrap());
-                                if if is_less {
-                                    value < min_max_v
-                                } else {
-                                    value > min_max_v
-                                } {
-                                    Some(v.clone())
-                                } else {
-                                    min_max_value
-                                }
-                            }
-                        };
-                        min_value = handle(min_value, true);
-                        max_value = handle(max_value, false);
-                    }
-                    WaveDataValue::Raw(_) => {}
+                if let Some(value) = analog_value(&item.value) {
+                    analog_no_value = false;
+                    min_value = Some(min_value.map_or(value, |v| v.min(value)));
+                    max_value = Some(max_value.map_or(value, |v| v.max(value)));
                 }
             }
             if analog_no_value {
@@ -489,22 +481,13 @@ impl WaveView {
                             (LINE_WIDTH, Color32::RED),
                         );
                     } else {
-                        let min_value = BigUint::from_bytes_le(min_value.as_ref().unwrap());
-                        let max_value = BigUint::from_bytes_le(max_value.as_ref().unwrap());
-                        let value_now = match &item_now.value {
-                            WaveDataValue::Comp(v) => BigUint::from_bytes_le(v),
-                            WaveDataValue::Raw(_) => BigUint::zero(),
-                        };
-                        let value_next = match &item_next.value {
-                            WaveDataValue::Comp(v) => BigUint::from_bytes_le(v),
-                            WaveDataValue::Raw(_) => BigUint::zero(),
-                        };
-                        let rate = (rect.bottom() - rect.top())
-                            / (max_value.clone() - min_value.clone()).to_f32().unwrap();
-                        let y_now =
-                            rate * (max_value.clone() - value_now).to_f32().unwrap() + rect.top();
-                        let y_next =
-                            rate * (max_value.clone() - value_next).to_f32().unwrap() + rect.top();
+                        let min_value = min_value.unwrap();
+                        let max_value = max_value.unwrap();
+                        let value_now = analog_value(&item_now.value).unwrap_or_default();
+                        let value_next = analog_value(&item_next.value).unwrap_or_default();
+                        let rate = (rect.bottom() - rect.top()) / (max_value - min_value) as f32;
+                        let y_now = rate * (max_value - value_now) as f32 + rect.top();
+                        let y_next = rate * (max_value - value_next) as f32 + rect.top();
                         let step = match &signal.mode {
                             SignalViewMode::Analog(i) => match i {
                                 AnalogDisplayType::Interpolated => false,
@@ -526,7 +509,7 @@ impl WaveView {
                         }
                     }
                 };
-                for item in paint_items {
+                for item in &paint_items {
                     if let Some(item_now) = item_last_analog {
                         let item_next = item;
                         paint_analog(item_now, item_next);
@@ -545,8 +528,50 @@ impl WaveView {
                 }
             }
         }
+        // dumping turned off, values are not recorded
+        for (start, end) in &info.dump_off {
+            let end = (*end).min(info.range.1 + 1);
+            self.paint_not_recorded(&painter, signal_rect, info, (*start, end), response.rect);
+        }
         response
     }
+    /// Paint hatched area where values are not recorded between wave positions `range`
+    fn paint_not_recorded(
+        &self,
+        painter: &Painter,
+        signal_rect: Rect,
+        info: &WaveInfo,
+        range: (u64, u64),
+        clip: Rect,
+    ) {
+        let x = |pos: u64| {
+            signal_rect.left()
+                + signal_rect.width()
+                    * (pos.saturating_sub(info.range.0) as f64 / (self.range.1 - self.range.0))
+                        as f32
+        };
+        let rect = Rect::from_x_y_ranges(
+            RangeInclusive::new(x(range.0), x(range.1)),
+            signal_rect.y_range(),
+        )
+        .intersect(clip);
+        if rect.width() <= 0.0 {
+            return;
+        }
+        painter.rect_filled(rect, 0.0, Color32::GRAY.linear_multiply(BG_MULTIPLY));
+        let painter = painter.with_clip_rect(rect);
+        let mut hatch_x = rect.left() - rect.height();
+        while hatch_x < rect.right() {
+            painter.line_segment(
+                [
+                    pos2(hatch_x, rect.bottom()),
+                    pos2(hatch_x + rect.height(), rect.top()),
+                ],
+                (LINE_WIDTH, Color32::GRAY),
+            );
+            hatch_x += NOT_RECORDED_HATCH;
+        }
+    }
     /// Paint signal label
     pub(crate) fn ui_signal_label(
         &self,
@@ -651,6 +676,18 @@ impl WaveView {
             SignalViewMode::Analog(_) => Radix::Hex,
         }
     }
+    /// Value text in signal radix, enum values are shown as literal names in default mode
+    pub fn value_text(
+        &self,
+        signal: &SignalView,
+        value: &WaveDataValue,
+        info: &WaveInfo,
+    ) -> String {
+        match (&signal.mode, info.enum_literal(signal.s.id, value)) {
+            (SignalViewMode::Default, Some(literal)) => literal.to_string(),
+            _ => value.as_radix(self.get_radix(signal)),
+        }
+    }
     pub fn do_source_goto(&self, path: Vec<String>) {
         let tx = self.tx.clone();
         if let Some(tx) = tx {
@@ -696,8 +733,11 @@ impl WaveView {
     pub fn do_signal_goto(&self, path: Vec<String>, info: &WaveInfo) {
         let tx = self.tx.clone();
         if let Some(tx) = tx {
-            let paths: Vec<(u64, Vec<String>)> =
-                info.code_paths.clone().into_iter().collect::<Vec<_>>();
+            // aliases are matched too, a signal is listed once with its best match
+            let paths: Vec<(u64, Vec<String>)> = info
+                .all_signal_paths()
+                .map(|(id, path)| (id, path.clone()))
+                .collect::<Vec<_>>();
             let match_num = |a: &Vec<String>, b: &Vec<String>| {
                 let mut a = a.iter().rev();
                 let mut b = b.iter().rev();
@@ -722,6 +762,8 @@ impl WaveView {
                     .collect::<Vec<_>>();
                 matches.sort_by_key(|x| x.2);
                 matches.reverse();
+                let mut listed = HashSet::new();
+                matches.retain(|x| listed.insert(x.0));
                 info!("matches: {:?}", matches);
                 tx.send(RvcdMsg::SetGotoSignals(
                     matches.into_iter().map(|x| x.0).collect::<Vec<_>>(),
@@ -126,6 +126,12 @@ impl WaveView {
             {
                 ui.close_menu();
             }
+            if ui
+                .checkbox(&mut self.follow_scroll, t!("view.follow_scroll"))
+                .clicked()
+            {
+                ui.close_menu();
+            }
             ui.horizontal(|ui| {
                 ui.label(t!("view.value_font_size"));
                 DragValue::new(&mut self.signal_font_size)
@@ -153,6 +159,17 @@ impl WaveView {
                     warn!("no tx in view!");
                 }
             }
+            #[cfg(not(target_arch = "wasm32"))]
+            if ui
+                .checkbox(&mut self.follow, t!("view.toolbar.follow"))
+                .changed()
+            {
+                if let Some(tx) = &self.tx {
+                    tx.send(RvcdMsg::SetFollow(self.follow)).unwrap();
+                } else {
+                    warn!("no tx in view!");
+                }
+            }
             // const EDIT_WIDTH: f32 = 100.0;
             ui.label(t!("view.toolbar.from"));
             let speed_min = 0.1;
@@ -299,7 +316,7 @@ impl WaveView {
                     .iter()
                     .map(|s| {
                         wave.find_value(s.s.id, marker_value_pos)
-                            .map(|v| v.value.as_radix(self.get_radix(s)))
+                            .map(|v| self.value_text(s, &v.value, info))
                             .unwrap_or("".to_string())
                     })
                     .collect::<Vec<_>>()
@@ -0,0 +1,643 @@
+use anyhow::Result;
+use egui::epaint::{ClippedShape, Shape, TextShape};
+use egui::{Color32, FontFamily, Pos2, Rect, Stroke, Vec2};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fmt::{Display, Formatter};
+use std::io::Write;
+
+/// File format of exported wave panel image
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VectorFormat {
+    #[default]
+    Svg,
+    Pdf,
+}
+
+impl Display for VectorFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", format!("{self:?}").to_ascii_uppercase())
+    }
+}
+
+impl VectorFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            VectorFormat::Svg => "svg",
+            VectorFormat::Pdf => "pdf",
+        }
+    }
+    /// Format by extension of file `path`
+    pub fn from_path(path: &str) -> Option<Self> {
+        let extension = path.rsplit_once('.')?.1.to_ascii_lowercase();
+        [VectorFormat::Svg, VectorFormat::Pdf]
+            .into_iter()
+            .find(|f| f.extension() == extension)
+    }
+}
+
+/// Primitive of vector image, flattened from egui shapes
+#[derive(Debug, Clone, PartialEq)]
+enum VectorItem {
+    Line {
+        points: Vec<Pos2>,
+        closed: bool,
+        fill: Color32,
+        stroke: Stroke,
+    },
+    Rect {
+        rect: Rect,
+        fill: Color32,
+        stroke: Stroke,
+    },
+    Ellipse {
+        center: Pos2,
+        radius: Vec2,
+        fill: Color32,
+        stroke: Stroke,
+    },
+    /// Characters placed at their own x positions on baseline `y`
+    Text {
+        chars: Vec<(f32, char)>,
+        y: f32,
+        size: f32,
+        monospace: bool,
+        color: Color32,
+    },
+}
+
+/// Text runs of `text`, split by rows and sections, whitespaces are dropped
+fn vector_text(text: &TextShape, items: &mut Vec<VectorItem>) {
+    let galley = &text.galley;
+    for row in &galley.rows {
+        let mut section = None;
+        for glyph in &row.glyphs {
+            if glyph.chr.is_whitespace() {
+                continue;
+            }
+            let pos = text.pos + glyph.pos.to_vec2();
+            if section != Some(glyph.section_index) {
+                section = Some(glyph.section_index);
+                let Some(format) = galley
+                    .job
+                    .sections
+                    .get(glyph.section_index as usize)
+                    .map(|s| &s.format)
+                else {
+                    continue;
+                };
+                let color = match text.override_text_color.unwrap_or(format.color) {
+                    Color32::PLACEHOLDER => text.fallback_color,
+                    color => color,
+                };
+                items.push(VectorItem::Text {
+                    chars: vec![],
+                    y: pos.y,
+                    size: format.font_id.size,
+                    monospace: format.font_id.family == FontFamily::Monospace,
+                    color: color.gamma_multiply(text.opacity_factor),
+                });
+            }
+            if let Some(VectorItem::Text { chars, .. }) = items.last_mut() {
+                chars.push((pos.x, glyph.chr));
+            }
+        }
+    }
+}
+
+/// Flatten `shape` to vector items, meshes, curves and callbacks are not exported
+fn vector_items(shape: &Shape, items: &mut Vec<VectorItem>) {
+    match shape {
+        Shape::Vec(shapes) => shapes.iter().for_each(|s| vector_items(s, items)),
+        Shape::Circle(c) => items.push(VectorItem::Ellipse {
+            center: c.center,
+            radius: Vec2::splat(c.radius),
+            fill: c.fill,
+            stroke: c.stroke,
+        }),
+        Shape::Ellipse(e) => items.push(VectorItem::Ellipse {
+            center: e.center,
+            radius: e.radius,
+            fill: e.fill,
+            stroke: e.stroke,
+        }),
+        Shape::LineSegment { points, stroke } => items.push(VectorItem::Line {
+            points: points.to_vec(),
+            closed: false,
+            fill: Color32::TRANSPARENT,
+            stroke: *stroke,
+        }),
+        Shape::Path(p) => items.push(VectorItem::Line {
+            points: p.points.clone(),
+            closed: p.closed,
+            fill: p.fill,
+            stroke: p.stroke,
+        }),
+        // rects can be painted from right to left
+        Shape::Rect(r) if r.rect.is_finite() => items.push(VectorItem::Rect {
+            rect: Rect::from_two_pos(r.rect.min, r.rect.max),
+            fill: r.fill,
+            stroke: r.stroke,
+        }),
+        Shape::Text(t) => vector_text(t, items),
+        _ => {}
+    }
+}
+
+/// Number with at most two decimals
+fn vector_num(v: f32) -> String {
+    let text = format!("{v:.2}");
+    match text.trim_end_matches('0').trim_end_matches('.') {
+        "-0" => "0".to_string(),
+        text => text.to_string(),
+    }
+}
+
+/// Whether `stroke` paints anything
+fn stroke_visible(stroke: &Stroke) -> bool {
+    stroke.width > 0.0 && stroke.color.a() > 0
+}
+
+/// Painted shapes grouped by clip rect inside page, shapes out of clip rect are dropped
+fn vector_groups(shapes: &[ClippedShape], size: Vec2) -> Vec<(Rect, Vec<VectorItem>)> {
+    let page = Rect::from_min_size(Pos2::ZERO, size);
+    let mut groups: Vec<(Rect, Vec<VectorItem>)> = vec![];
+    for ClippedShape { clip_rect, shape } in shapes {
+        let clip = clip_rect.intersect(page);
+        if !clip.is_positive() || !clip.intersects(shape.visual_bounding_rect()) {
+            continue;
+        }
+        if groups.last().map(|g| g.0) != Some(clip) {
+            groups.push((clip, vec![]));
+        }
+        if let Some((_, items)) = groups.last_mut() {
+            vector_items(shape, items);
+        }
+    }
+    groups
+}
+
+fn svg_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Svg paint attribute `name` of `color`, like `fill="#00ff00" fill-opacity="0.5"`
+fn svg_paint(name: &str, color: Color32) -> String {
+    let [r, g, b, a] = color.to_srgba_unmultiplied();
+    match a {
+        0 => format!("{name}=\"none\""),
+        255 => format!("{name}=\"#{r:02x}{g:02x}{b:02x}\""),
+        a => format!(
+            "{name}=\"#{r:02x}{g:02x}{b:02x}\" {name}-opacity=\"{}\"",
+            vector_num(a as f32 / 255.0)
+        ),
+    }
+}
+
+/// Svg fill and stroke attributes
+fn svg_style(fill: Color32, stroke: &Stroke) -> String {
+    let fill = svg_paint("fill", fill);
+    match stroke_visible(stroke) {
+        true => format!(
+            "{fill} {} stroke-width=\"{}\"",
+            svg_paint("stroke", stroke.color),
+            vector_num(stroke.width)
+        ),
+        false => fill,
+    }
+}
+
+fn svg_points(points: &[Pos2]) -> String {
+    points
+        .iter()
+        .map(|p| format!("{},{}", vector_num(p.x), vector_num(p.y)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn svg_write(groups: &[(Rect, Vec<VectorItem>)], size: Vec2, writer: &mut dyn Write) -> Result<()> {
+    let (width, height) = (vector_num(size.x), vector_num(size.y));
+    writeln!(
+        writer,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" viewBox=\"0 0 {width} {height}\">"
+    )?;
+    for (index, (clip, items)) in groups.iter().enumerate() {
+        writeln!(
+            writer,
+            "<clipPath id=\"c{index}\"><rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"/></clipPath>",
+            vector_num(clip.left()),
+            vector_num(clip.top()),
+            vector_num(clip.width()),
+            vector_num(clip.height())
+        )?;
+        writeln!(writer, "<g clip-path=\"url(#c{index})\">")?;
+        for item in items {
+            match item {
+                VectorItem::Line {
+                    points,
+                    closed,
+                    fill,
+                    stroke,
+                } => writeln!(
+                    writer,
+                    "<{} points=\"{}\" {}/>",
+                    if *closed { "polygon" } else { "polyline" },
+                    svg_points(points),
+                    svg_style(*fill, stroke)
+                )?,
+                VectorItem::Rect { rect, fill, stroke } => writeln!(
+                    writer,
+                    "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" {}/>",
+                    vector_num(rect.left()),
+                    vector_num(rect.top()),
+                    vector_num(rect.width()),
+                    vector_num(rect.height()),
+                    svg_style(*fill, stroke)
+                )?,
+                VectorItem::Ellipse {
+                    center,
+                    radius,
+                    fill,
+                    stroke,
+                } => writeln!(
+                    writer,
+                    "<ellipse cx=\"{}\" cy=\"{}\" rx=\"{}\" ry=\"{}\" {}/>",
+                    vector_num(center.x),
+                    vector_num(center.y),
+                    vector_num(radius.x),
+                    vector_num(radius.y),
+                    svg_style(*fill, stroke)
+                )?,
+                VectorItem::Text {
+                    chars,
+                    y,
+                    size,
+                    monospace,
+                    color,
+                } => writeln!(
+                    writer,
+                    "<text x=\"{}\" y=\"{}\" font-family=\"{}\" font-size=\"{}\" {}>{}</text>",
+                    chars
+                        .iter()
+                        .map(|(x, _)| vector_num(*x))
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                    vector_num(*y),
+                    if *monospace {
+                        "monospace"
+                    } else {
+                        "sans-serif"
+                    },
+                    vector_num(*size),
+                    svg_paint("fill", *color),
+                    svg_escape(&chars.iter().map(|(_, c)| c).collect::<String>())
+                )?,
+            }
+        }
+        writeln!(writer, "</g>")?;
+    }
+    writeln!(writer, "</svg>")?;
+    Ok(())
+}
+
+/// Content stream of pdf page, painted in egui coordinates flipped by page transform
+struct PdfContent {
+    ops: String,
+    /// Names of graphics states setting fill (`F`) or stroke (`S`) alpha
+    states: BTreeSet<(char, u8)>,
+    fill_alpha: u8,
+    stroke_alpha: u8,
+}
+
+/// Pdf string of character, characters out of WinAnsi encoding are written as `?`
+fn pdf_char(c: char) -> String {
+    match c {
+        '(' | ')' | '\\' => format!("\\{c}"),
+        ' '..='~' => c.to_string(),
+        '\u{a0}'..='\u{ff}' => format!("\\{:03o}", c as u32),
+        _ => "?".to_string(),
+    }
+}
+
+impl PdfContent {
+    fn new(size: Vec2) -> Self {
+        Self {
+            ops: format!("1 0 0 -1 0 {} cm\n", vector_num(size.y)),
+            states: BTreeSet::new(),
+            fill_alpha: 255,
+            stroke_alpha: 255,
+        }
+    }
+    fn op(&mut self, op: &str) {
+        self.ops.push_str(op);
+        self.ops.push('\n');
+    }
+    /// Set fill or stroke color, returns false for transparent color
+    fn color(&mut self, color: Color32, stroke: bool) -> bool {
+        let [r, g, b, a] = color.to_srgba_unmultiplied();
+        if a == 0 {
+            return false;
+        }
+        let (kind, current) = match stroke {
+            true => ('S', &mut self.stroke_alpha),
+            false => ('F', &mut self.fill_alpha),
+        };
+        if *current != a {
+            *current = a;
+            self.states.insert((kind, a));
+            self.op(&format!("/{kind}{a} gs"));
+        }
+        let [r, g, b] = [r, g, b].map(|v| vector_num(v as f32 / 255.0));
+        self.op(&format!("{r} {g} {b} {}", if stroke { "RG" } else { "rg" }));
+        true
+    }
+    fn stroke(&mut self, stroke: &Stroke) -> bool {
+        if !stroke_visible(stroke) || !self.color(stroke.color, true) {
+            return false;
+        }
+        self.op(&format!("{} w", vector_num(stroke.width)));
+        true
+    }
+    fn points(&mut self, points: &[Pos2], closed: bool) {
+        for (index, p) in points.iter().enumerate() {
+            let op = if index == 0 { "m" } else { "l" };
+            self.op(&format!("{} {} {op}", vector_num(p.x), vector_num(p.y)));
+        }
+        if closed {
+            self.op("h");
+        }
+    }
+    fn rect(&mut self, rect: &Rect) {
+        self.op(&format!(
+            "{} {} {} {} re",
+            vector_num(rect.left()),
+            vector_num(rect.top()),
+            vector_num(rect.width()),
+            vector_num(rect.height())
+        ));
+    }
+    /// Ellipse approximated by four bezier curves
+    fn ellipse(&mut self, center: Pos2, radius: Vec2) {
+        const K: f32 = 0.552_284_8;
+        let (x, y, rx, ry) = (center.x, center.y, radius.x, radius.y);
+        let n = vector_num;
+        self.op(&format!("{} {} m", n(x + rx), n(y)));
+        for (c1, c2, end) in [
+            ((x + rx, y + ry * K), (x + rx * K, y + ry), (x, y + ry)),
+            ((x - rx * K, y + ry), (x - rx, y + ry * K), (x - rx, y)),
+            ((x - rx, y - ry * K), (x - rx * K, y - ry), (x, y - ry)),
+            ((x + rx * K, y - ry), (x + rx, y - ry * K), (x + rx, y)),
+        ] {
+            self.op(&format!(
+                "{} {} {} {} {} {} c",
+                n(c1.0),
+                n(c1.1),
+                n(c2.0),
+                n(c2.1),
+                n(end.0),
+                n(end.1)
+            ));
+        }
+    }
+    fn item(&mut self, item: &VectorItem) {
+        match item {
+            VectorItem::Line {
+                points,
+                closed,
+                fill,
+                stroke,
+            } => {
+                if *closed && points.len() > 2 && self.color(*fill, false) {
+                    self.points(points, true);
+                    self.op("f");
+                }
+                if self.stroke(stroke) {
+                    self.points(points, *closed);
+                    self.op("S");
+                }
+            }
+            VectorItem::Rect { rect, fill, stroke } => {
+                if self.color(*fill, false) {
+                    self.rect(rect);
+                    self.op("f");
+                }
+                if self.stroke(stroke) {
+                    self.rect(rect);
+                    self.op("S");
+                }
+            }
+            VectorItem::Ellipse {
+                center,
+                radius,
+                fill,
+                stroke,
+            } => {
+                if self.color(*fill, false) {
+                    self.ellipse(*center, *radius);
+                    self.op("f");
+                }
+                if self.stroke(stroke) {
+                    self.ellipse(*center, *radius);
+                    self.op("S");
+                }
+            }
+            VectorItem::Text {
+                chars,
+                y,
+                size,
+                monospace,
+                color,
+            } => {
+                if !self.color(*color, false) {
+                    return;
+                }
+                let font = if *monospace { "F2" } else { "F1" };
+                self.op(&format!("BT /{font} {} Tf", vector_num(*size)));
+                // text matrix flips glyphs back upright
+                for (x, c) in chars {
+                    self.op(&format!(
+                        "1 0 0 -1 {} {} Tm ({}) Tj",
+                        vector_num(*x),
+                        vector_num(*y),
+                        pdf_char(*c)
+                    ));
+                }
+                self.op("ET");
+            }
+        }
+    }
+    /// Paint `items` clipped by `clip`
+    fn group(&mut self, clip: &Rect, items: &[VectorItem]) {
+        self.op("q");
+        self.rect(clip);
+        self.op("W n");
+        items.iter().for_each(|item| self.item(item));
+        self.op("Q");
+        // alphas are restored with graphics state
+        self.fill_alpha = 255;
+        self.stroke_alpha = 255;
+    }
+}
+
+fn pdf_write(groups: &[(Rect, Vec<VectorItem>)], size: Vec2, writer: &mut dyn Write) -> Result<()> {
+    let mut content = PdfContent::new(size);
+    for (clip, items) in groups {
+        content.group(clip, items);
+    }
+    let states = content
+        .states
+        .iter()
+        .map(|(kind, a)| {
+            let key = if *kind == 'S' { "CA" } else { "ca" };
+            format!("/{kind}{a} << /{key} {} >>", vector_num(*a as f32 / 255.0))
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    let objects = [
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+        format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] \
+            /Resources << /Font << /F1 5 0 R /F2 6 0 R >> /ExtGState << {states} >> >> \
+            /Contents 4 0 R >>",
+            vector_num(size.x),
+            vector_num(size.y)
+        ),
+        format!(
+            "<< /Length {} >>\nstream\n{}endstream",
+            content.ops.len(),
+            content.ops
+        ),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>"
+            .to_string(),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Courier /Encoding /WinAnsiEncoding >>"
+            .to_string(),
+    ];
+    let mut pdf = b"%PDF-1.4\n".to_vec();
+    let mut offsets = vec![];
+    for (index, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        write!(pdf, "{} 0 obj\n{object}\nendobj\n", index + 1)?;
+    }
+    let xref = pdf.len();
+    write!(pdf, "xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1)?;
+    for offset in offsets {
+        writeln!(pdf, "{offset:010} 00000 n ")?;
+    }
+    write!(
+        pdf,
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref}\n%%EOF\n",
+        objects.len() + 1
+    )?;
+    writer.write_all(&pdf)?;
+    Ok(())
+}
+
+/// Write `shapes` painted by egui on a page of `size` points as svg or pdf image.
+/// Texts use generic fonts placed at egui glyph positions, meshes and curves are not written
+pub fn vector_write(
+    shapes: &[ClippedShape],
+    size: Vec2,
+    format: VectorFormat,
+    writer: &mut dyn Write,
+) -> Result<()> {
+    let groups = vector_groups(shapes, size);
+    match format {
+        VectorFormat::Svg => svg_write(&groups, size, writer),
+        VectorFormat::Pdf => pdf_write(&groups, size, writer),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::view::vector::{vector_write, VectorFormat};
+    use anyhow::Result;
+    use egui::{pos2, vec2, Align2, CentralPanel, Color32, Context, FontId, Frame, RawInput, Rect};
+
+    #[test]
+    fn test_vector_write() -> Result<()> {
+        let size = vec2(200.0, 100.0);
+        let ctx = Context::default();
+        let output = ctx.run(
+            RawInput {
+                screen_rect: Some(Rect::from_min_size(pos2(0.0, 0.0), size)),
+                ..Default::default()
+            },
+            |ctx| {
+                CentralPanel::default()
+                    .frame(Frame::none())
+                    .show(ctx, |ui| {
+                        let painter = ui.painter();
+                        painter.line_segment(
+                            [pos2(10.0, 10.0), pos2(50.0, 10.0)],
+                            (1.5, Color32::GREEN),
+                        );
+                        painter.rect_filled(
+                            Rect::from_min_max(pos2(60.0, 20.0), pos2(40.0, 40.0)),
+                            0.0,
+                            Color32::BLUE.linear_multiply(0.5),
+                        );
+                        painter.text(
+                            pos2(10.0, 50.0),
+                            Align2::LEFT_TOP,
+                            "a(b)",
+                            FontId::monospace(12.0),
+                            Color32::WHITE,
+                        );
+                        // out of page
+                        painter.text(
+                            pos2(300.0, 50.0),
+                            Align2::LEFT_TOP,
+                            "hidden",
+                            FontId::monospace(12.0),
+                            Color32::WHITE,
+                        );
+                    });
+            },
+        );
+        let write = |format| -> Result<String> {
+            let mut data = vec![];
+            vector_write(&output.shapes, size, format, &mut data)?;
+            Ok(String::from_utf8(data)?)
+        };
+        let svg = write(VectorFormat::Svg)?;
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains(
+            "<polyline points=\"10,10 50,10\" fill=\"none\" stroke=\"#00ff00\" stroke-width=\"1.5\"/>"
+        ));
+        assert!(svg.contains("<rect x=\"40\" y=\"20\" width=\"20\" height=\"20\" fill=\"#0000ff\""));
+        assert!(
+            svg.contains("font-family=\"monospace\" font-size=\"12\" fill=\"#ffffff\">a(b)</text>")
+        );
+        assert!(!svg.contains("hidden"));
+        let pdf = write(VectorFormat::Pdf)?;
+        assert!(pdf.starts_with("%PDF-1.4\n"));
+        assert!(pdf.contains("/MediaBox [0 0 200 100]"));
+        assert!(pdf.contains("10 10 m\n50 10 l\nS"));
+        assert!(pdf.contains("(\\()"));
+        // cross reference points to objects
+        let xref: usize = pdf
+            .rsplit("startxref\n")
+            .next()
+            .unwrap()
+            .lines()
+            .next()
+            .unwrap()
+            .parse()?;
+        assert!(pdf[xref..].starts_with("xref\n0 7\n"));
+        for line in pdf[xref..].lines().skip(3).take(6) {
+            let offset: usize = line[..10].parse()?;
+            assert!(pdf[offset..].starts_with(char::is_numeric));
+            assert!(pdf[offset..]
+                .split_once('\n')
+                .unwrap()
+                .0
+                .ends_with(" 0 obj"));
+        }
+        assert!(pdf.ends_with("%%EOF\n"));
+        Ok(())
+    }
+}
@@ -0,0 +1,285 @@
+use crate::wave::storage::WaveColumn;
+use crate::wave::{Wave, WaveInfo, WaveSignalInfo, WaveTimescaleUnit, WaveTreeNode};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::iter::Peekable;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use tracing::info;
+use trees::Tree;
+
+/// Magic and format version of wave cache files, bump version when layout changes
+pub const WAVE_CACHE_MAGIC: &[u8; 8] = b"RVCDWC04";
+
+/// Files smaller than this are parsed quickly enough and not cached
+pub const WAVE_CACHE_MIN_SIZE: u64 = 1024 * 1024;
+
+/// Identifies a wave file, cache is valid while source path, size and modified time keep same
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct WaveCacheKey {
+    pub path: String,
+    pub size: u64,
+    /// Modified time in nanoseconds from unix epoch
+    pub modified: u64,
+}
+
+impl WaveCacheKey {
+    pub fn new(path: &str) -> Result<Self> {
+        let path = std::fs::canonicalize(path)?;
+        let metadata = std::fs::metadata(&path)?;
+        Ok(Self {
+            path: path.to_string_lossy().to_string(),
+            size: metadata.len(),
+            modified: metadata.modified()?.duration_since(UNIX_EPOCH)?.as_nanos() as u64,
+        })
+    }
+    /// Cache file for this source in [wave_cache_dir], one cache file per source path
+    pub fn cache_path(&self) -> PathBuf {
+        // fnv-1a, keeps cache file names stable between builds
+        let hash = self.path.bytes().fold(0xcbf29ce484222325u64, |h, b| {
+            (h ^ b as u64).wrapping_mul(0x100000001b3)
+        });
+        wave_cache_dir().join(format!("{hash:016x}.wave"))
+    }
+}
+
+/// Directory to store wave caches, e.g. `~/.cache/rvcd`
+pub fn wave_cache_dir() -> PathBuf {
+    std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("LOCALAPPDATA").map(PathBuf::from))
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+        .unwrap_or_else(std::env::temp_dir)
+        .join("rvcd")
+}
+
+pub(crate) fn write_u64(w: &mut dyn Write, value: u64) -> Result<()> {
+    w.write_all(&value.to_le_bytes())?;
+    Ok(())
+}
+
+pub(crate) fn read_u64(r: &mut dyn Read) -> Result<u64> {
+    let mut buf = [0u8; 8];
+    r.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+pub(crate) fn write_bytes(w: &mut dyn Write, data: &[u8]) -> Result<()> {
+    write_u64(w, data.len() as u64)?;
+    w.write_all(data)?;
+    Ok(())
+}
+
+pub(crate) fn read_bytes(r: &mut dyn Read) -> Result<Vec<u8>> {
+    let len = read_u64(r)?;
+    let mut data = vec![];
+    r.take(len).read_to_end(&mut data)?;
+    if data.len() as u64 != len {
+        return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+    }
+    Ok(data)
+}
+
+/// [WaveInfo] with tree flattened, small enough to store as json
+#[derive(Serialize, Deserialize)]
+struct WaveCacheInfo {
+    key: WaveCacheKey,
+    /// Bytes consumed by parser, to follow vcd file after loading from cache
+    consumed: Option<u64>,
+    timescale: (u64, WaveTimescaleUnit),
+    range: (u64, u64),
+    headers: HashMap<String, String>,
+    code_signal_info: HashMap<u64, WaveSignalInfo>,
+    code_paths: HashMap<u64, Vec<String>>,
+    code_aliases: HashMap<u64, Vec<Vec<String>>>,
+    code_enum_literals: HashMap<u64, Vec<String>>,
+    /// Tree nodes in pre-order with depth
+    tree: Vec<(usize, WaveTreeNode)>,
+    dump_off: Vec<(u64, u64)>,
+}
+
+fn tree_flatten(
+    node: &trees::Node<WaveTreeNode>,
+    depth: usize,
+    nodes: &mut Vec<(usize, WaveTreeNode)>,
+) {
+    nodes.push((depth, node.data().clone()));
+    for child in node.iter() {
+        tree_flatten(child, depth + 1, nodes);
+    }
+}
+
+fn tree_build<I>(nodes: &mut Peekable<I>, depth: usize, data: WaveTreeNode) -> Tree<WaveTreeNode>
+where
+    I: Iterator<Item = (usize, WaveTreeNode)>,
+{
+    let mut tree = Tree::new(data);
+    while let Some((child_depth, _)) = nodes.peek() {
+        if *child_depth != depth + 1 {
+            break;
+        }
+        if let Some((child_depth, child)) = nodes.next() {
+            tree.push_back(tree_build(nodes, child_depth, child));
+        }
+    }
+    tree
+}
+
+/// Store parsed wave to `path`, written to a temporary file first so that
+/// a broken cache is never left behind
+pub fn wave_cache_save(
+    path: &Path,
+    key: &WaveCacheKey,
+    wave: &Wave,
+    consumed: Option<u64>,
+) -> Result<()> {
+    let time_start = std::time::Instant::now();
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut tree = vec![];
+    tree_flatten(wave.info.tree.root(), 0, &mut tree);
+    let info = WaveCacheInfo {
+        key: key.clone(),
+        consumed,
+        timescale: wave.info.timescale,
+        range: wave.info.range,
+        headers: wave.info.headers.clone(),
+        code_signal_info: wave.info.code_signal_info.clone(),
+        code_paths: wave.info.code_paths.clone(),
+        code_aliases: wave.info.code_aliases.clone(),
+        code_enum_literals: wave.info.code_enum_literals.clone(),
+        tree,
+        dump_off: wave.info.dump_off.clone(),
+    };
+    let temp = path.with_extension("tmp");
+    {
+        let mut w = BufWriter::new(File::create(&temp)?);
+        w.write_all(WAVE_CACHE_MAGIC)?;
+        write_bytes(&mut w, &serde_json::to_vec(&info)?)?;
+        write_u64(&mut w, wave.data.len() as u64)?;
+        for (id, column) in &wave.data {
+            write_u64(&mut w, *id)?;
+            column.write_to(&mut w)?;
+        }
+        w.flush()?;
+    }
+    std::fs::rename(&temp, path)?;
+    info!(
+        "wave cache saved to {:?}, used {} ms",
+        path,
+        time_start.elapsed().as_millis()
+    );
+    Ok(())
+}
+
+/// Load wave cached in `path`, returns wave and bytes consumed by parser.
+/// `None` if cache not exists or not made from the same file
+pub fn wave_cache_load(path: &Path, key: &WaveCacheKey) -> Result<Option<(Wave, Option<u64>)>> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let mut r = BufReader::new(file);
+    let mut magic = [0u8; WAVE_CACHE_MAGIC.len()];
+    if r.read_exact(&mut magic).is_err() || &magic != WAVE_CACHE_MAGIC {
+        return Ok(None);
+    }
+    let info: WaveCacheInfo = serde_json::from_slice(&read_bytes(&mut r)?)?;
+    if &info.key != key {
+        return Ok(None);
+    }
+    let mut data = HashMap::new();
+    for _ in 0..read_u64(&mut r)? {
+        let id = read_u64(&mut r)?;
+        data.insert(id, WaveColumn::read_from(&mut r)?);
+    }
+    let mut nodes = info.tree.into_iter().peekable();
+    let tree = match nodes.next() {
+        Some((depth, root)) => tree_build(&mut nodes, depth, root),
+        None => Tree::new(WaveTreeNode::WaveRoot),
+    };
+    let wave = Wave {
+        info: WaveInfo {
+            timescale: info.timescale,
+            range: info.range,
+            headers: info.headers,
+            code_signal_info: info.code_signal_info,
+            code_paths: info.code_paths,
+            code_aliases: info.code_aliases,
+            code_enum_literals: info.code_enum_literals,
+            tree,
+            dump_off: info.dump_off,
+        },
+        data,
+        selected: None,
+    };
+    Ok(Some((wave, info.consumed)))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::wave::cache::{wave_cache_load, wave_cache_save, WaveCacheKey};
+    use crate::wave::ghw_parser::Ghw;
+    use crate::wave::utils::Node;
+    use crate::wave::vcd_parser::Vcd;
+    use crate::wave::{Wave, WaveLoader};
+    use anyhow::Result;
+    use std::fs::File;
+
+    #[test]
+    fn test_wave_cache() -> Result<()> {
+        let tree_json = |wave: &Wave| serde_json::to_string(&Node(wave.info.tree.root())).unwrap();
+        for (file, wave) in [
+            (
+                "data/cpu_ila_commit.vcd",
+                Vcd.load(
+                    &mut File::open("data/cpu_ila_commit.vcd")?,
+                    &|_, _| {},
+                    None,
+                )?,
+            ),
+            (
+                "data/fsm.ghw",
+                Ghw.load(&mut File::open("data/fsm.ghw")?, &|_, _| {}, None)?,
+            ),
+        ] {
+            let key = WaveCacheKey::new(file)?;
+            let path = std::env::temp_dir()
+                .join(format!("rvcd-test-{}", std::process::id()))
+                .join(key.cache_path().file_name().unwrap());
+            wave_cache_save(&path, &key, &wave, Some(123))?;
+            let (cached, consumed) = wave_cache_load(&path, &key)?.unwrap();
+            assert_eq!(consumed, Some(123));
+            assert_eq!(cached.info.range, wave.info.range);
+            assert_eq!(cached.info.code_paths, wave.info.code_paths);
+            assert_eq!(cached.info.code_enum_literals, wave.info.code_enum_literals);
+            assert_eq!(tree_json(&cached), tree_json(&wave));
+            assert_eq!(cached.data.len(), wave.data.len());
+            for (id, column) in wave.data.iter() {
+                assert_eq!(
+                    cached
+                        .data
+                        .get(id)
+                        .unwrap()
+                        .iter()
+                        .map(|i| i.to_string())
+                        .collect::<Vec<_>>(),
+                    column.iter().map(|i| i.to_string()).collect::<Vec<_>>()
+                );
+            }
+            // a modified file invalidates cache
+            let modified = WaveCacheKey {
+                size: key.size + 1,
+                ..key.clone()
+            };
+            assert!(wave_cache_load(&path, &modified)?.is_none());
+            std::fs::remove_dir_all(path.parent().unwrap())?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,624 @@
+use crate::radix::{radix_value_big_uint, Radix};
+use crate::wave::filter::{WaveFilter, WaveTime};
+use crate::wave::storage::WaveColumn;
+use crate::wave::utils::WaveTreeBuilder;
+use crate::wave::vcd_parser::vcd_column_push;
+use crate::wave::{
+    Wave, WaveDataItem, WaveDataValue, WaveInfo, WaveLoader, WaveScopeType, WaveSignalInfo,
+    WaveSignalType, WaveSource, WaveTimescaleUnit, WireValue,
+};
+use anyhow::{anyhow, Result};
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read};
+use tracing::info;
+
+/// First header of csv exported by Vivado ILA
+pub const ILA_CSV_MAGIC: &str = "Sample in Buffer";
+
+/// Prefix of the radix row below header in Vivado ILA csv
+const ILA_RADIX_PREFIX: &str = "Radix - ";
+
+/// Headers taken as time column when [CsvOptions::time_column] is empty
+const CSV_TIME_COLUMNS: &[&str] = &["time", "timestamp", "t", "sample in buffer"];
+
+/// How to read a csv wave
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct CsvOptions {
+    /// Header of time column, empty to find `time` / `timestamp` or the sample index of ILA
+    pub time_column: String,
+    /// Time between samples without time column, and unit of time column values,
+    /// like `10ns`. Headers like `time (us)` give unit of time column themselves
+    pub period: String,
+    /// Radix of values if not given by file
+    pub radix: Radix,
+    /// Characters splitting signal names into scopes
+    pub separators: String,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            time_column: "".to_string(),
+            period: "1ns".to_string(),
+            radix: Radix::Hex,
+            separators: "/.".to_string(),
+        }
+    }
+}
+
+/// Loader for waves sampled into csv, one column per signal and one row per sample,
+/// like Vivado ILA exports or logic analyzer scripts
+#[derive(Default)]
+pub struct Csv {
+    pub options: CsvOptions,
+}
+
+/// Check if `data` starts with header of Vivado ILA csv, other csv files are found by extension
+pub fn is_ila_csv(data: &[u8]) -> bool {
+    data.starts_with(ILA_CSV_MAGIC.as_bytes())
+}
+
+/// Split csv line into cells, cells can be quoted with `"`
+fn csv_split(line: &str, delimiter: char) -> Vec<String> {
+    let mut cells = vec![];
+    let mut cell = String::new();
+    let mut quoted = false;
+    let mut chars = line.trim_end_matches(['\r', '\n']).chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                cell.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            c if c == delimiter && !quoted => cells.push(std::mem::take(&mut cell)),
+            c => cell.push(c),
+        }
+    }
+    cells.push(cell);
+    cells.into_iter().map(|c| c.trim().to_string()).collect()
+}
+
+/// Delimiter used most in header line, one of `,`, `;` or tab
+fn csv_delimiter(header: &str) -> char {
+    [',', ';', '\t']
+        .into_iter()
+        .max_by_key(|d| header.matches(*d).count())
+        .unwrap()
+}
+
+/// Split header like `time (ns)` or `time[ns]` into name and time unit
+fn csv_time_header(header: &str) -> (String, Option<WaveTimescaleUnit>) {
+    let header = header.trim();
+    for (open, close) in [('(', ')'), ('[', ']')] {
+        if let Some(rest) = header.strip_suffix(close) {
+            if let Some((name, unit)) = rest.rsplit_once(open) {
+                if let Some(unit) = WaveTimescaleUnit::parse(unit.trim()) {
+                    return (name.trim().to_string(), Some(unit));
+                }
+            }
+        }
+    }
+    (header.to_string(), None)
+}
+
+/// Split signal header like `top/cpu/pc[31:0]` into path and width given by range
+fn csv_signal_path(header: &str, separators: &str) -> (Vec<String>, Option<u64>) {
+    let mut name = header.trim();
+    let mut width = None;
+    if let Some((left, range)) = name
+        .strip_suffix(']')
+        .and_then(|rest| rest.rsplit_once('['))
+    {
+        if let Some((msb, lsb)) = range.split_once(':') {
+            if let (Ok(msb), Ok(lsb)) = (msb.trim().parse::<u64>(), lsb.trim().parse::<u64>()) {
+                name = left;
+                width = Some(msb.abs_diff(lsb) + 1);
+            }
+        }
+    }
+    let path = name
+        .split(|c| separators.contains(c))
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_string())
+        .collect::<Vec<_>>();
+    match path.is_empty() {
+        true => (vec![header.to_string()], width),
+        false => (path, width),
+    }
+}
+
+/// Radix of column given in Vivado ILA radix row
+fn csv_ila_radix(text: &str) -> Option<Radix> {
+    match text.trim().to_ascii_uppercase().as_str() {
+        "HEX" => Some(Radix::Hex),
+        "BINARY" => Some(Radix::Bin),
+        "OCTAL" => Some(Radix::Oct),
+        "UNSIGNED" | "SIGNED" => Some(Radix::Dec),
+        _ => None,
+    }
+}
+
+fn csv_wire_value(c: char) -> Option<WireValue> {
+    match c {
+        '0' => Some(WireValue::V0),
+        '1' => Some(WireValue::V1),
+        'x' | 'X' => Some(WireValue::X),
+        'z' | 'Z' => Some(WireValue::Z),
+        _ => None,
+    }
+}
+
+/// Value in a csv cell
+#[derive(Debug, PartialEq)]
+enum CsvCell {
+    /// Wires msb first, as many as written for binary and as needed for other radixes
+    Bits(Vec<WireValue>),
+    /// Magnitude of negative decimal, stored as two's complement in signal width
+    Negative(BigUint),
+    Real(f64),
+}
+
+impl CsvCell {
+    fn parse(text: &str, radix: &Radix) -> Result<Self> {
+        let (digits, radix) = match text.get(..2).map(|p| p.to_ascii_lowercase()) {
+            Some(p) if p == "0x" => (&text[2..], Radix::Hex),
+            Some(p) if p == "0b" => (&text[2..], Radix::Bin),
+            _ => (text, radix.clone()),
+        };
+        let digits = digits.replace('_', "");
+        let bits = match radix {
+            Radix::Dec => match digits.strip_prefix('-') {
+                Some(magnitude) => {
+                    BigUint::parse_bytes(magnitude.as_bytes(), 10).map(Self::Negative)
+                }
+                None => BigUint::parse_bytes(digits.as_bytes(), 10)
+                    .map(|v| Self::Bits(csv_biguint_bits(&v))),
+            },
+            Radix::Bin => digits
+                .chars()
+                .map(csv_wire_value)
+                .collect::<Option<Vec<_>>>()
+                .map(Self::Bits),
+            Radix::Oct | Radix::Hex => {
+                let n = if radix == Radix::Hex { 4 } else { 3 };
+                let mut wires = vec![];
+                let mut valid = true;
+                for c in digits.chars() {
+                    match (csv_wire_value(c), c.to_digit(radix.to_number() as u32)) {
+                        (Some(w), _) if w == WireValue::X || w == WireValue::Z => {
+                            wires.extend(vec![w; n])
+                        }
+                        (_, Some(d)) => wires.extend((0..n).rev().map(|i| match (d >> i) & 1 {
+                            0 => WireValue::V0,
+                            _ => WireValue::V1,
+                        })),
+                        _ => valid = false,
+                    }
+                }
+                (valid && !digits.is_empty()).then(|| Self::Bits(csv_trim_bits(wires)))
+            }
+        };
+        match bits {
+            Some(bits) if !digits.is_empty() => Ok(bits),
+            _ => text
+                .parse()
+                .map(Self::Real)
+                .map_err(|_| anyhow!("invalid value {}", text)),
+        }
+    }
+    /// Value of cell in a real column, x and z are taken as 1
+    fn real(&self) -> f64 {
+        let integer = |v: BigUint| v.to_string().parse().unwrap_or(f64::NAN);
+        match self {
+            CsvCell::Bits(bits) => integer(radix_value_big_uint(
+                &bits.iter().rev().cloned().collect::<Vec<_>>(),
+            )),
+            CsvCell::Negative(magnitude) => -integer(magnitude.clone()),
+            CsvCell::Real(v) => *v,
+        }
+    }
+    /// Bits needed to store this value
+    fn width(&self) -> u64 {
+        match self {
+            CsvCell::Bits(bits) => bits.len() as u64,
+            CsvCell::Negative(magnitude) => magnitude.bits() + 1,
+            CsvCell::Real(_) => 64,
+        }
+    }
+    /// Value of signal in `width` bits
+    fn value(self, width: u64) -> WaveDataValue {
+        let width = width as usize;
+        let mut bits = match self {
+            CsvCell::Bits(bits) => bits,
+            CsvCell::Negative(magnitude) => {
+                let modulus = BigUint::from(1u8) << width;
+                csv_biguint_bits(&((&modulus - magnitude % &modulus) % &modulus))
+            }
+            CsvCell::Real(v) => return WaveDataValue::Real(v),
+        };
+        if bits.len() > width {
+            bits.drain(..bits.len() - width);
+        } else if bits.len() < width {
+            // extend x and z like vcd
+            let fill = match bits.first() {
+                Some(WireValue::X) => WireValue::X,
+                Some(WireValue::Z) => WireValue::Z,
+                _ => WireValue::V0,
+            };
+            let mut extended = vec![fill; width - bits.len()];
+            extended.extend(bits);
+            bits = extended;
+        }
+        WaveDataValue::Raw(bits)
+    }
+}
+
+/// Bits of `value` msb first, at least one bit
+fn csv_biguint_bits(value: &BigUint) -> Vec<WireValue> {
+    value
+        .to_str_radix(2)
+        .chars()
+        .filter_map(csv_wire_value)
+        .collect()
+}
+
+/// Remove leading zeros, keeping at least one bit
+fn csv_trim_bits(mut bits: Vec<WireValue>) -> Vec<WireValue> {
+    let zeros = bits
+        .iter()
+        .take_while(|b| **b == WireValue::V0)
+        .count()
+        .min(bits.len().saturating_sub(1));
+    bits.drain(..zeros);
+    bits
+}
+
+/// Changes of one csv column, cells equal to previous row are skipped
+struct CsvColumn {
+    path: Vec<String>,
+    width: Option<u64>,
+    radix: Radix,
+    changes: Vec<(u64, String)>,
+}
+
+impl Csv {
+    pub fn new(options: CsvOptions) -> Self {
+        Self { options }
+    }
+    /// Load csv, keeping value changes of signals and time window selected by `filter` only
+    pub fn load_filtered(&self, reader: &mut dyn Read, filter: &WaveFilter) -> Result<Wave> {
+        info!("start parsing csv file");
+        #[cfg(not(target_arch = "wasm32"))]
+        let perf_start = std::time::Instant::now();
+        let period = WaveTime::parse(&self.options.period)?;
+        let timescale = (
+            period.value.max(1),
+            period.unit.unwrap_or(WaveTimescaleUnit::NS),
+        );
+        let mut lines = BufReader::new(reader).lines();
+        let header = loop {
+            match lines.next() {
+                Some(line) if line.as_ref().map(|l| l.trim().is_empty()).unwrap_or(false) => {}
+                Some(line) => break line?,
+                None => return Err(anyhow!("csv file without header")),
+            }
+        };
+        let delimiter = csv_delimiter(&header);
+        let headers = csv_split(header.trim_start_matches('\u{feff}'), delimiter);
+        let time_column = headers.iter().position(|h| {
+            let (name, _) = csv_time_header(h);
+            match self.options.time_column.trim() {
+                "" => CSV_TIME_COLUMNS.contains(&name.to_ascii_lowercase().as_str()),
+                column => name.eq_ignore_ascii_case(column) || h.eq_ignore_ascii_case(column),
+            }
+        });
+        if time_column.is_none() && !self.options.time_column.trim().is_empty() {
+            return Err(anyhow!(
+                "time column {} not found",
+                self.options.time_column
+            ));
+        }
+        // time column values in timescale units
+        let time_scale = match time_column.and_then(|c| csv_time_header(&headers[c]).1) {
+            Some(unit) => {
+                unit.femtoseconds() as f64 / (timescale.0 * timescale.1.femtoseconds()) as f64
+            }
+            None => 1.0,
+        };
+        let mut columns = headers
+            .iter()
+            .map(|h| {
+                let (path, width) = csv_signal_path(h, &self.options.separators);
+                CsvColumn {
+                    path,
+                    width,
+                    radix: self.options.radix.clone(),
+                    changes: vec![],
+                }
+            })
+            .collect::<Vec<_>>();
+        let mut range: Option<(u64, u64)> = None;
+        let mut last: Option<Vec<String>> = None;
+        let mut sample = 0u64;
+        for (index, line) in lines.enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let cells = csv_split(&line, delimiter);
+            if let Some(radixes) = cells.first().and_then(|c| c.strip_prefix(ILA_RADIX_PREFIX)) {
+                let radixes = std::iter::once(radixes).chain(cells[1..].iter().map(|c| c.as_str()));
+                for (column, radix) in columns.iter_mut().zip(radixes) {
+                    if let Some(radix) = csv_ila_radix(radix) {
+                        column.radix = radix;
+                    }
+                }
+                continue;
+            }
+            if cells.len() != columns.len() {
+                return Err(anyhow!(
+                    "line {} has {} cells, but header has {}",
+                    index + 2,
+                    cells.len(),
+                    columns.len()
+                ));
+            }
+            let timestamp = match time_column {
+                Some(c) => {
+                    let time: f64 = cells[c]
+                        .parse()
+                        .map_err(|_| anyhow!("invalid time {} at line {}", cells[c], index + 2))?;
+                    (time * time_scale).round().max(0.0) as u64
+                }
+                None => sample,
+            };
+            sample += 1;
+            range = match range {
+                Some((_, stop)) if timestamp < stop => {
+                    return Err(anyhow!(
+                        "time goes backwards from {} to {} at line {}",
+                        stop,
+                        timestamp,
+                        index + 2
+                    ))
+                }
+                Some((start, _)) => Some((start, timestamp)),
+                None => Some((timestamp, timestamp)),
+            };
+            for (i, (column, cell)) in columns.iter_mut().zip(cells.iter()).enumerate() {
+                if Some(i) == time_column || last.as_ref().map(|l| &l[i] == cell).unwrap_or(false) {
+                    continue;
+                }
+                column.changes.push((timestamp, cell.to_string()));
+            }
+            last = Some(cells);
+        }
+        let mut builder = WaveTreeBuilder::new();
+        let mut cells = HashMap::new();
+        for (id, column) in columns.into_iter().enumerate() {
+            if Some(id) == time_column {
+                continue;
+            }
+            let id = id as u64;
+            let mut changes = column
+                .changes
+                .iter()
+                .map(|(t, text)| CsvCell::parse(text, &column.radix).map(|cell| (*t, cell)))
+                .collect::<Result<Vec<_>>>()?;
+            let is_real = changes.iter().any(|(_, c)| matches!(c, CsvCell::Real(_)));
+            if is_real {
+                // integers in real column are decimal, like `1` next to `0.5`
+                for ((_, cell), (_, text)) in changes.iter_mut().zip(column.changes.iter()) {
+                    *cell = CsvCell::Real(text.parse().unwrap_or_else(|_| cell.real()));
+                }
+            }
+            let width = match is_real {
+                true => 64,
+                false => column
+                    .width
+                    .unwrap_or_else(|| changes.iter().map(|(_, c)| c.width()).max().unwrap_or(1)),
+            };
+            let (name, scopes) = column.path.split_last().unwrap();
+            for scope in scopes {
+                builder.push_scope(scope.to_string(), WaveScopeType::Module);
+            }
+            builder.add_var(WaveSignalInfo {
+                id,
+                name: name.to_string(),
+                width,
+                typ: match is_real {
+                    true => WaveSignalType::Real,
+                    false => WaveSignalType::Wire,
+                },
+                direction: None,
+            });
+            for _ in scopes {
+                builder.pop_scope()?;
+            }
+            cells.insert(id, (width, changes));
+        }
+        let (code_signal_info, code_paths, code_aliases, tree) = builder.finish();
+        let mut headers = HashMap::new();
+        headers.insert("comment".to_string(), "csv".to_string());
+        let mut info = WaveInfo {
+            timescale,
+            range: range.unwrap_or_default(),
+            headers,
+            code_signal_info,
+            code_paths,
+            code_aliases,
+            code_enum_literals: HashMap::new(),
+            tree,
+            dump_off: vec![],
+        };
+        let selected = filter.select(&info)?;
+        let mut window = filter.window(&info.timescale);
+        let mut data: HashMap<u64, WaveColumn> = HashMap::new();
+        for (id, (width, changes)) in cells {
+            if selected.as_ref().map(|s| !s.contains(&id)).unwrap_or(false) {
+                continue;
+            }
+            for (timestamp, cell) in changes {
+                let item = WaveDataItem {
+                    value: cell.value(width),
+                    timestamp,
+                }
+                .compress()?;
+                vcd_column_push(&mut data, &mut window, &info, id, item, false)?;
+            }
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        info!("parse csv use time: {:?}", perf_start.elapsed());
+        if let Some(window) = window {
+            info.range = window.range(info.range);
+            window.finish(&mut data, &info)?;
+        }
+        Ok(Wave {
+            info,
+            data,
+            selected,
+        })
+    }
+}
+
+impl WaveLoader for Csv {
+    fn name(&self) -> &'static str {
+        "CSV"
+    }
+    fn extensions(&self) -> &'static [&'static str] {
+        &["csv"]
+    }
+    fn detect(&self, head: &[u8]) -> bool {
+        is_ila_csv(head)
+    }
+    fn load(
+        &self,
+        reader: &mut dyn Read,
+        _progress_handler: &dyn Fn(f32, u64),
+        _last_timestamp: Option<u64>,
+    ) -> Result<Wave> {
+        self.load_filtered(reader, &WaveFilter::default())
+    }
+    fn load_source(
+        &self,
+        mut source: &mut dyn WaveSource,
+        _progress_handler: &dyn Fn(f32, u64),
+        filter: &WaveFilter,
+    ) -> Result<(Wave, Option<u64>)> {
+        Ok((self.load_filtered(&mut source, filter)?, None))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::radix::Radix;
+    use crate::wave::csv_parser::{is_ila_csv, Csv, CsvOptions};
+    use crate::wave::filter::WaveFilter;
+    use crate::wave::{
+        Wave, WaveDataValue, WaveLoader, WaveSignalType, WaveTimescaleUnit, WireValue,
+    };
+    use anyhow::Result;
+    use std::collections::HashSet;
+
+    const ILA: &str =
+        "Sample in Buffer,Sample in Window,TRIGGER,top/cpu/pc[7:0],top/cpu/valid,top/state[2:0]\n\
+        Radix - UNSIGNED,UNSIGNED,UNSIGNED,HEX,BINARY,BINARY\n\
+        0,0,0,00,0,000\n\
+        1,1,0,04,1,001\n\
+        2,2,1,08,1,001\n\
+        3,3,0,0c,0,1xx\n";
+
+    const CSV: &str = "time (us); a.b.count; level; \"name;quoted\"\n\
+        0; 0x0; 1; 0b1\n\
+        0.5; 0x1; 0.25; 0b0\n\
+        1.5; 0x1f; -2; 0b0\n";
+
+    fn items(wave: &Wave, id: u64) -> Vec<String> {
+        wave.data
+            .get(&id)
+            .unwrap()
+            .iter()
+            .map(|i| i.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_csv_ila() -> Result<()> {
+        assert!(is_ila_csv(ILA.as_bytes()));
+        assert!(!is_ila_csv(CSV.as_bytes()));
+        let wave = Csv::default().load(&mut ILA.as_bytes(), &|_, _| {}, None)?;
+        assert_eq!(wave.info.timescale, (1, WaveTimescaleUnit::NS));
+        assert_eq!(wave.info.range, (0, 3));
+        let info = |id: u64| wave.info.code_signal_info.get(&id).unwrap();
+        assert_eq!(info(3).width, 8);
+        assert_eq!(info(4).width, 1);
+        assert_eq!(info(5).width, 3);
+        assert_eq!(info(3).typ, WaveSignalType::Wire);
+        assert_eq!(
+            wave.info.code_paths.get(&3).unwrap(),
+            &vec!["top".to_string(), "cpu".to_string(), "pc".to_string()]
+        );
+        // sample index is time column, other columns are signals at root
+        assert!(!wave.info.code_signal_info.contains_key(&0));
+        assert_eq!(info(1).name, "Sample in Window");
+        assert_eq!(items(&wave, 3), vec!["#0 0", "#1 4", "#2 8", "#3 c"]);
+        assert_eq!(items(&wave, 4), vec!["#0 0", "#1 1", "#3 0"]);
+        assert_eq!(
+            wave.find_value(5, 3).unwrap().value,
+            WaveDataValue::Raw(vec![WireValue::V1, WireValue::X, WireValue::X])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_csv_options() -> Result<()> {
+        let csv = Csv::new(CsvOptions {
+            period: "100ns".to_string(),
+            ..Default::default()
+        });
+        let wave = csv.load(&mut CSV.as_bytes(), &|_, _| {}, None)?;
+        // time in us converted to periods of 100ns
+        assert_eq!(wave.info.timescale, (100, WaveTimescaleUnit::NS));
+        assert_eq!(wave.info.range, (0, 15));
+        let info = |id: u64| wave.info.code_signal_info.get(&id).unwrap();
+        assert_eq!(info(1).width, 5);
+        assert_eq!(info(2).typ, WaveSignalType::Real);
+        assert_eq!(info(3).name, "name;quoted");
+        assert_eq!(
+            wave.info.code_paths.get(&1).unwrap(),
+            &vec!["a".to_string(), "b".to_string(), "count".to_string()]
+        );
+        assert_eq!(items(&wave, 1), vec!["#0 0", "#5 1", "#15 1f"]);
+        assert_eq!(
+            wave.find_value(2, 15).unwrap().value,
+            WaveDataValue::Real(-2.0)
+        );
+        // without time column, rows are periods
+        let csv = Csv::new(CsvOptions {
+            radix: Radix::Dec,
+            separators: "".to_string(),
+            ..Default::default()
+        });
+        let wave = csv.load(&mut &b"a.b,c\n1,-1\n2,5\n"[..], &|_, _| {}, None)?;
+        assert_eq!(wave.info.range, (0, 1));
+        assert_eq!(wave.info.code_signal_info.get(&0).unwrap().name, "a.b");
+        // negative values in two's complement
+        assert_eq!(wave.info.code_signal_info.get(&1).unwrap().width, 3);
+        assert_eq!(items(&wave, 1), vec!["#0 7", "#1 5"]);
+        let mut filter = WaveFilter::parse("a.b.count")?;
+        filter.set_window("1000", "")?;
+        let wave = Csv::default().load_filtered(&mut CSV.as_bytes(), &filter)?;
+        assert_eq!(wave.selected, Some(HashSet::from([1])));
+        assert_eq!(items(&wave, 1), vec!["#1000 1", "#1500 1f"]);
+        // time must not go backwards, cells must match header
+        let mut invalid = [&b"time,a\n2,0\n1,1\n"[..], &b"time,a\n0,1,2\n"[..]];
+        for data in invalid.iter_mut() {
+            assert!(Csv::default().load(data, &|_, _| {}, None).is_err());
+        }
+        Ok(())
+    }
+}
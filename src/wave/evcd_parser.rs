@@ -0,0 +1,517 @@
+use crate::wave::filter::WaveFilter;
+use crate::wave::storage::WaveColumn;
+use crate::wave::utils::WaveTreeBuilder;
+use crate::wave::vcd_parser::{vcd_column_push, vcd_source_last_timestamp};
+use crate::wave::{
+    Wave, WaveDataItem, WaveDataValue, WaveInfo, WaveLoader, WavePortDirection, WavePortValue,
+    WaveScopeType, WaveSignalInfo, WaveSignalType, WaveSource, WaveTimescaleUnit, WireValue,
+};
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read};
+use tracing::info;
+
+/// Loader for extended vcd written by `$dumpports` (IEEE 1364 18.4), ports are loaded with
+/// driving direction and strength, other vars like plain vcd
+pub struct Evcd;
+
+/// Bytes at file start searched for port declarations by [is_evcd]
+pub const EVCD_DETECT_SIZE: usize = 64 * 1024;
+
+/// Check if vcd header in `data` declares extended vcd ports
+pub fn is_evcd(data: &[u8]) -> bool {
+    let data = String::from_utf8_lossy(&data[..data.len().min(EVCD_DETECT_SIZE)]);
+    let mut tokens = data.split_ascii_whitespace();
+    while let Some(token) = tokens.next() {
+        match token {
+            "$var" if tokens.next() == Some("port") => return true,
+            "$dumpports" | "$enddefinitions" => return token == "$dumpports",
+            _ => {}
+        }
+    }
+    false
+}
+
+/// Width of port from its size, like `[7:0]` or `1`
+fn evcd_port_width(size: &str) -> Result<u64> {
+    let invalid = || anyhow!("invalid port size {}", size);
+    match size
+        .strip_prefix('[')
+        .and_then(|s| s.strip_suffix(']'))
+        .and_then(|s| s.split_once(':'))
+    {
+        Some((msb, lsb)) => {
+            let msb: i64 = msb.trim().parse().map_err(|_| invalid())?;
+            let lsb: i64 = lsb.trim().parse().map_err(|_| invalid())?;
+            Ok(msb.abs_diff(lsb) + 1)
+        }
+        None => size.parse().map_err(|_| invalid()),
+    }
+}
+
+fn evcd_var_type(typ: &str) -> WaveSignalType {
+    match typ {
+        "port" => WaveSignalType::Port,
+        "event" => WaveSignalType::Event,
+        "integer" => WaveSignalType::Integer,
+        "parameter" => WaveSignalType::Parameter,
+        "real" => WaveSignalType::Real,
+        "reg" => WaveSignalType::Reg,
+        "supply0" => WaveSignalType::Supply0,
+        "supply1" => WaveSignalType::Supply1,
+        "time" => WaveSignalType::Time,
+        "tri" => WaveSignalType::Tri,
+        "triand" => WaveSignalType::TriAnd,
+        "trior" => WaveSignalType::TriOr,
+        "trireg" => WaveSignalType::TriReg,
+        "tri0" => WaveSignalType::Tri0,
+        "tri1" => WaveSignalType::Tri1,
+        "wand" => WaveSignalType::WAnd,
+        "wor" => WaveSignalType::WOr,
+        "string" => WaveSignalType::String,
+        _ => WaveSignalType::Wire,
+    }
+}
+
+fn evcd_scope_type(typ: &str) -> WaveScopeType {
+    match typ {
+        "task" => WaveScopeType::Task,
+        "function" => WaveScopeType::Function,
+        "begin" => WaveScopeType::Begin,
+        "fork" => WaveScopeType::Fork,
+        _ => WaveScopeType::Module,
+    }
+}
+
+fn evcd_wire_value(c: char) -> WireValue {
+    match c {
+        '0' => WireValue::V0,
+        '1' => WireValue::V1,
+        'z' | 'Z' => WireValue::Z,
+        _ => WireValue::X,
+    }
+}
+
+/// Strongest strength in a strength component, which has a digit per bit or one for all bits
+fn evcd_strength(text: &str) -> Result<u8> {
+    text.bytes()
+        .map(|c| match c {
+            b'0'..=b'7' => Ok(c - b'0'),
+            _ => Err(anyhow!("invalid port strength {}", text)),
+        })
+        .try_fold(0, |max, s| s.map(|s| max.max(s)))
+}
+
+/// Whitespace separated tokens of a vcd text
+struct EvcdTokens<'a> {
+    reader: BufReader<&'a mut dyn Read>,
+    line: String,
+    pos: usize,
+}
+
+impl<'a> EvcdTokens<'a> {
+    fn next(&mut self) -> Result<Option<String>> {
+        loop {
+            let rest = &self.line[self.pos..];
+            if let Some(start) = rest.find(|c: char| !c.is_ascii_whitespace()) {
+                let len = rest[start..]
+                    .find(|c: char| c.is_ascii_whitespace())
+                    .unwrap_or(rest.len() - start);
+                self.pos += start + len;
+                return Ok(Some(rest[start..start + len].to_string()));
+            }
+            self.line.clear();
+            self.pos = 0;
+            if self.reader.read_line(&mut self.line)? == 0 {
+                return Ok(None);
+            }
+        }
+    }
+    fn expect(&mut self) -> Result<String> {
+        self.next()?
+            .ok_or_else(|| anyhow!("unexpected end of evcd file"))
+    }
+    /// Tokens until `$end`
+    fn until_end(&mut self) -> Result<Vec<String>> {
+        let mut tokens = vec![];
+        loop {
+            match self.expect()? {
+                token if token == "$end" => return Ok(tokens),
+                token => tokens.push(token),
+            }
+        }
+    }
+}
+
+impl Evcd {
+    /// Load extended vcd, keeping value changes of signals and time window selected by `filter` only
+    pub fn load_filtered<F>(
+        reader: &mut dyn Read,
+        progress_handler: F,
+        last_timestamp: Option<u64>,
+        filter: &WaveFilter,
+    ) -> Result<Wave>
+    where
+        F: Fn(f32, u64),
+    {
+        info!("start parsing evcd file");
+        #[cfg(not(target_arch = "wasm32"))]
+        let perf_start = std::time::Instant::now();
+        let mut tokens = EvcdTokens {
+            reader: BufReader::new(reader),
+            line: String::new(),
+            pos: 0,
+        };
+        let mut builder = WaveTreeBuilder::new();
+        let mut codes: HashMap<String, u64> = HashMap::new();
+        let mut headers = HashMap::new();
+        let mut timescale = (1, WaveTimescaleUnit::default());
+        loop {
+            let token = tokens
+                .next()?
+                .ok_or_else(|| anyhow!("file ends before $enddefinitions"))?;
+            match token.as_str() {
+                "$enddefinitions" => {
+                    tokens.until_end()?;
+                    break;
+                }
+                "$scope" => {
+                    let scope = tokens.until_end()?;
+                    let (typ, name) = match scope.as_slice() {
+                        [typ, name, ..] => (evcd_scope_type(typ), name.to_string()),
+                        [name] => (WaveScopeType::Module, name.to_string()),
+                        [] => return Err(anyhow!("scope without name")),
+                    };
+                    builder.push_scope(name, typ);
+                }
+                "$upscope" => {
+                    tokens.until_end()?;
+                    builder.pop_scope()?;
+                }
+                "$var" => {
+                    let var = tokens.until_end()?;
+                    let (typ, size, code, name) = match var.as_slice() {
+                        [typ, size, code, name, ..] => (typ, size, code, name),
+                        _ => return Err(anyhow!("invalid var {}", var.join(" "))),
+                    };
+                    let next = codes.len() as u64;
+                    let id = *codes.entry(code.to_string()).or_insert(next);
+                    builder.add_var(WaveSignalInfo {
+                        id,
+                        name: name.to_string(),
+                        width: evcd_port_width(size)?,
+                        typ: evcd_var_type(typ),
+                        direction: None,
+                    });
+                }
+                "$timescale" => {
+                    let text = tokens.until_end()?.concat();
+                    let split = text
+                        .find(|c: char| !c.is_ascii_digit())
+                        .unwrap_or(text.len());
+                    timescale = (
+                        text[..split].parse().unwrap_or(1),
+                        WaveTimescaleUnit::parse(&text[split..])
+                            .ok_or_else(|| anyhow!("invalid timescale {}", text))?,
+                    );
+                }
+                "$date" | "$version" | "$comment" => {
+                    let text = tokens.until_end()?.join(" ");
+                    headers.insert(token[1..].to_string(), text);
+                }
+                token if token.starts_with('$') => {
+                    tokens.until_end()?;
+                }
+                token => return Err(anyhow!("unexpected {} in evcd header", token)),
+            }
+        }
+        let (code_signal_info, code_paths, code_aliases, tree) = builder.finish();
+        let mut info = WaveInfo {
+            timescale,
+            range: (0, 0),
+            headers,
+            code_signal_info,
+            code_paths,
+            code_aliases,
+            code_enum_literals: HashMap::new(),
+            tree,
+            dump_off: vec![],
+        };
+        let selected = filter.select(&info)?;
+        let mut window = filter.window(&info.timescale);
+        let mut data: HashMap<u64, WaveColumn> = HashMap::new();
+        let mut directions: HashMap<u64, WavePortDirection> = HashMap::new();
+        // timestamp of last `$dumpports`, `$dumpportsall` or `$dumpportson`
+        let mut checkpoint = None;
+        let mut timestamp = 0u64;
+        let mut range: Option<(u64, u64)> = None;
+        let timestamp_skip = last_timestamp.map(|last| last / 1000).unwrap_or(0);
+        let mut timestamp_notified = 0u64;
+        while let Some(token) = tokens.next()? {
+            let (id, value) = match token.as_bytes()[0] {
+                b'#' => {
+                    let t = token[1..]
+                        .parse()
+                        .map_err(|_| anyhow!("invalid timestamp {}", token))?;
+                    range = Some(match range {
+                        Some((start, stop)) => (start.min(t), stop.max(t)),
+                        None => (t, t),
+                    });
+                    timestamp = t;
+                    // range ends at last timestamp read from tail, changes are not needed
+                    if let (true, Some(last)) = (filter.header_only, last_timestamp) {
+                        range = range.map(|(start, stop)| (start, stop.max(last)));
+                        break;
+                    }
+                    // changes after window are not needed
+                    if matches!(&window, Some(window) if t > window.end) {
+                        break;
+                    }
+                    if let Some(last_timestamp) = last_timestamp {
+                        if timestamp_skip > 0 && timestamp_notified + timestamp_skip < t {
+                            progress_handler(t as f32 / last_timestamp as f32, t);
+                            timestamp_notified = t;
+                        }
+                    }
+                    continue;
+                }
+                b'$' => {
+                    match token.as_str() {
+                        "$dumpportsoff" | "$dumpoff" => info.set_dumping(timestamp, false),
+                        "$dumpportson" | "$dumpon" => {
+                            info.set_dumping(timestamp, true);
+                            checkpoint = Some(timestamp);
+                        }
+                        "$dumpports" | "$dumpportsall" | "$dumpvars" | "$dumpall" => {
+                            checkpoint = Some(timestamp)
+                        }
+                        "$comment" => {
+                            tokens.until_end()?;
+                        }
+                        _ => {}
+                    }
+                    continue;
+                }
+                b'p' => {
+                    let states = token.as_bytes()[1..].to_vec();
+                    if states.is_empty() || !states.iter().all(|s| WavePortValue::is_state(*s)) {
+                        return Err(anyhow!("invalid port value {}", token));
+                    }
+                    let strengths = (
+                        evcd_strength(&tokens.expect()?)?,
+                        evcd_strength(&tokens.expect()?)?,
+                    );
+                    let value = WavePortValue { states, strengths };
+                    (tokens.expect()?, WaveDataValue::Port(value))
+                }
+                b'b' | b'B' => (
+                    tokens.expect()?,
+                    WaveDataValue::Raw(token[1..].chars().map(evcd_wire_value).collect()),
+                ),
+                b'r' | b'R' => (
+                    tokens.expect()?,
+                    WaveDataValue::Real(
+                        token[1..]
+                            .parse()
+                            .map_err(|_| anyhow!("invalid real value {}", token))?,
+                    ),
+                ),
+                b's' | b'S' => (
+                    tokens.expect()?,
+                    WaveDataValue::String(token[1..].to_string()),
+                ),
+                b'0' | b'1' | b'x' | b'X' | b'z' | b'Z' => (
+                    token[1..].to_string(),
+                    WaveDataValue::Raw(vec![evcd_wire_value(token.chars().next().unwrap())]),
+                ),
+                _ => return Err(anyhow!("unexpected {} in evcd data", token)),
+            };
+            let id = match codes.get(&id) {
+                Some(id) => *id,
+                None => return Err(anyhow!("unknown id code {}", id)),
+            };
+            if selected.as_ref().map(|s| !s.contains(&id)).unwrap_or(false) {
+                continue;
+            }
+            if let WaveDataValue::Port(value) = &value {
+                if let Some(direction) = value.direction() {
+                    directions
+                        .entry(id)
+                        .and_modify(|d| *d = d.merge(direction))
+                        .or_insert(direction);
+                }
+            }
+            let item = WaveDataItem { value, timestamp }.compress()?;
+            let is_checkpoint = checkpoint == Some(timestamp);
+            vcd_column_push(&mut data, &mut window, &info, id, item, is_checkpoint)?;
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        info!("parse evcd use time: {:?}", perf_start.elapsed());
+        info.range = range.unwrap_or_default();
+        if let Some(window) = window {
+            info.range = window.range(info.range);
+            window.finish(&mut data, &info)?;
+        }
+        info.set_directions(&directions);
+        Ok(Wave {
+            info,
+            data,
+            selected,
+        })
+    }
+}
+
+impl WaveLoader for Evcd {
+    fn name(&self) -> &'static str {
+        "EVCD"
+    }
+    fn extensions(&self) -> &'static [&'static str] {
+        &["evcd"]
+    }
+    fn detect(&self, head: &[u8]) -> bool {
+        is_evcd(head)
+    }
+    fn load(
+        &self,
+        reader: &mut dyn Read,
+        progress_handler: &dyn Fn(f32, u64),
+        last_timestamp: Option<u64>,
+    ) -> Result<Wave> {
+        Self::load_filtered(
+            reader,
+            progress_handler,
+            last_timestamp,
+            &WaveFilter::default(),
+        )
+    }
+    fn load_source(
+        &self,
+        mut source: &mut dyn WaveSource,
+        progress_handler: &dyn Fn(f32, u64),
+        filter: &WaveFilter,
+    ) -> Result<(Wave, Option<u64>)> {
+        // timestamps are written like plain vcd
+        let last_timestamp = vcd_source_last_timestamp(source)?;
+        Ok((
+            Self::load_filtered(&mut source, progress_handler, last_timestamp, filter)?,
+            None,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::wave::evcd_parser::{is_evcd, Evcd};
+    use crate::wave::filter::WaveFilter;
+    use crate::wave::utils::Node;
+    use crate::wave::{
+        WaveDataValue, WaveLoader, WavePortDirection, WaveSignalType, WaveTimescaleUnit,
+        WaveTreeNode,
+    };
+    use anyhow::Result;
+    use std::collections::HashSet;
+
+    const EVCD: &str = "$date today $end\n\
+        $timescale 1 ns $end\n\
+        $scope module top $end\n\
+        $var port 1 <0 clk $end\n\
+        $var port [3:0] <1 data $end\n\
+        $var port 1 <2 ack $end\n\
+        $upscope $end\n\
+        $enddefinitions $end\n\
+        #0\n\
+        $dumpports\n\
+        pD 6 0 <0\n\
+        pDDDD 6666 0000 <1\n\
+        pL 6 0 <2\n\
+        $end\n\
+        #10\n\
+        pU 0 6 <0\n\
+        pHHLL 0 6 <1\n\
+        #20\n\
+        pD 6 0 <0\n\
+        pH 0 5 <2\n\
+        #30\n\
+        $dumpportsall\n\
+        pD 6 0 <0\n\
+        pHHLL 0 6 <1\n\
+        pH 0 5 <2\n\
+        $end\n\
+        #40\n\
+        pUUUD 6 6 <1\n";
+
+    #[test]
+    fn test_evcd() -> Result<()> {
+        assert!(is_evcd(EVCD.as_bytes()));
+        assert!(!is_evcd(
+            b"$var wire 1 ! clk $end $enddefinitions $end $var port"
+        ));
+        let wave = Evcd.load(&mut EVCD.as_bytes(), &|_, _| {}, None)?;
+        assert_eq!(wave.info.timescale, (1, WaveTimescaleUnit::NS));
+        assert_eq!(wave.info.range, (0, 40));
+        assert_eq!(wave.info.headers.get("date").unwrap(), "today");
+        let items = |id: u64| {
+            wave.data
+                .get(&id)
+                .unwrap()
+                .iter()
+                .map(|i| i.to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(items(0), vec!["#0 0", "#10 1", "#20 0"]);
+        // values repeated by `$dumpportsall` are not changes
+        assert_eq!(items(1), vec!["#0 0", "#10 c", "#40 e"]);
+        assert_eq!(items(2), vec!["#0 0", "#20 1"]);
+        let port = |id: u64, pos: u64| match wave.find_value(id, pos).unwrap().value {
+            WaveDataValue::Port(v) => v,
+            v => panic!("not a port value: {v:?}"),
+        };
+        assert_eq!(port(1, 5).direction(), Some(WavePortDirection::Input));
+        assert_eq!(port(1, 15).direction(), Some(WavePortDirection::Output));
+        assert_eq!(port(1, 15).strengths, (0, 6));
+        assert_eq!(port(2, 25).strengths, (0, 5));
+        let info = |id: u64| wave.info.code_signal_info.get(&id).unwrap();
+        assert_eq!(info(1).typ, WaveSignalType::Port);
+        assert_eq!(info(1).width, 4);
+        assert_eq!(info(0).direction, Some(WavePortDirection::Input));
+        assert_eq!(info(1).direction, Some(WavePortDirection::Inout));
+        assert_eq!(info(2).direction, Some(WavePortDirection::Output));
+        // directions are shown in tree
+        let tree = serde_json::to_string(&Node(wave.info.tree.root()))?;
+        assert!(tree.contains(&serde_json::to_string(&WaveTreeNode::WaveVar(
+            info(2).clone()
+        ))?));
+        Ok(())
+    }
+
+    #[test]
+    fn test_evcd_filtered() -> Result<()> {
+        let mut filter = WaveFilter::parse("top.data")?;
+        filter.set_window("15", "35")?;
+        let wave = Evcd::load_filtered(&mut EVCD.as_bytes(), |_, _| {}, None, &filter)?;
+        assert_eq!(wave.selected, Some(HashSet::from([1])));
+        assert_eq!(wave.data.len(), 1);
+        assert_eq!(wave.info.range, (15, 35));
+        let items = wave
+            .data
+            .get(&1)
+            .unwrap()
+            .iter()
+            .map(|i| i.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(items, vec!["#15 c"]);
+        // values before window are parsed too
+        assert_eq!(
+            wave.info.code_signal_info.get(&1).unwrap().direction,
+            Some(WavePortDirection::Inout)
+        );
+        assert!(Evcd
+            .load(
+                &mut &b"$var port 1 <0 a $end\n$enddefinitions $end\n#0\npQ 6 6 <0\n"[..],
+                &|_, _| {},
+                None
+            )
+            .is_err());
+        Ok(())
+    }
+}
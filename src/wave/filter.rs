@@ -0,0 +1,348 @@
+use crate::wave::storage::WaveColumn;
+use crate::wave::{WaveDataItem, WaveDataValue, WaveInfo, WaveTimescaleUnit};
+use anyhow::{anyhow, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+
+/// Select signals to keep value changes when loading, the whole tree is still loaded.
+/// Empty filter keeps all signals
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq)]
+pub struct WaveFilter {
+    /// Scope or signal path globs like `top.cpu.*` or `top.cpu.pc`, or regex wrapped in `/`.
+    /// A scope matched selects all signals inside it
+    pub patterns: Vec<String>,
+    /// Signal ids to keep besides matched ones, e.g. signals loaded on demand
+    pub ids: HashSet<u64>,
+    /// Start of time window to load, `None` from the beginning
+    pub start: Option<WaveTime>,
+    /// End of time window to load, `None` to the end
+    pub end: Option<WaveTime>,
+    /// Keep no value changes, loaders stop after header when time range is known without
+    /// value changes, e.g. from fst header or vcd tail
+    #[serde(default)]
+    pub header_only: bool,
+}
+
+/// Time given by user, like `1200us`, or in timestamps of the file when unit is omitted
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct WaveTime {
+    pub value: u64,
+    pub unit: Option<WaveTimescaleUnit>,
+}
+
+impl Display for WaveTime {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.unit {
+            Some(unit) => write!(f, "{}{}", self.value, unit),
+            None => write!(f, "{}", self.value),
+        }
+    }
+}
+
+impl WaveTime {
+    pub fn parse(text: &str) -> Result<Self> {
+        let text = text.trim();
+        let split = text
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(text.len());
+        let (value, unit) = text.split_at(split);
+        let value = value
+            .parse()
+            .map_err(|_| anyhow!("invalid time {}", text))?;
+        let unit = match unit.trim() {
+            "" => None,
+            unit => Some(
+                WaveTimescaleUnit::parse(unit)
+                    .ok_or_else(|| anyhow!("invalid time unit {}", unit))?,
+            ),
+        };
+        Ok(Self { value, unit })
+    }
+    /// Convert to timestamp of a wave with `timescale`, rounded down
+    pub fn to_pos(&self, timescale: &(u64, WaveTimescaleUnit)) -> u64 {
+        match self.unit {
+            Some(unit) => {
+                let fs = self.value as u128 * unit.femtoseconds() as u128;
+                let scale = timescale.0.max(1) as u128 * timescale.1.femtoseconds() as u128;
+                (fs / scale).min(u64::MAX as u128) as u64
+            }
+            None => self.value,
+        }
+    }
+}
+
+/// Clips value changes to a time window while loading.
+/// Only the last change before the window is kept, moved to window start
+#[derive(Debug, Clone)]
+pub struct WaveWindow {
+    pub start: u64,
+    pub end: u64,
+    before: HashMap<u64, WaveDataItem>,
+}
+
+impl WaveWindow {
+    pub fn new(start: u64, end: u64) -> Self {
+        Self {
+            start,
+            end,
+            before: HashMap::new(),
+        }
+    }
+    /// Push value change of signal `id` to `data` if it is inside the window,
+    /// items must be pushed in time order for every signal, see [WaveColumn::push]
+    pub fn push(
+        &mut self,
+        data: &mut HashMap<u64, WaveColumn>,
+        info: &WaveInfo,
+        id: u64,
+        item: WaveDataItem,
+    ) -> Result<()> {
+        if item.timestamp < self.start {
+            if let Some(before) = self.before.get(&id) {
+                if item.timestamp < before.timestamp {
+                    return Err(anyhow!(
+                        "value change at #{} before last change at #{}",
+                        item.timestamp,
+                        before.timestamp
+                    ));
+                }
+            }
+            self.before.insert(id, item);
+            return Ok(());
+        }
+        if item.timestamp > self.end {
+            return Ok(());
+        }
+        let column = data.entry(id).or_insert_with(|| info.new_column(id));
+        if let Some(before) = self.before.remove(&id) {
+            column.push(WaveDataItem {
+                timestamp: self.start,
+                ..before
+            })?;
+        }
+        match column.last_timestamp() {
+            Some(last) if last == item.timestamp => column.replace_last(item),
+            _ => column.push(item),
+        }
+    }
+    /// Whether value of signal `id` kept before window start equals `value`,
+    /// `None` if no value is kept
+    pub fn last_value_is(&self, id: &u64, value: &WaveDataValue) -> Option<bool> {
+        self.before.get(id).map(|item| &item.value == value)
+    }
+    /// Push values of signals not changed inside the window
+    pub fn finish(self, data: &mut HashMap<u64, WaveColumn>, info: &WaveInfo) -> Result<()> {
+        for (id, item) in self.before {
+            data.entry(id)
+                .or_insert_with(|| info.new_column(id))
+                .push(WaveDataItem {
+                    timestamp: self.start,
+                    ..item
+                })?;
+        }
+        Ok(())
+    }
+    /// Range of loaded wave clipped by window
+    pub fn range(&self, range: (u64, u64)) -> (u64, u64) {
+        let end = range.1.min(self.end);
+        (range.0.max(self.start).min(end), end)
+    }
+}
+
+/// Convert path glob to anchored regex, `*` matches any chars and `?` matches one char
+pub fn glob_regex(glob: &str) -> String {
+    let mut regex = "^".to_string();
+    for c in glob.chars() {
+        match c {
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push('$');
+    regex
+}
+
+impl WaveFilter {
+    /// Parse patterns separated by whitespaces, commas or new lines
+    pub fn parse(text: &str) -> Result<Self> {
+        let filter = Self {
+            patterns: text
+                .split(|c: char| c.is_whitespace() || c == ',')
+                .filter(|s| !s.is_empty())
+                .map(|s| s.to_string())
+                .collect(),
+            ..Default::default()
+        };
+        filter.regexes()?;
+        Ok(filter)
+    }
+    /// Filter that keeps only `ids`
+    pub fn from_ids(ids: impl IntoIterator<Item = u64>) -> Self {
+        Self {
+            ids: ids.into_iter().collect(),
+            ..Default::default()
+        }
+    }
+    /// Filter that keeps header and hierarchy only
+    pub fn header_only() -> Self {
+        Self {
+            header_only: true,
+            ..Default::default()
+        }
+    }
+    /// Set time window from user input, empty text for an open bound.
+    /// Inverted window is rejected, unless one bound is in timestamps and the other
+    /// has a unit, which are compared in [WaveFilter::window] when timescale is known
+    pub fn set_window(&mut self, start: &str, end: &str) -> Result<()> {
+        let parse = |text: &str| match text.trim() {
+            "" => Ok(None),
+            text => WaveTime::parse(text).map(Some),
+        };
+        let (start, end) = (parse(start)?, parse(end)?);
+        if let (Some(s), Some(e)) = (start, end) {
+            let inverted = match (s.unit, e.unit) {
+                (Some(su), Some(eu)) => {
+                    s.value as u128 * su.femtoseconds() as u128
+                        > e.value as u128 * eu.femtoseconds() as u128
+                }
+                (None, None) => s.value > e.value,
+                _ => false,
+            };
+            if inverted {
+                return Err(anyhow!("time window start {} is after end {}", s, e));
+            }
+        }
+        self.start = start;
+        self.end = end;
+        Ok(())
+    }
+    /// Whether all signals in the whole time range are kept
+    pub fn is_empty(&self) -> bool {
+        !self.header_only
+            && self.patterns.is_empty()
+            && self.ids.is_empty()
+            && self.window_is_empty()
+    }
+    fn window_is_empty(&self) -> bool {
+        self.start.is_none() && self.end.is_none()
+    }
+    /// Time window to load in timestamps of a wave with `timescale`, `None` to load all
+    pub fn window(&self, timescale: &(u64, WaveTimescaleUnit)) -> Option<WaveWindow> {
+        if self.window_is_empty() {
+            return None;
+        }
+        let start = self.start.map(|t| t.to_pos(timescale)).unwrap_or(0);
+        let end = self.end.map(|t| t.to_pos(timescale)).unwrap_or(u64::MAX);
+        // bounds in timestamps and in units may still be inverted, keep nothing outside
+        Some(WaveWindow::new(start, end.max(start)))
+    }
+    pub fn regexes(&self) -> Result<Vec<Regex>> {
+        Ok(self
+            .patterns
+            .iter()
+            .map(
+                |p| match p.strip_prefix('/').and_then(|p| p.strip_suffix('/')) {
+                    Some(regex) => Regex::new(regex),
+                    None => Regex::new(&glob_regex(p)),
+                },
+            )
+            .collect::<std::result::Result<Vec<_>, _>>()?)
+    }
+    /// Ids to keep value changes, `None` if all signals are kept
+    pub fn select(&self, info: &WaveInfo) -> Result<Option<HashSet<u64>>> {
+        if self.header_only {
+            return Ok(Some(HashSet::new()));
+        }
+        if self.patterns.is_empty() && self.ids.is_empty() {
+            return Ok(None);
+        }
+        let regexes = self.regexes()?;
+        let mut ids = self.ids.clone();
+        for (id, path) in info.all_signal_paths() {
+            // match signal path and all scope paths above it, any alias selects the signal
+            let selected = (1..=path.len()).any(|n| {
+                let path = path[..n].join(".");
+                regexes.iter().any(|r| r.is_match(&path))
+            });
+            if selected {
+                ids.insert(id);
+            }
+        }
+        Ok(Some(ids))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::wave::filter::{WaveFilter, WaveTime};
+    use crate::wave::vcd_parser::Vcd;
+    use crate::wave::{WaveLoader, WaveTimescaleUnit};
+    use anyhow::Result;
+    use std::collections::HashSet;
+    use std::fs::File;
+
+    #[test]
+    fn test_wave_filter() -> Result<()> {
+        let wave = Vcd.load(&mut File::open("data/counter.vcd")?, &|_, _| {}, None)?;
+        let select = |text: &str| -> Result<HashSet<String>> {
+            Ok(WaveFilter::parse(text)?
+                .select(&wave.info)?
+                .unwrap()
+                .iter()
+                .map(|id| wave.info.code_paths.get(id).unwrap().join("."))
+                .collect())
+        };
+        let all = wave
+            .info
+            .code_paths
+            .values()
+            .map(|p| p.join("."))
+            .collect::<HashSet<_>>();
+        assert_eq!(select("top")?, all);
+        assert_eq!(
+            select("top.counter.cnt")?,
+            HashSet::from(["top.counter.cnt".to_string()])
+        );
+        assert_eq!(select("*.cn?, top.clk")?.len(), 2);
+        assert_eq!(select("/cnt$/")?.len(), 1);
+        assert!(select("top.count")?.is_empty());
+        assert!(WaveFilter::parse("/(/").is_err());
+        assert!(WaveFilter::parse(" , ")?.select(&wave.info)?.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_wave_time() -> Result<()> {
+        let ns = (1, WaveTimescaleUnit::NS);
+        assert_eq!(WaveTime::parse("1200")?.to_pos(&ns), 1200);
+        assert_eq!(WaveTime::parse("3us")?.to_pos(&ns), 3000);
+        assert_eq!(WaveTime::parse("1500 PS")?.to_pos(&ns), 1);
+        assert_eq!(
+            WaveTime::parse("2s")?.to_pos(&(10, WaveTimescaleUnit::FS)),
+            200_000_000_000_000
+        );
+        assert_eq!(WaveTime::parse("3us")?.to_string(), "3us");
+        assert!(WaveTime::parse("1.5us").is_err());
+        assert!(WaveTime::parse("3 mins").is_err());
+        let mut filter = WaveFilter::default();
+        filter.set_window("", "2us")?;
+        assert!(!filter.is_empty());
+        let window = filter.window(&ns).unwrap();
+        assert_eq!((window.start, window.end), (0, 2000));
+        assert_eq!(window.range((100, 5000)), (100, 2000));
+        filter.set_window(" ", "")?;
+        assert!(filter.is_empty() && filter.window(&ns).is_none());
+        // inverted window is rejected and keeps previous window
+        assert!(filter.set_window("30", "20").is_err());
+        assert!(filter.set_window("2us", "1500ns").is_err());
+        assert!(filter.is_empty());
+        filter.set_window("1us", "1000ns")?;
+        filter.set_window("3000", "2us")?;
+        let window = filter.window(&ns).unwrap();
+        assert_eq!((window.start, window.end), (3000, 3000));
+        Ok(())
+    }
+}
@@ -0,0 +1,476 @@
+use crate::wave::filter::WaveFilter;
+use crate::wave::storage::WaveColumn;
+use crate::wave::utils::{WaveHierarchy, WaveTreeBuilder};
+use crate::wave::WaveDataValue::Raw;
+use crate::wave::{
+    Wave, WaveDataItem, WaveDataValue, WaveInfo, WaveLoader, WaveScopeType, WaveSignalInfo,
+    WaveSignalType, WaveSource, WaveTimescaleUnit, WireValue,
+};
+use anyhow::Result;
+use fst_reader::{
+    FstFilter, FstHierarchyEntry, FstReader, FstScopeType, FstSignalHandle, FstSignalValue,
+    FstVarType,
+};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Cursor, Read, Seek, SeekFrom};
+use tracing::info;
+
+pub struct Fst;
+
+/// Length of header block, the first block in fst files
+const FST_HEADER_LENGTH: u64 = 329;
+
+/// Check if `data` starts with a fst header block, or a gzip wrapper block of compressed fst.
+/// Wrapper block has its section length and uncompressed length, then gzip data
+pub fn is_fst(data: &[u8]) -> bool {
+    let u64_at = |offset: usize| {
+        data.get(offset..offset + 8)
+            .map(|b| u64::from_be_bytes(b.try_into().unwrap()))
+    };
+    match data.first() {
+        Some(0) => u64_at(1) == Some(FST_HEADER_LENGTH),
+        Some(254) => {
+            matches!(u64_at(1), Some(section) if section > 16)
+                && matches!(u64_at(9), Some(length) if length > FST_HEADER_LENGTH)
+                && data.get(17..19) == Some(&[0x1f, 0x8b][..])
+        }
+        _ => false,
+    }
+}
+
+impl From<FstVarType> for WaveSignalType {
+    fn from(value: FstVarType) -> Self {
+        match value {
+            FstVarType::Event => Self::Event,
+            FstVarType::Integer
+            | FstVarType::Int
+            | FstVarType::ShortInt
+            | FstVarType::LongInt
+            | FstVarType::Byte => Self::Integer,
+            FstVarType::Parameter => Self::Parameter,
+            FstVarType::Real
+            | FstVarType::RealParameter
+            | FstVarType::RealTime
+            | FstVarType::ShortReal => Self::Real,
+            FstVarType::Supply0 => Self::Supply0,
+            FstVarType::Supply1 => Self::Supply1,
+            FstVarType::Time => Self::Time,
+            FstVarType::Tri => Self::Tri,
+            FstVarType::TriAnd => Self::TriAnd,
+            FstVarType::TriOr => Self::TriOr,
+            FstVarType::TriReg => Self::TriReg,
+            FstVarType::Tri0 => Self::Tri0,
+            FstVarType::Tri1 => Self::Tri1,
+            FstVarType::Wand => Self::WAnd,
+            FstVarType::Wire | FstVarType::Port => Self::Wire,
+            FstVarType::Wor => Self::WOr,
+            FstVarType::GenericString => Self::String,
+            _ => Self::Reg,
+        }
+    }
+}
+
+impl From<FstScopeType> for WaveScopeType {
+    fn from(value: FstScopeType) -> Self {
+        match value {
+            FstScopeType::Task => Self::Task,
+            FstScopeType::Function => Self::Function,
+            FstScopeType::Begin => Self::Begin,
+            FstScopeType::Fork => Self::Fork,
+            // other vhdl / system verilog scopes are shown as modules
+            _ => Self::Module,
+        }
+    }
+}
+
+/// Convert fst timescale exponent (`10^exponent` seconds) to wave timescale,
+/// e.g. `-10` is `100ps`
+pub fn fst_timescale(exponent: i8) -> (u64, WaveTimescaleUnit) {
+    use WaveTimescaleUnit::*;
+    for (e, unit) in [(0i8, S), (-3, MS), (-6, US), (-9, NS), (-12, PS), (-15, FS)] {
+        if exponent >= e {
+            return (10u64.pow((exponent - e) as u32), unit);
+        }
+    }
+    (1, FS)
+}
+
+/// Remove bit range suffix in fst var name, e.g. `data [7:0]` => `data`
+pub fn fst_var_name(name: &str) -> &str {
+    match name.split_once(" [") {
+        Some((name, _)) => name,
+        None => name,
+    }
+}
+
+/// Convert fst value chars (msb first) to [WireValue]
+pub fn fst_wire_value(c: u8) -> WireValue {
+    match c {
+        b'0' | b'l' | b'L' => WireValue::V0,
+        b'1' | b'h' | b'H' => WireValue::V1,
+        b'z' | b'Z' => WireValue::Z,
+        _ => WireValue::X,
+    }
+}
+
+/// Read fst hierarchy to get code info, code paths and scope tree
+pub fn fst_hierarchy<R>(reader: &mut FstReader<R>) -> Result<WaveHierarchy>
+where
+    R: std::io::BufRead + std::io::Seek,
+{
+    let mut builder = WaveTreeBuilder::new();
+    let mut error = None;
+    reader.read_hierarchy(|entry| match entry {
+        FstHierarchyEntry::Scope { tpe, name, .. } => builder.push_scope(name, tpe.into()),
+        FstHierarchyEntry::UpScope => {
+            if let Err(e) = builder.pop_scope() {
+                error = Some(e);
+            }
+        }
+        FstHierarchyEntry::Var {
+            tpe,
+            name,
+            length,
+            handle,
+            ..
+        } => builder.add_var(WaveSignalInfo {
+            id: handle.get_index() as u64,
+            name: fst_var_name(&name).to_string(),
+            width: length as u64,
+            typ: tpe.into(),
+            direction: None,
+        }),
+        _ => {}
+    })?;
+    if let Some(e) = error {
+        return Err(e);
+    }
+    Ok(builder.finish())
+}
+
+impl Fst {
+    /// Load fst from a seekable reader, e.g. `BufReader<File>`, without reading all data first.
+    /// Only value changes of signals and time window selected by `filter` are kept
+    pub fn load_seekable<R, F>(
+        reader: R,
+        progress_handler: F,
+        last_timestamp: Option<u64>,
+        filter: &WaveFilter,
+    ) -> Result<Wave>
+    where
+        R: BufRead + Seek,
+        F: Fn(f32, u64),
+    {
+        info!("start parsing fst file");
+        #[cfg(not(target_arch = "wasm32"))]
+        let perf_start = std::time::Instant::now();
+        let mut reader = FstReader::open(reader)?;
+        let header = reader.get_header();
+        let (code_info, code_paths, code_aliases, tree) = fst_hierarchy(&mut reader)?;
+        let mut headers: HashMap<String, String> = HashMap::new();
+        if !header.date.is_empty() {
+            headers.insert("date".to_string(), header.date.to_string());
+        }
+        if !header.version.is_empty() {
+            headers.insert("version".to_string(), header.version.to_string());
+        }
+        let timescale = fst_timescale(header.timescale_exponent);
+        let time_start = header.start_time;
+        let last = last_timestamp.unwrap_or(header.end_time);
+        // progress is counted from start time, which is not 0 in many dumps
+        let span = last.saturating_sub(time_start).max(1);
+        let timestamp_skip = span / 1000;
+        let mut timestamp_notified = 0u64;
+        let mut info = WaveInfo {
+            timescale,
+            range: (header.start_time, header.end_time),
+            headers,
+            code_signal_info: code_info,
+            code_paths,
+            code_aliases,
+            code_enum_literals: HashMap::new(),
+            tree,
+            dump_off: vec![],
+        };
+        let selected = filter.select(&info)?;
+        let fst_filter = match &selected {
+            Some(selected) => FstFilter::filter_signals(
+                selected
+                    .iter()
+                    .map(|id| FstSignalHandle::from_index(*id as usize))
+                    .collect(),
+            ),
+            None => FstFilter::all(),
+        };
+        let mut window = filter.window(&info.timescale);
+        let mut data: HashMap<u64, WaveColumn> = HashMap::new();
+        let mut error = None;
+        let mut push = |id: u64, item: WaveDataItem| match &mut window {
+            Some(window) => window.push(&mut data, &info, id, item),
+            None => data
+                .entry(id)
+                .or_insert_with(|| info.new_column(id))
+                .push(item),
+        };
+        // range is given by header, value change blocks are not read for header only
+        if !filter.header_only {
+            reader.read_signals(&fst_filter, |timestamp, handle, value| {
+                if timestamp_skip > 0
+                    && timestamp > time_start
+                    && timestamp_notified + timestamp_skip < timestamp
+                {
+                    let progress = (timestamp - time_start) as f32 / span as f32;
+                    progress_handler(progress, timestamp);
+                    timestamp_notified = timestamp;
+                }
+                let pushed = match value {
+                    FstSignalValue::String(v) => {
+                        let id = handle.get_index() as u64;
+                        let is_string = info
+                            .code_signal_info
+                            .get(&id)
+                            .map(|i| i.typ == WaveSignalType::String)
+                            .unwrap_or(false);
+                        let value = match is_string {
+                            true => WaveDataValue::String(String::from_utf8_lossy(v).to_string()),
+                            false => Raw(v.iter().map(|c| fst_wire_value(*c)).collect()),
+                        };
+                        (WaveDataItem { value, timestamp })
+                            .compress()
+                            .and_then(|item| push(id, item))
+                    }
+                    FstSignalValue::Real(v) => {
+                        let id = handle.get_index() as u64;
+                        push(
+                            id,
+                            WaveDataItem {
+                                value: WaveDataValue::Real(v),
+                                timestamp,
+                            },
+                        )
+                    }
+                };
+                if let Err(e) = pushed {
+                    error = Some(e);
+                }
+            })?;
+        }
+        if let Some(e) = error {
+            return Err(e);
+        }
+        if let Some(window) = window {
+            info.range = window.range(info.range);
+            window.finish(&mut data, &info)?;
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let perf_stop = std::time::Instant::now();
+            info!("parse fst use time: {:?}", perf_stop - perf_start);
+        }
+        Ok(Wave {
+            info,
+            data,
+            selected,
+        })
+    }
+}
+
+impl WaveLoader for Fst {
+    fn name(&self) -> &'static str {
+        "FST"
+    }
+    fn extensions(&self) -> &'static [&'static str] {
+        &["fst"]
+    }
+    fn detect(&self, head: &[u8]) -> bool {
+        is_fst(head)
+    }
+    fn load(
+        &self,
+        reader: &mut dyn Read,
+        progress_handler: &dyn Fn(f32, u64),
+        last_timestamp: Option<u64>,
+    ) -> Result<Wave> {
+        // fst blocks are located by seeking, so read all data first
+        let mut buf = vec![];
+        reader.read_to_end(&mut buf)?;
+        Self::load_seekable(
+            Cursor::new(buf),
+            progress_handler,
+            last_timestamp,
+            &WaveFilter::default(),
+        )
+    }
+    fn load_source(
+        &self,
+        source: &mut dyn WaveSource,
+        progress_handler: &dyn Fn(f32, u64),
+        filter: &WaveFilter,
+    ) -> Result<(Wave, Option<u64>)> {
+        // fst blocks are located by seeking, decompressing streams can not seek
+        let wave = match source.seek(SeekFrom::End(0)) {
+            Ok(_) => {
+                source.seek(SeekFrom::Start(0))?;
+                Self::load_seekable(BufReader::new(source), progress_handler, None, filter)?
+            }
+            Err(_) => {
+                let mut buf = vec![];
+                source.read_to_end(&mut buf)?;
+                Self::load_seekable(Cursor::new(buf), progress_handler, None, filter)?
+            }
+        };
+        Ok((wave, None))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::wave::filter::WaveFilter;
+    use crate::wave::fst_parser::{fst_timescale, fst_var_name, is_fst, Fst};
+    use crate::wave::loader::WaveStream;
+    use crate::wave::utils::Node;
+    use crate::wave::vcd_parser::Vcd;
+    use crate::wave::{Wave, WaveDataValue, WaveLoader, WaveTimescaleUnit};
+    use anyhow::Result;
+    use std::fs::File;
+    use std::io::{BufReader, Read};
+
+    #[test]
+    fn test_fst_timescale() {
+        let check = |e: i8, v: u64, u: &str| {
+            let t = fst_timescale(e);
+            assert_eq!((t.0, t.1.to_string()), (v, u.to_string()));
+        };
+        check(0, 1, "s");
+        check(-9, 1, "ns");
+        check(-10, 100, "ps");
+        check(-11, 10, "ps");
+        check(-15, 1, "fs");
+        check(-18, 1, "fs");
+        assert_eq!(fst_var_name("data [7:0]"), "data");
+        assert_eq!(fst_var_name("clk"), "clk");
+    }
+
+    #[test]
+    fn test_is_fst() -> Result<()> {
+        let mut head = vec![];
+        File::open("data/counter.fst")?.read_to_end(&mut head)?;
+        assert!(is_fst(&head));
+        let mut wrapped = vec![254];
+        wrapped.extend_from_slice(&100u64.to_be_bytes());
+        wrapped.extend_from_slice(&head.len().to_be_bytes());
+        wrapped.extend_from_slice(&[0x1f, 0x8b, 8, 0]);
+        assert!(is_fst(&wrapped));
+        // any file starting with 0xfe is not wrapped fst
+        let mut other = wrapped.clone();
+        other[17] = 0;
+        assert!(!is_fst(&other));
+        assert!(!is_fst(&wrapped[..12]));
+        assert!(!is_fst(&[254; 64]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_fst_parser() -> Result<()> {
+        let wave = Fst.load(&mut File::open("data/counter.fst")?, &|_, _| {}, None)?;
+        println!("loaded wave: {wave}");
+        let seekable = Fst::load_seekable(
+            BufReader::new(File::open("data/counter.fst")?),
+            |_, _| {},
+            None,
+            &WaveFilter::default(),
+        )?;
+        assert_eq!(seekable.info.range, wave.info.range);
+        assert_eq!(seekable.data.len(), wave.data.len());
+        let filtered = Fst::load_seekable(
+            BufReader::new(File::open("data/counter.fst")?),
+            |_, _| {},
+            None,
+            &WaveFilter::parse("top.counter.cnt")?,
+        )?;
+        assert_eq!(filtered.info.code_paths, wave.info.code_paths);
+        assert_eq!(filtered.data.keys().collect::<Vec<_>>(), vec![&2]);
+        assert_eq!(
+            filtered.data.get(&2).unwrap().len(),
+            wave.data.get(&2).unwrap().len()
+        );
+        let header = Fst::load_seekable(
+            BufReader::new(File::open("data/counter.fst")?),
+            |_, _| {},
+            None,
+            &WaveFilter::header_only(),
+        )?;
+        assert_eq!(header.info.range, wave.info.range);
+        assert!(header.data.is_empty());
+        // decompressed fst cannot seek, so it is read into memory first
+        let mut stream = WaveStream::new(File::open("data/counter.fst")?);
+        let (streamed, _) = Fst.load_source(&mut stream, &|_, _| {}, &WaveFilter::default())?;
+        assert_eq!(streamed.info.range, wave.info.range);
+        assert_eq!(streamed.data.len(), wave.data.len());
+        let mut filter = WaveFilter::default();
+        filter.set_window("12ns", "30ns")?;
+        let window = Fst::load_seekable(
+            BufReader::new(File::open("data/counter.fst")?),
+            |_, _| {},
+            None,
+            &filter,
+        )?;
+        assert_eq!(window.info.range, (12, 30));
+        assert_eq!(
+            window
+                .data
+                .get(&2)
+                .unwrap()
+                .iter()
+                .map(|i| i.to_string())
+                .collect::<Vec<_>>(),
+            vec!["#12 0", "#15 1", "#25 2"]
+        );
+        assert!(matches!(wave.info.timescale, (1, WaveTimescaleUnit::NS)));
+        assert_eq!(wave.info.range, (0, 45));
+        assert_eq!(wave.info.code_signal_info.len(), 4);
+        let cnt = wave.info.code_signal_info.get(&2).unwrap();
+        assert_eq!((cnt.name.as_str(), cnt.width), ("cnt", 8));
+        assert_eq!(
+            wave.info.code_paths.get(&2).unwrap(),
+            &vec!["top".to_string(), "counter".to_string(), "cnt".to_string()]
+        );
+        let value = |id: u64, pos: u64| wave.find_value(id, pos).unwrap().value.to_string();
+        assert_eq!(value(0, 7), "1");
+        assert_eq!(value(2, 3), "xx");
+        assert_eq!(value(2, 27), "2");
+        assert!(matches!(
+            wave.find_value(2, 40).unwrap().value,
+            WaveDataValue::Raw(_)
+        ));
+        assert_eq!(value(3, 0), "z");
+        Ok(())
+    }
+
+    /// `data/counter.fst` and `data/counter.vcd` contain the same wave
+    #[test]
+    fn test_fst_vcd_round_trip() -> Result<()> {
+        let fst = Fst.load(&mut File::open("data/counter.fst")?, &|_, _| {}, None)?;
+        let vcd = Vcd.load(&mut File::open("data/counter.vcd")?, &|_, _| {}, None)?;
+        assert_eq!(fst.info.range, vcd.info.range);
+        assert_eq!(fst.info.code_paths, vcd.info.code_paths);
+        assert_eq!(
+            fst.info.code_signal_info.len(),
+            vcd.info.code_signal_info.len()
+        );
+        for (id, info) in vcd.info.code_signal_info.iter() {
+            let fst_info = fst.info.code_signal_info.get(id).unwrap();
+            assert_eq!((&fst_info.name, fst_info.width), (&info.name, info.width));
+        }
+        let tree_json = |wave: &Wave| serde_json::to_string(&Node(wave.info.tree.root())).unwrap();
+        assert_eq!(tree_json(&fst), tree_json(&vcd));
+        for (id, items) in vcd.data.iter() {
+            let fst_items = fst.data.get(id).unwrap();
+            assert_eq!(
+                fst_items.iter().map(|i| i.to_string()).collect::<Vec<_>>(),
+                items.iter().map(|i| i.to_string()).collect::<Vec<_>>()
+            );
+        }
+        Ok(())
+    }
+}
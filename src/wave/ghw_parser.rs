@@ -0,0 +1,981 @@
+use crate::wave::filter::{WaveFilter, WaveWindow};
+use crate::wave::storage::WaveColumn;
+use crate::wave::utils::WaveTreeBuilder;
+use crate::wave::WaveDataValue::{Comp, Raw, Real};
+use crate::wave::{
+    Wave, WaveDataItem, WaveDataValue, WaveInfo, WaveLoader, WaveScopeType, WaveSignalInfo,
+    WaveSignalType, WaveSource, WaveTimescaleUnit, WireValue,
+};
+use anyhow::{anyhow, Result};
+use num_bigint::BigUint;
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+use tracing::info;
+
+/// Loader for GHDL native wave file (`.ghw`)
+pub struct Ghw;
+
+/// Magic bytes at the start of a ghw file
+pub const GHW_MAGIC: &[u8] = b"GHDLwave\n";
+
+/// Check ghw magic bytes
+pub fn is_ghw(data: &[u8]) -> bool {
+    data.starts_with(GHW_MAGIC)
+}
+
+/// Literals of `std_ulogic`, in the order ghdl writes them
+const GHW_STD_LOGIC: &[u8] = b"ux01zwlh-";
+/// Literals of `bit`
+const GHW_BIT: &[u8] = b"01";
+
+/// Convert `std_ulogic` literal index to [WireValue]
+pub fn ghw_std_logic_value(v: u8) -> WireValue {
+    match GHW_STD_LOGIC.get(v as usize) {
+        Some(b'0') | Some(b'l') => WireValue::V0,
+        Some(b'1') | Some(b'h') => WireValue::V1,
+        Some(b'z') => WireValue::Z,
+        _ => WireValue::X,
+    }
+}
+
+/// Check if enum literals are chars like `'0'`, `'1'` listed in `chars`
+fn ghw_literals_match(literals: &[String], chars: &[u8]) -> bool {
+    literals.len() == chars.len()
+        && literals.iter().zip(chars.iter()).all(|(l, c)| {
+            let l = l.as_bytes();
+            l.len() == 3 && l[0] == b'\'' && l[2] == b'\'' && l[1].to_ascii_lowercase() == *c
+        })
+}
+
+/// Bits used to store enum literal index
+pub fn ghw_enum_width(literals: usize) -> u64 {
+    (u64::BITS - (literals.max(2) as u64 - 1).leading_zeros()) as u64
+}
+
+/// Range of array index
+#[derive(Clone, Debug)]
+struct GhwRange {
+    left: i64,
+    right: i64,
+    downto: bool,
+}
+
+impl GhwRange {
+    /// Indexes from left to right
+    fn indexes(&self) -> Vec<i64> {
+        if self.downto {
+            (self.right..=self.left).rev().collect()
+        } else {
+            (self.left..=self.right).collect()
+        }
+    }
+}
+
+/// Vhdl types used in ghw hierarchy
+#[derive(Clone, Debug)]
+enum GhwType {
+    /// Enumeration literals, `bit` and `std_ulogic` are enums too
+    Enum(Vec<String>),
+    /// Integer or physical type, with bits width
+    Integer(u64),
+    Real,
+    /// Array with element type, dimensions and ranges of dimensions, ranges is `None` when unbounded
+    Array(Box<GhwType>, usize, Option<Vec<GhwRange>>),
+    Record(Vec<(String, GhwType)>),
+}
+
+impl GhwType {
+    fn is_bounded(&self) -> bool {
+        match self {
+            GhwType::Array(element, _, ranges) => ranges.is_some() && element.is_bounded(),
+            GhwType::Record(fields) => fields.iter().all(|(_, t)| t.is_bounded()),
+            _ => true,
+        }
+    }
+
+    /// Is `bit` or `std_ulogic`, `Some(true)` for `std_ulogic`
+    fn logic(&self) -> Option<bool> {
+        match self {
+            GhwType::Enum(literals) if ghw_literals_match(literals, GHW_STD_LOGIC) => Some(true),
+            GhwType::Enum(literals) if ghw_literals_match(literals, GHW_BIT) => Some(false),
+            _ => None,
+        }
+    }
+}
+
+/// How a ghw signal value is stored in file
+#[derive(Clone, Copy, Debug)]
+enum GhwValueKind {
+    U8,
+    Signed,
+    F64,
+}
+
+impl From<&GhwType> for GhwValueKind {
+    fn from(value: &GhwType) -> Self {
+        match value {
+            GhwType::Integer(_) => Self::Signed,
+            GhwType::Real => Self::F64,
+            _ => Self::U8,
+        }
+    }
+}
+
+/// Kind of wave signal made of ghw signals
+#[derive(Clone, Debug)]
+enum GhwLeafKind {
+    /// `bit` / `std_ulogic` and vectors of them, `true` for `std_ulogic`
+    Bits(bool),
+    Enum,
+    Integer(u64),
+    Real,
+}
+
+/// Wave signal made of ghw signals, msb first
+#[derive(Clone, Debug)]
+struct GhwLeaf {
+    kind: GhwLeafKind,
+    signals: Vec<usize>,
+}
+
+/// Ghw hierarchy kinds
+mod kind {
+    pub const END: u8 = 0;
+    pub const BLOCK: u8 = 3;
+    pub const GENERATE_IF: u8 = 4;
+    pub const GENERATE_FOR: u8 = 5;
+    pub const INSTANCE: u8 = 6;
+    pub const PACKAGE: u8 = 7;
+    pub const PROCESS: u8 = 13;
+    pub const GENERIC: u8 = 14;
+    pub const END_OF_SCOPE: u8 = 15;
+    pub const SIGNAL: u8 = 16;
+    pub const PORT_LINKAGE: u8 = 21;
+    pub const TYPE_B2: u8 = 22;
+    pub const TYPE_E8: u8 = 23;
+    pub const TYPE_I32: u8 = 25;
+    pub const TYPE_I64: u8 = 26;
+    pub const TYPE_F64: u8 = 27;
+    pub const TYPE_P32: u8 = 28;
+    pub const TYPE_P64: u8 = 29;
+    pub const TYPE_ARRAY: u8 = 31;
+    pub const TYPE_RECORD: u8 = 32;
+    pub const SUBTYPE_SCALAR: u8 = 34;
+    pub const SUBTYPE_ARRAY: u8 = 35;
+    pub const SUBTYPE_UNBOUNDED_ARRAY: u8 = 37;
+    pub const SUBTYPE_RECORD: u8 = 38;
+    pub const SUBTYPE_UNBOUNDED_RECORD: u8 = 39;
+}
+
+/// Read ghw primitives from loaded data
+struct GhwReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    big_endian: bool,
+}
+
+impl<'a> GhwReader<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        match self.data.get(self.pos..self.pos + len) {
+            Some(v) => {
+                self.pos += len;
+                Ok(v)
+            }
+            None => Err(anyhow!("unexpected end of ghw file at {}", self.pos)),
+        }
+    }
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+    fn u32(&mut self) -> Result<u32> {
+        let v: [u8; 4] = self.bytes(4)?.try_into()?;
+        Ok(match self.big_endian {
+            true => u32::from_be_bytes(v),
+            false => u32::from_le_bytes(v),
+        })
+    }
+    fn i64(&mut self) -> Result<i64> {
+        let v: [u8; 8] = self.bytes(8)?.try_into()?;
+        Ok(match self.big_endian {
+            true => i64::from_be_bytes(v),
+            false => i64::from_le_bytes(v),
+        })
+    }
+    fn f64(&mut self) -> Result<f64> {
+        Ok(f64::from_le_bytes(self.bytes(8)?.try_into()?))
+    }
+    fn uleb(&mut self) -> Result<u64> {
+        let mut value = 0u64;
+        let mut shift = 0;
+        loop {
+            let c = self.u8()?;
+            if shift < 64 {
+                value |= ((c & 0x7f) as u64) << shift;
+            }
+            shift += 7;
+            if c & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+    }
+    fn sleb(&mut self) -> Result<i64> {
+        let mut value = 0i64;
+        let mut shift = 0;
+        loop {
+            let c = self.u8()?;
+            if shift < 64 {
+                value |= ((c & 0x7f) as i64) << shift;
+            }
+            shift += 7;
+            if c & 0x80 == 0 {
+                if shift < 64 && c & 0x40 != 0 {
+                    value |= -1 << shift;
+                }
+                return Ok(value);
+            }
+        }
+    }
+    /// Read 4 bytes section mark
+    fn mark(&mut self) -> Result<[u8; 4]> {
+        Ok(self.bytes(4)?.try_into()?)
+    }
+    fn expect_mark(&mut self, mark: &[u8; 4]) -> Result<()> {
+        let read = self.mark()?;
+        if &read != mark {
+            return Err(anyhow!(
+                "expected ghw section {:?}, got {:?}",
+                String::from_utf8_lossy(mark),
+                String::from_utf8_lossy(&read)
+            ));
+        }
+        Ok(())
+    }
+    /// Sections header starts with 4 zero bytes
+    fn zeros(&mut self) -> Result<()> {
+        if self.bytes(4)?.iter().any(|x| *x != 0) {
+            return Err(anyhow!("invalid ghw section header at {}", self.pos));
+        }
+        Ok(())
+    }
+    fn value(&mut self, kind: GhwValueKind) -> Result<i64> {
+        Ok(match kind {
+            GhwValueKind::U8 => self.u8()? as i64,
+            GhwValueKind::Signed => self.sleb()?,
+            GhwValueKind::F64 => self.f64()?.to_bits() as i64,
+        })
+    }
+}
+
+/// Ghw file reading state
+struct GhwParser<'a> {
+    reader: GhwReader<'a>,
+    version: u8,
+    strings: Vec<String>,
+    types: Vec<GhwType>,
+    builder: WaveTreeBuilder,
+    /// Wave signals indexed by wave id
+    leaves: Vec<GhwLeaf>,
+    /// Wave id of ghw signals lists, for ports and signals sharing same signals
+    leaf_ids: HashMap<Vec<usize>, u64>,
+    /// Value kind and wave ids indexed by ghw signal id
+    signals: Vec<Option<(GhwValueKind, Vec<u64>)>>,
+    enum_literals: HashMap<u64, Vec<String>>,
+    /// Current values indexed by ghw signal id
+    values: Vec<i64>,
+    /// Wave ids changed in current time step
+    changed: Vec<u64>,
+    is_changed: Vec<bool>,
+    data: HashMap<u64, WaveColumn>,
+    /// Wave ids to keep value changes, `None` to keep all
+    selected: Option<HashSet<u64>>,
+    /// Time window to keep value changes, `None` to keep all
+    window: Option<WaveWindow>,
+}
+
+impl<'a> GhwParser<'a> {
+    fn string(&mut self) -> Result<String> {
+        let id = self.reader.uleb()? as usize;
+        self.strings
+            .get(id)
+            .cloned()
+            .ok_or_else(|| anyhow!("invalid ghw string id {id}"))
+    }
+
+    fn typ(&mut self) -> Result<GhwType> {
+        let id = self.reader.uleb()? as usize;
+        match id {
+            0 => None,
+            _ => self.types.get(id - 1).cloned(),
+        }
+        .ok_or_else(|| anyhow!("invalid ghw type id {id}"))
+    }
+
+    fn read_header(&mut self) -> Result<()> {
+        if !is_ghw(self.reader.bytes(GHW_MAGIC.len())?) {
+            return Err(anyhow!("invalid ghw magic"));
+        }
+        let h = self.reader.bytes(16 - GHW_MAGIC.len())?;
+        if h[0] != 16 || h[1] != 0 || h[2] > 1 || h[6] != 0 {
+            return Err(anyhow!("unsupported ghw header {h:?}"));
+        }
+        self.version = h[2];
+        self.reader.big_endian = match h[3] {
+            1 => false,
+            2 => true,
+            _ => return Err(anyhow!("invalid ghw endian {}", h[3])),
+        };
+        Ok(())
+    }
+
+    /// Strings are stored with prefix length shared with previous string
+    fn read_strings(&mut self) -> Result<()> {
+        self.reader.zeros()?;
+        let count = self.reader.u32()?;
+        let _size = self.reader.u32()?;
+        self.strings = vec!["<anon>".to_string()];
+        let mut buf = vec![];
+        for _ in 0..count {
+            let mut c;
+            loop {
+                c = self.reader.u8()?;
+                if c <= 31 || (128..=159).contains(&c) {
+                    break;
+                }
+                buf.push(c);
+            }
+            self.strings.push(String::from_utf8_lossy(&buf).to_string());
+            let mut prefix = (c & 0x1f) as usize;
+            let mut shift = 5;
+            while c >= 128 {
+                c = self.reader.u8()?;
+                prefix |= ((c & 0x1f) as usize) << shift;
+                shift += 5;
+            }
+            buf.truncate(prefix);
+        }
+        self.reader.expect_mark(b"EOS\0")
+    }
+
+    fn read_range(&mut self) -> Result<GhwRange> {
+        let t = self.reader.u8()?;
+        let downto = t & 0x80 != 0;
+        let (left, right) = match t & 0x7f {
+            kind::TYPE_B2 | kind::TYPE_E8 => (self.reader.u8()? as i64, self.reader.u8()? as i64),
+            kind::TYPE_I32 | kind::TYPE_I64 | kind::TYPE_P32 | kind::TYPE_P64 => {
+                (self.reader.sleb()?, self.reader.sleb()?)
+            }
+            kind::TYPE_F64 => {
+                self.reader.f64()?;
+                self.reader.f64()?;
+                (0, 0)
+            }
+            k => return Err(anyhow!("invalid ghw range kind {k}")),
+        };
+        Ok(GhwRange {
+            left,
+            right,
+            downto,
+        })
+    }
+
+    /// Read bounds of an unbounded array or record type
+    fn read_bounds(&mut self, base: GhwType) -> Result<GhwType> {
+        match base {
+            GhwType::Array(element, dims, _) => {
+                let ranges = (0..dims)
+                    .map(|_| self.read_range())
+                    .collect::<Result<Vec<_>>>()?;
+                let element = match element.is_bounded() {
+                    true => *element,
+                    false => self.read_bounds(*element)?,
+                };
+                Ok(GhwType::Array(Box::new(element), dims, Some(ranges)))
+            }
+            GhwType::Record(fields) => {
+                let mut bounded = vec![];
+                for (name, typ) in fields {
+                    let typ = match typ.is_bounded() {
+                        true => typ,
+                        false => self.read_bounds(typ)?,
+                    };
+                    bounded.push((name, typ));
+                }
+                Ok(GhwType::Record(bounded))
+            }
+            typ => Ok(typ),
+        }
+    }
+
+    fn read_types(&mut self) -> Result<()> {
+        self.reader.zeros()?;
+        let count = self.reader.u32()?;
+        for _ in 0..count {
+            let k = self.reader.u8()?;
+            let _name = self.string()?;
+            let typ = match k {
+                kind::TYPE_B2 | kind::TYPE_E8 => {
+                    let count = self.reader.uleb()?;
+                    GhwType::Enum((0..count).map(|_| self.string()).collect::<Result<_>>()?)
+                }
+                kind::TYPE_I32 => GhwType::Integer(32),
+                kind::TYPE_I64 => GhwType::Integer(64),
+                kind::TYPE_F64 => GhwType::Real,
+                kind::TYPE_P32 | kind::TYPE_P64 => {
+                    if self.version > 0 {
+                        // units: name and value
+                        for _ in 0..self.reader.uleb()? {
+                            self.reader.uleb()?;
+                            self.reader.sleb()?;
+                        }
+                    }
+                    GhwType::Integer(if k == kind::TYPE_P32 { 32 } else { 64 })
+                }
+                kind::SUBTYPE_SCALAR => {
+                    let base = self.typ()?;
+                    self.read_range()?;
+                    base
+                }
+                kind::TYPE_ARRAY => {
+                    let element = self.typ()?;
+                    let dims = self.reader.uleb()? as usize;
+                    // index types, array type is always unbounded
+                    for _ in 0..dims {
+                        self.typ()?;
+                    }
+                    GhwType::Array(Box::new(element), dims, None)
+                }
+                kind::SUBTYPE_ARRAY | kind::SUBTYPE_RECORD => {
+                    let base = self.typ()?;
+                    self.read_bounds(base)?
+                }
+                kind::SUBTYPE_UNBOUNDED_ARRAY | kind::SUBTYPE_UNBOUNDED_RECORD => self.typ()?,
+                kind::TYPE_RECORD => {
+                    let count = self.reader.uleb()?;
+                    let mut fields = vec![];
+                    for _ in 0..count {
+                        fields.push((self.string()?, self.typ()?));
+                    }
+                    GhwType::Record(fields)
+                }
+                k => return Err(anyhow!("unsupported ghw type kind {k}")),
+            };
+            self.types.push(typ);
+        }
+        match self.reader.u8()? {
+            0 => Ok(()),
+            _ => Err(anyhow!("invalid ghw type section end")),
+        }
+    }
+
+    /// Well known types can be inferred from enum literals, just skip
+    fn read_well_known_types(&mut self) -> Result<()> {
+        self.reader.zeros()?;
+        while self.reader.u8()? != 0 {
+            self.typ()?;
+        }
+        Ok(())
+    }
+
+    fn read_signal_ids(&mut self, count: usize) -> Result<Vec<usize>> {
+        let mut ids = vec![];
+        for _ in 0..count {
+            let id = self.reader.uleb()? as usize;
+            if id == 0 || id >= self.signals.len() {
+                return Err(anyhow!("invalid ghw signal id {id}"));
+            }
+            ids.push(id);
+        }
+        Ok(ids)
+    }
+
+    /// Add wave signal to current scope
+    fn add_leaf(&mut self, name: String, kind: GhwLeafKind, typ: &GhwType, signals: Vec<usize>) {
+        let (width, signal_type) = match &kind {
+            GhwLeafKind::Bits(_) => (signals.len() as u64, WaveSignalType::Wire),
+            GhwLeafKind::Enum => match typ {
+                GhwType::Enum(literals) => (ghw_enum_width(literals.len()), WaveSignalType::Enum),
+                _ => (1, WaveSignalType::Enum),
+            },
+            GhwLeafKind::Integer(bits) => (*bits, WaveSignalType::Integer),
+            GhwLeafKind::Real => (64, WaveSignalType::Real),
+        };
+        let id = match self.leaf_ids.get(&signals) {
+            Some(id) => *id,
+            None => {
+                let id = self.leaves.len() as u64;
+                let value_kind = typ.into();
+                for signal in signals.iter() {
+                    let (_, ids) = self.signals[*signal].get_or_insert((value_kind, vec![]));
+                    ids.push(id);
+                }
+                if let GhwType::Enum(literals) = typ {
+                    if matches!(kind, GhwLeafKind::Enum) {
+                        self.enum_literals.insert(id, literals.clone());
+                    }
+                }
+                self.leaf_ids.insert(signals.clone(), id);
+                self.leaves.push(GhwLeaf { kind, signals });
+                id
+            }
+        };
+        self.builder.add_var(WaveSignalInfo {
+            id,
+            name,
+            width,
+            typ: signal_type,
+            direction: None,
+        });
+    }
+
+    /// Read signal ids of a declared signal, records and arrays become scopes
+    fn read_var(&mut self, name: String, typ: &GhwType) -> Result<()> {
+        match typ {
+            GhwType::Enum(_) => {
+                let signals = self.read_signal_ids(1)?;
+                let kind = match typ.logic() {
+                    Some(logic) => GhwLeafKind::Bits(logic),
+                    None => GhwLeafKind::Enum,
+                };
+                self.add_leaf(name, kind, typ, signals);
+            }
+            GhwType::Integer(bits) => {
+                let signals = self.read_signal_ids(1)?;
+                self.add_leaf(name, GhwLeafKind::Integer(*bits), typ, signals);
+            }
+            GhwType::Real => {
+                let signals = self.read_signal_ids(1)?;
+                self.add_leaf(name, GhwLeafKind::Real, typ, signals);
+            }
+            GhwType::Array(element, dims, Some(ranges)) => match (element.logic(), dims) {
+                (Some(logic), 1) => {
+                    let signals = self.read_signal_ids(ranges[0].indexes().len())?;
+                    self.add_leaf(name, GhwLeafKind::Bits(logic), element, signals);
+                }
+                _ => {
+                    self.builder.push_scope(name, WaveScopeType::Array);
+                    let inner = match dims {
+                        1 => *element.clone(),
+                        _ => GhwType::Array(element.clone(), dims - 1, Some(ranges[1..].to_vec())),
+                    };
+                    for index in ranges[0].indexes() {
+                        self.read_var(format!("[{index}]"), &inner)?;
+                    }
+                    self.builder.pop_scope()?;
+                }
+            },
+            GhwType::Array(_, _, None) => return Err(anyhow!("unbounded ghw signal {name}")),
+            GhwType::Record(fields) => {
+                self.builder.push_scope(name, WaveScopeType::Record);
+                for (field, typ) in fields {
+                    self.read_var(field.to_string(), typ)?;
+                }
+                self.builder.pop_scope()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn read_hierarchy(&mut self) -> Result<()> {
+        self.reader.zeros()?;
+        let _scopes = self.reader.u32()?;
+        let _vars = self.reader.u32()?;
+        let max_signal_id = self.reader.u32()? as usize;
+        self.signals = vec![None; max_signal_id + 1];
+        loop {
+            match self.reader.u8()? {
+                kind::END => return Ok(()),
+                kind::END_OF_SCOPE => self.builder.pop_scope()?,
+                // processes contain no signals and have no end of scope
+                kind::PROCESS => {
+                    self.string()?;
+                }
+                k @ (kind::BLOCK
+                | kind::GENERATE_IF
+                | kind::GENERATE_FOR
+                | kind::INSTANCE
+                | kind::PACKAGE
+                | kind::GENERIC) => {
+                    let mut name = self.string()?;
+                    if k == kind::GENERATE_FOR {
+                        let typ = self.typ()?;
+                        let value = match GhwValueKind::from(&typ) {
+                            GhwValueKind::F64 => self.reader.f64()?.to_string(),
+                            kind => self.reader.value(kind)?.to_string(),
+                        };
+                        name = format!("{name}({value})");
+                    }
+                    self.builder.push_scope(name, WaveScopeType::Module);
+                }
+                kind::SIGNAL..=kind::PORT_LINKAGE => {
+                    let name = self.string()?;
+                    let typ = self.typ()?;
+                    self.read_var(name, &typ)?;
+                }
+                k => return Err(anyhow!("unsupported ghw hierarchy kind {k}")),
+            }
+        }
+    }
+
+    /// Read value of ghw signal and mark wave signals changed
+    fn read_value(&mut self, id: usize) -> Result<()> {
+        let (kind, leaves) = self
+            .signals
+            .get(id)
+            .and_then(|s| s.as_ref())
+            .ok_or_else(|| anyhow!("unknown ghw signal {id}"))?;
+        self.values[id] = self.reader.value(*kind)?;
+        for leaf in leaves {
+            if !self.is_changed[*leaf as usize] {
+                self.is_changed[*leaf as usize] = true;
+                self.changed.push(*leaf);
+            }
+        }
+        Ok(())
+    }
+
+    /// Push changed wave signals values, columns are created for signals declared in `info`
+    fn finish_step(&mut self, info: &WaveInfo, timestamp: u64) -> Result<()> {
+        for id in std::mem::take(&mut self.changed) {
+            self.is_changed[id as usize] = false;
+            if let Some(selected) = &self.selected {
+                if !selected.contains(&id) {
+                    continue;
+                }
+            }
+            let value = self.leaf_value(&self.leaves[id as usize]);
+            let item = WaveDataItem { value, timestamp }.compress()?;
+            match &mut self.window {
+                Some(window) => window.push(&mut self.data, info, id, item)?,
+                None => self
+                    .data
+                    .entry(id)
+                    .or_insert_with(|| info.new_column(id))
+                    .push(item)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Snapshot contains values of all signals
+    fn read_snapshot(&mut self, info: &WaveInfo) -> Result<u64> {
+        self.reader.zeros()?;
+        let timestamp = self.reader.i64()? as u64;
+        for id in 0..self.signals.len() {
+            if self.signals[id].is_some() {
+                self.read_value(id)?;
+            }
+        }
+        self.finish_step(info, timestamp)?;
+        self.reader.expect_mark(b"ESN\0")?;
+        Ok(timestamp)
+    }
+
+    /// Cycle contains changed signals of time steps, returns first and last timestamp
+    fn read_cycle<F>(&mut self, info: &WaveInfo, progress_handler: &F) -> Result<(u64, u64)>
+    where
+        F: Fn(f32, u64),
+    {
+        let start = self.reader.i64()? as u64;
+        let mut timestamp = start;
+        let pos_skip = self.reader.data.len() / 1000;
+        let mut pos_notified = self.reader.pos;
+        loop {
+            let mut id = 0usize;
+            loop {
+                match self.reader.uleb()? as usize {
+                    0 => break,
+                    delta => id += delta,
+                }
+                self.read_value(id)?;
+            }
+            self.finish_step(info, timestamp)?;
+            if self.reader.pos > pos_notified + pos_skip {
+                pos_notified = self.reader.pos;
+                let progress = pos_notified as f32 / self.reader.data.len() as f32;
+                progress_handler(progress, timestamp);
+            }
+            match self.reader.sleb()? {
+                delta if delta < 0 => break,
+                delta => timestamp += delta as u64,
+            }
+        }
+        self.reader.expect_mark(b"ECY\0")?;
+        Ok((start, timestamp))
+    }
+
+    /// Current value of wave signal
+    fn leaf_value(&self, leaf: &GhwLeaf) -> WaveDataValue {
+        let values = &self.values;
+        let value = |index: usize| values[leaf.signals[index]];
+        match leaf.kind {
+            GhwLeafKind::Bits(logic) => Raw(leaf
+                .signals
+                .iter()
+                .map(|s| match logic {
+                    true => ghw_std_logic_value(values[*s] as u8),
+                    false if values[*s] == 0 => WireValue::V0,
+                    false => WireValue::V1,
+                })
+                .collect()),
+            GhwLeafKind::Enum => Comp(BigUint::from(value(0) as u64).to_bytes_le()),
+            GhwLeafKind::Integer(bits) => {
+                let v = match bits {
+                    32 => value(0) as u32 as u64,
+                    _ => value(0) as u64,
+                };
+                Comp(BigUint::from(v).to_bytes_le())
+            }
+            GhwLeafKind::Real => Real(f64::from_bits(value(0) as u64)),
+        }
+    }
+}
+
+impl Ghw {
+    /// Load ghw, keeping value changes of signals and time window selected by `filter` only
+    pub fn load_filtered<F>(
+        reader: &mut dyn Read,
+        progress_handler: F,
+        _last_timestamp: Option<u64>,
+        filter: &WaveFilter,
+    ) -> Result<Wave>
+    where
+        F: Fn(f32, u64),
+    {
+        info!("start parsing ghw file");
+        #[cfg(not(target_arch = "wasm32"))]
+        let perf_start = std::time::Instant::now();
+        let mut buf = vec![];
+        reader.read_to_end(&mut buf)?;
+        let mut parser = GhwParser {
+            reader: GhwReader {
+                data: &buf,
+                pos: 0,
+                big_endian: false,
+            },
+            version: 0,
+            strings: vec![],
+            types: vec![],
+            builder: WaveTreeBuilder::new(),
+            leaves: vec![],
+            leaf_ids: HashMap::new(),
+            signals: vec![],
+            enum_literals: HashMap::new(),
+            values: vec![],
+            changed: vec![],
+            is_changed: vec![],
+            data: HashMap::new(),
+            selected: None,
+            window: None,
+        };
+        parser.read_header()?;
+        loop {
+            match &parser.reader.mark()? {
+                b"STR\0" => parser.read_strings()?,
+                b"TYP\0" => parser.read_types()?,
+                b"WKT\0" => parser.read_well_known_types()?,
+                b"HIE\0" => parser.read_hierarchy()?,
+                b"EOH\0" => break,
+                m => {
+                    return Err(anyhow!(
+                        "unexpected ghw section {:?}",
+                        String::from_utf8_lossy(m)
+                    ))
+                }
+            }
+        }
+        let (code_signal_info, code_paths, code_aliases, tree) =
+            std::mem::take(&mut parser.builder).finish();
+        let mut info = WaveInfo {
+            // ghw always uses fs
+            timescale: (1, WaveTimescaleUnit::FS),
+            range: (0, 0),
+            headers: HashMap::from([("version".to_string(), format!("ghw {}", parser.version))]),
+            code_signal_info,
+            code_paths,
+            code_aliases,
+            code_enum_literals: std::mem::take(&mut parser.enum_literals),
+            tree,
+            dump_off: vec![],
+        };
+        parser.selected = filter.select(&info)?;
+        parser.window = filter.window(&info.timescale);
+        parser.values = vec![0; parser.signals.len()];
+        parser.is_changed = vec![false; parser.leaves.len()];
+        let mut range: Option<(u64, u64)> = None;
+        loop {
+            let mark = match parser.reader.mark() {
+                Ok(mark) => mark,
+                // simulation may be still running
+                Err(_) => break,
+            };
+            let (start, end) = match &mark {
+                b"SNP\0" => {
+                    let timestamp = parser.read_snapshot(&info)?;
+                    (timestamp, timestamp)
+                }
+                b"CYC\0" => parser.read_cycle(&info, &progress_handler)?,
+                b"DIR\0" => {
+                    parser.reader.bytes(4)?;
+                    let count = parser.reader.u32()? as usize;
+                    parser.reader.bytes(count * 8)?;
+                    parser.reader.expect_mark(b"EOD\0")?;
+                    continue;
+                }
+                b"TAI\0" => break,
+                m => {
+                    return Err(anyhow!(
+                        "unexpected ghw section {:?}",
+                        String::from_utf8_lossy(m)
+                    ))
+                }
+            };
+            range = Some(match range {
+                Some((s, e)) => (s.min(start), e.max(end)),
+                None => (start, end),
+            });
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let perf_stop = std::time::Instant::now();
+            info!("parse ghw use time: {:?}", perf_stop - perf_start);
+        }
+        info.range = range.unwrap_or_default();
+        if let Some(window) = parser.window.take() {
+            info.range = window.range(info.range);
+            window.finish(&mut parser.data, &info)?;
+        }
+        Ok(Wave {
+            info,
+            data: parser.data,
+            selected: parser.selected,
+        })
+    }
+}
+
+impl WaveLoader for Ghw {
+    fn name(&self) -> &'static str {
+        "GHW"
+    }
+    fn extensions(&self) -> &'static [&'static str] {
+        &["ghw"]
+    }
+    fn detect(&self, head: &[u8]) -> bool {
+        is_ghw(head)
+    }
+    fn load(
+        &self,
+        reader: &mut dyn Read,
+        progress_handler: &dyn Fn(f32, u64),
+        last_timestamp: Option<u64>,
+    ) -> Result<Wave> {
+        Self::load_filtered(
+            reader,
+            progress_handler,
+            last_timestamp,
+            &WaveFilter::default(),
+        )
+    }
+    fn load_source(
+        &self,
+        mut source: &mut dyn WaveSource,
+        progress_handler: &dyn Fn(f32, u64),
+        filter: &WaveFilter,
+    ) -> Result<(Wave, Option<u64>)> {
+        Ok((
+            Self::load_filtered(&mut source, progress_handler, None, filter)?,
+            None,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::wave::filter::WaveFilter;
+    use crate::wave::ghw_parser::{ghw_enum_width, ghw_std_logic_value, is_ghw, Ghw};
+    use crate::wave::utils::Node;
+    use crate::wave::{WaveLoader, WaveScopeType, WaveSignalType, WaveTimescaleUnit, WaveTreeNode};
+    use anyhow::Result;
+    use std::fs::File;
+    use std::io::Read;
+
+    #[test]
+    fn test_ghw_utils() {
+        assert_eq!(ghw_enum_width(2), 1);
+        assert_eq!(ghw_enum_width(3), 2);
+        assert_eq!(ghw_enum_width(4), 2);
+        assert_eq!(ghw_enum_width(9), 4);
+        let logic = (0..9)
+            .map(|v| ghw_std_logic_value(v).to_string())
+            .collect::<String>();
+        assert_eq!(logic, "xx01zx01x");
+        assert!(is_ghw(b"GHDLwave\n\x10"));
+        assert!(!is_ghw(b"$date"));
+    }
+
+    #[test]
+    fn test_ghw_parser() -> Result<()> {
+        let mut data = vec![];
+        File::open("data/fsm.ghw")?.read_to_end(&mut data)?;
+        assert!(is_ghw(&data));
+        let wave = Ghw.load(&mut data.as_slice(), &|_, _| {}, None)?;
+        println!("loaded wave: {wave}");
+        let tree = serde_json::to_string(&Node(wave.info.tree.root()))?;
+        println!("tree: {tree}");
+        assert!(matches!(wave.info.timescale, (1, WaveTimescaleUnit::FS)));
+        assert_eq!(wave.info.range, (0, 15000000));
+        // ports of `dut` share signals with `tb`
+        assert_eq!(wave.info.code_signal_info.len(), 9);
+        let path = |id: u64| wave.info.code_paths.get(&id).unwrap().join(".");
+        assert_eq!(path(0), "tb.clk");
+        assert_eq!(path(6), "tb.bus_r.addr");
+        assert_eq!(path(8), "tb.mem.[1]");
+        let scope_type = |name: &str| {
+            wave.info.tree.root().iter().find_map(|n| {
+                n.iter().find_map(|n| match n.data() {
+                    WaveTreeNode::WaveScope(s) if s.name == name => Some(s.typ.clone()),
+                    _ => None,
+                })
+            })
+        };
+        assert_eq!(scope_type("dut"), Some(WaveScopeType::Module));
+        assert_eq!(scope_type("bus_r"), Some(WaveScopeType::Record));
+        assert_eq!(scope_type("mem"), Some(WaveScopeType::Array));
+        let info = |id: u64| wave.info.code_signal_info.get(&id).unwrap();
+        assert_eq!((info(2).width, &info(2).typ), (2, &WaveSignalType::Enum));
+        assert_eq!(
+            (info(3).width, &info(3).typ),
+            (32, &WaveSignalType::Integer)
+        );
+        assert_eq!(info(4).width, 4);
+        let value = |id: u64, pos: u64| wave.find_value(id, pos).unwrap().value;
+        let literal = |id: u64, pos: u64| {
+            wave.info
+                .enum_literal(id, &value(id, pos))
+                .map(|s| s.to_string())
+        };
+        assert_eq!(value(0, 0).to_string(), "0");
+        assert_eq!(value(0, 7000000).to_string(), "1");
+        assert_eq!(value(1, 12000000).to_string(), "0");
+        assert_eq!(literal(2, 0).as_deref(), Some("idle"));
+        assert_eq!(literal(2, 5000000).as_deref(), Some("run"));
+        assert_eq!(literal(2, 20000000).as_deref(), Some("done"));
+        assert_eq!(literal(5, 10000000).as_deref(), Some("true"));
+        assert_eq!(literal(0, 0), None);
+        assert_eq!(value(3, 5000000).to_string(), "1");
+        assert_eq!(value(3, 10000000).to_string(), "fffffffd");
+        assert_eq!(value(4, 5000000).to_string(), "5");
+        assert_eq!(value(6, 10000000).to_string(), "2");
+        assert_eq!(value(8, 0).to_string(), "x");
+        assert_eq!(wave.data.get(&8).unwrap().len(), 2);
+        let filter = WaveFilter::parse("tb.bus_r")?;
+        let filtered = Ghw::load_filtered(&mut data.as_slice(), |_, _| {}, None, &filter)?;
+        assert_eq!(filtered.info.code_paths.len(), wave.info.code_paths.len());
+        assert!(filtered.is_loaded(6) && !filtered.is_loaded(0));
+        assert!(!filtered.data.contains_key(&0));
+        assert_eq!(
+            filtered.find_value(6, 10000000).unwrap().value.to_string(),
+            "2"
+        );
+        Ok(())
+    }
+}
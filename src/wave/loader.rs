@@ -0,0 +1,351 @@
+use crate::wave::csv_parser::Csv;
+use crate::wave::evcd_parser::Evcd;
+use crate::wave::fst_parser::Fst;
+use crate::wave::ghw_parser::Ghw;
+use crate::wave::sigrok_parser::Sigrok;
+use crate::wave::vcd_parser::Vcd;
+use crate::wave::wavedrom::WaveDrom;
+use crate::wave::{WaveLoader, WaveSource};
+use anyhow::{anyhow, Result};
+use std::fmt::{Display, Formatter};
+use std::io::{Cursor, Read, Seek, SeekFrom};
+
+/// Bytes at file start read to detect format, see [WaveLoader::detect]
+pub const WAVE_DETECT_SIZE: usize = 64 * 1024;
+
+/// Compression of wave files, decompressed on the fly while loading
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WaveCompression {
+    Gzip,
+    Zstd,
+    Xz,
+}
+
+impl Display for WaveCompression {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WaveCompression::Gzip => write!(f, "gzip"),
+            WaveCompression::Zstd => write!(f, "zstd"),
+            WaveCompression::Xz => write!(f, "xz"),
+        }
+    }
+}
+
+impl WaveCompression {
+    pub const ALL: [WaveCompression; 3] = [
+        WaveCompression::Gzip,
+        WaveCompression::Zstd,
+        WaveCompression::Xz,
+    ];
+    pub fn magic(&self) -> &'static [u8] {
+        match self {
+            WaveCompression::Gzip => &[0x1f, 0x8b],
+            WaveCompression::Zstd => &[0x28, 0xb5, 0x2f, 0xfd],
+            WaveCompression::Xz => &[0xfd, b'7', b'z', b'X', b'Z', 0x00],
+        }
+    }
+    pub fn extension(&self) -> &'static str {
+        match self {
+            WaveCompression::Gzip => "gz",
+            WaveCompression::Zstd => "zst",
+            WaveCompression::Xz => "xz",
+        }
+    }
+    /// Check magic bytes at file start
+    pub fn detect(head: &[u8]) -> Option<Self> {
+        Self::ALL.into_iter().find(|c| head.starts_with(c.magic()))
+    }
+    /// Compression of file named like `sim.vcd.gz`
+    pub fn from_name(name: &str) -> Option<Self> {
+        let (_, extension) = name.rsplit_once('.')?;
+        Self::ALL
+            .into_iter()
+            .find(|c| c.extension().eq_ignore_ascii_case(extension))
+    }
+    /// File name without compression extension, `sim.vcd.gz` to `sim.vcd`
+    pub fn strip_name(name: &str) -> &str {
+        match Self::from_name(name) {
+            Some(c) => &name[..name.len() - c.extension().len() - 1],
+            None => name,
+        }
+    }
+    /// Wrap `reader` of compressed data to read decompressed data.
+    /// Xz in web has no streaming decoder, it is decompressed to memory at once
+    pub fn decoder<'a, R: Read + 'a>(&self, reader: R) -> Result<Box<dyn Read + 'a>> {
+        Ok(match self {
+            WaveCompression::Gzip => Box::new(flate2::read::MultiGzDecoder::new(reader)),
+            WaveCompression::Zstd => Box::new(
+                ruzstd::streaming_decoder::StreamingDecoder::new(reader)
+                    .map_err(|e| anyhow!("invalid zstd data: {:?}", e))?,
+            ),
+            #[cfg(not(target_arch = "wasm32"))]
+            WaveCompression::Xz => Box::new(xz2::read::XzDecoder::new_multi_decoder(reader)),
+            #[cfg(target_arch = "wasm32")]
+            WaveCompression::Xz => {
+                let mut data = vec![];
+                lzma_rs::xz_decompress(&mut std::io::BufReader::new(reader), &mut data)
+                    .map_err(|e| anyhow!("invalid xz data: {:?}", e))?;
+                Box::new(Cursor::new(data))
+            }
+        })
+    }
+}
+
+/// Reader that cannot seek back, e.g. decompressed data, as [WaveSource].
+/// Seeking fails unless to current position, so that loaders can tell whether
+/// to read file end first
+pub struct WaveStream<R> {
+    inner: R,
+    position: u64,
+}
+
+impl<R> WaveStream<R> {
+    pub fn new(inner: R) -> Self {
+        Self { inner, position: 0 }
+    }
+}
+
+impl<R: Read> Read for WaveStream<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let sz = self.inner.read(buf)?;
+        self.position += sz as u64;
+        Ok(sz)
+    }
+}
+
+impl<R> Seek for WaveStream<R> {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        match pos {
+            SeekFrom::Start(position) if position == self.position => Ok(position),
+            SeekFrom::Current(0) => Ok(self.position),
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "cannot seek in wave stream",
+            )),
+        }
+    }
+}
+
+/// Wave file opened by [WaveLoaders::open], ready to load
+pub struct WaveOpened<'l, 'a> {
+    pub loader: &'l dyn WaveLoader,
+    /// Data to load from, compressed sources cannot seek
+    pub source: Box<dyn WaveSource + 'a>,
+    pub compression: Option<WaveCompression>,
+}
+
+/// Loaders of all supported formats, format of a file is detected by magic bytes first,
+/// then by file extension
+pub struct WaveLoaders {
+    loaders: Vec<Box<dyn WaveLoader>>,
+}
+
+impl Default for WaveLoaders {
+    fn default() -> Self {
+        let mut loaders = Self { loaders: vec![] };
+        loaders.register(Box::new(Fst));
+        loaders.register(Box::new(Ghw));
+        loaders.register(Box::new(Sigrok));
+        // extended vcd is plain vcd text too, detect it first
+        loaders.register(Box::new(Evcd));
+        loaders.register(Box::new(Vcd));
+        loaders.register(Box::new(Csv::default()));
+        loaders.register(Box::new(WaveDrom));
+        loaders
+    }
+}
+
+impl WaveLoaders {
+    /// Add loader of a new format, magic bytes are checked in registering order;
+    /// a loader with the same name is replaced in place, e.g. to change its options
+    pub fn register(&mut self, loader: Box<dyn WaveLoader>) {
+        match self.loaders.iter_mut().find(|l| l.name() == loader.name()) {
+            Some(existing) => *existing = loader,
+            None => self.loaders.push(loader),
+        }
+    }
+    pub fn iter(&self) -> impl Iterator<Item = &dyn WaveLoader> {
+        self.loaders.iter().map(|loader| loader.as_ref())
+    }
+    /// Extensions of all formats, for file dialogs
+    pub fn extensions(&self) -> Vec<&'static str> {
+        self.iter()
+            .flat_map(|loader| loader.extensions())
+            .copied()
+            .collect()
+    }
+    /// Find loader by extension of file `name`, compression extension is skipped
+    pub fn by_extension(&self, name: &str) -> Option<&dyn WaveLoader> {
+        let (_, extension) = WaveCompression::strip_name(name).rsplit_once('.')?;
+        self.iter().find(|loader| {
+            loader
+                .extensions()
+                .iter()
+                .any(|e| e.eq_ignore_ascii_case(extension))
+        })
+    }
+    /// Find loader of file `name` starting with `head`, `name` can be empty if unknown
+    pub fn detect(&self, name: &str, head: &[u8]) -> Option<&dyn WaveLoader> {
+        self.iter()
+            .find(|loader| loader.detect(head))
+            .or_else(|| self.by_extension(name))
+    }
+    /// Detect format of `reader` from its start, decompressing it on the fly if compressed
+    pub fn open<'a, R: Read + Seek + 'a>(
+        &self,
+        name: &str,
+        mut reader: R,
+    ) -> Result<WaveOpened<'_, 'a>> {
+        let mut head = vec![];
+        (&mut reader)
+            .take(WAVE_DETECT_SIZE as u64)
+            .read_to_end(&mut head)?;
+        reader.seek(SeekFrom::Start(0))?;
+        let compression = WaveCompression::detect(&head);
+        let source: Box<dyn WaveSource + 'a> = match compression {
+            Some(compression) => {
+                let mut decoder = compression.decoder(reader)?;
+                head.clear();
+                (&mut decoder)
+                    .take(WAVE_DETECT_SIZE as u64)
+                    .read_to_end(&mut head)?;
+                Box::new(WaveStream::new(Cursor::new(head.clone()).chain(decoder)))
+            }
+            None => Box::new(reader),
+        };
+        let loader = self
+            .detect(name, &head)
+            .ok_or_else(|| anyhow!("unknown wave format"))?;
+        Ok(WaveOpened {
+            loader,
+            source,
+            compression,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::wave::csv_parser::Csv;
+    use crate::wave::loader::{WaveCompression, WaveLoaders, WAVE_DETECT_SIZE};
+    use crate::wave::vcd_parser::{vcd_tail_end, Vcd};
+    use crate::wave::WaveLoader;
+    use anyhow::Result;
+    use std::fs::File;
+    use std::io::{Read, SeekFrom};
+
+    #[test]
+    fn test_wave_loaders() -> Result<()> {
+        let loaders = WaveLoaders::default();
+        for (file, name) in [
+            ("data/counter.vcd", "VCD"),
+            ("data/cpu_ila_commit.vcd", "VCD"),
+            ("data/counter.fst", "FST"),
+            ("data/fsm.ghw", "GHW"),
+            ("data/counter.sr", "sigrok"),
+            ("data/handshake.json", "WaveDrom"),
+        ] {
+            let mut head = vec![];
+            File::open(file)?
+                .take(WAVE_DETECT_SIZE as u64)
+                .read_to_end(&mut head)?;
+            // detected by magic bytes, whatever file is named
+            assert_eq!(loaders.detect("", &head).unwrap().name(), name);
+            assert_eq!(loaders.detect("wave.bin", &head).unwrap().name(), name);
+            let (wave, consumed) = loaders.detect(file, &head).unwrap().load_source(
+                &mut File::open(file)?,
+                &|_, _| {},
+                &Default::default(),
+            )?;
+            let mut data = vec![];
+            File::open(file)?.read_to_end(&mut data)?;
+            // only vcd can be followed after loading, from its last timestamp
+            let last = vcd_tail_end(&data).map(|end| end as u64);
+            assert_eq!(consumed, (name == "VCD").then_some(last).flatten());
+            let loaded =
+                loaders
+                    .detect("", &head)
+                    .unwrap()
+                    .load(&mut data.as_slice(), &|_, _| {}, None)?;
+            assert_eq!(wave.info.range, loaded.info.range);
+            assert_eq!(wave.data.len(), loaded.data.len());
+        }
+        let evcd = b"$scope module top $end $var port 1 <0 clk $end";
+        assert_eq!(loaders.detect("", evcd).unwrap().name(), "EVCD");
+        assert_eq!(loaders.detect("a.vcd", evcd).unwrap().name(), "EVCD");
+        // unknown magic falls back to extension
+        assert_eq!(loaders.detect("a.fst", b"").unwrap().name(), "FST");
+        assert_eq!(loaders.detect("a.VCD.gz", b"").unwrap().name(), "VCD");
+        assert!(loaders.detect("a.txt", b"hello").is_none());
+        assert!(loaders.detect("", b"").is_none());
+        assert!(loaders.extensions().contains(&"ghw"));
+        // ila csv by header, other csv by extension
+        let ila = b"Sample in Buffer,Sample in Window,TRIGGER,top/clk";
+        assert_eq!(loaders.detect("", ila).unwrap().name(), "CSV");
+        assert_eq!(loaders.detect("a.csv", b"time,clk").unwrap().name(), "CSV");
+        // registering again replaces loader of the same name
+        let count = loaders.iter().count();
+        let mut loaders = loaders;
+        loaders.register(Box::new(Csv::default()));
+        assert_eq!(loaders.iter().count(), count);
+        // vcd with leading blank lines
+        let vcd = b"\n\n$timescale 1ns $end";
+        assert_eq!(loaders.detect("", vcd).unwrap().name(), "VCD");
+        Ok(())
+    }
+
+    #[test]
+    fn test_wave_compressed() -> Result<()> {
+        let loaders = WaveLoaders::default();
+        let plain = Vcd.load(&mut File::open("data/counter.vcd")?, &|_, _| {}, None)?;
+        for file in [
+            "data/counter.vcd.gz",
+            "data/counter.vcd.zst",
+            "data/counter.vcd.xz",
+        ] {
+            let mut opened = loaders.open(file, File::open(file)?)?;
+            assert_eq!(opened.loader.name(), "VCD");
+            assert_eq!(opened.compression, WaveCompression::from_name(file));
+            // decompressed data cannot seek back to read last timestamp first
+            assert!(opened.source.seek(SeekFrom::End(0)).is_err());
+            let (wave, _) =
+                opened
+                    .loader
+                    .load_source(&mut *opened.source, &|_, _| {}, &Default::default())?;
+            assert_eq!(wave.info.range, plain.info.range);
+            assert_eq!(wave.data.len(), plain.data.len());
+            for (id, column) in plain.data.iter() {
+                assert_eq!(
+                    wave.data
+                        .get(id)
+                        .unwrap()
+                        .iter()
+                        .map(|i| i.to_string())
+                        .collect::<Vec<_>>(),
+                    column.iter().map(|i| i.to_string()).collect::<Vec<_>>()
+                );
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_wave_compression() {
+        assert_eq!(
+            WaveCompression::detect(&[0x1f, 0x8b, 0x08]),
+            Some(WaveCompression::Gzip)
+        );
+        assert_eq!(
+            WaveCompression::detect(b"\xfd7zXZ\x00\x00"),
+            Some(WaveCompression::Xz)
+        );
+        assert_eq!(WaveCompression::detect(b"$date"), None);
+        assert_eq!(
+            WaveCompression::from_name("sim.vcd.zst"),
+            Some(WaveCompression::Zstd)
+        );
+        assert_eq!(WaveCompression::from_name("sim.vcd"), None);
+        assert_eq!(WaveCompression::strip_name("sim.vcd.gz"), "sim.vcd");
+        assert_eq!(WaveCompression::strip_name("sim.fst"), "sim.fst");
+    }
+}
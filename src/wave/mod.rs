@@ -1,15 +1,31 @@
 use crate::radix::{radix_value_big_uint, radix_vector_to_string, Radix};
+use crate::wave::filter::WaveFilter;
+use crate::wave::storage::WaveColumn;
 use anyhow::{anyhow, Result};
 use num_bigint::BigUint;
+use num_traits::ToPrimitive;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::fmt::{Display, Formatter};
-use std::io::Read;
+use std::io::{Read, Seek};
+use std::pin::Pin;
 use trees::Tree;
 use vcd::{IdCode, Scope, ScopeType, Var, VarType};
 
+pub mod cache;
+pub mod csv_parser;
+pub mod evcd_parser;
+pub mod filter;
+pub mod fst_parser;
+pub mod ghw_parser;
+pub mod loader;
+pub mod sigrok_parser;
+pub mod storage;
+pub mod table_writer;
 pub mod utils;
 pub mod vcd_parser;
+pub mod vcd_writer;
+pub mod wavedrom;
 
 /// like [vcd::Value], basically for (de)serialize
 #[derive(Default, Serialize, Deserialize, Ord, PartialOrd, Eq, PartialEq, Copy, Clone, Debug)]
@@ -36,7 +52,7 @@ impl Display for WireValue {
     }
 }
 
-#[derive(Default, Serialize, Deserialize, Debug, Clone, Copy)]
+#[derive(Default, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
 pub enum WaveTimescaleUnit {
     S,
     MS,
@@ -74,13 +90,123 @@ impl WaveTimescaleUnit {
             FS => Some(PS),
         }
     }
+    /// Femtoseconds in one unit
+    pub fn femtoseconds(&self) -> u64 {
+        use WaveTimescaleUnit::*;
+        match self {
+            S => 1_000_000_000_000_000,
+            MS => 1_000_000_000_000,
+            US => 1_000_000_000,
+            NS => 1_000_000,
+            PS => 1_000,
+            FS => 1,
+        }
+    }
+    /// Parse unit name like `ns`, case insensitive
+    pub fn parse(text: &str) -> Option<Self> {
+        use WaveTimescaleUnit::*;
+        match text.to_ascii_lowercase().as_str() {
+            "s" => Some(S),
+            "ms" => Some(MS),
+            "us" => Some(US),
+            "ns" => Some(NS),
+            "ps" => Some(PS),
+            "fs" => Some(FS),
+            _ => None,
+        }
+    }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum WaveDataValue {
     /// when vec empty, invalid
     Comp(Vec<u8>),
     Raw(Vec<WireValue>),
+    /// Real number, from `real` vars
+    Real(f64),
+    /// Text, from `string` vars
+    String(String),
+    /// Port value with driving direction and strength, from extended vcd
+    Port(WavePortValue),
+}
+
+/// Side driving a port of extended vcd
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WavePortDirection {
+    /// Driven by test fixture
+    Input,
+    /// Driven by device under test
+    Output,
+    /// Driven by both sides at once, or by different sides in different time
+    Inout,
+}
+
+impl Display for WavePortDirection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                WavePortDirection::Input => "in",
+                WavePortDirection::Output => "out",
+                WavePortDirection::Inout => "inout",
+            }
+        )
+    }
+}
+
+impl WavePortDirection {
+    /// Direction of a port driven by `self` and `other`
+    pub fn merge(self, other: Self) -> Self {
+        match self == other {
+            true => self,
+            false => Self::Inout,
+        }
+    }
+}
+
+/// Port value of extended vcd `$dumpports`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct WavePortValue {
+    /// State chars of IEEE 1364 extended vcd, msb first, e.g. `D` input low, `H` output high
+    pub states: Vec<u8>,
+    /// Strengths of `0` and `1` components, from `0` (highz) to `7` (supply)
+    pub strengths: (u8, u8),
+}
+
+impl WavePortValue {
+    /// Wire value of state char, conflicting drivers are `x`
+    pub fn state_value(state: u8) -> WireValue {
+        match state {
+            b'D' | b'd' | b'L' | b'l' | b'0' => WireValue::V0,
+            b'U' | b'u' | b'H' | b'h' | b'1' => WireValue::V1,
+            b'Z' | b'T' | b'F' | b'f' => WireValue::Z,
+            _ => WireValue::X,
+        }
+    }
+    /// Side driving state char, `None` if not driven
+    pub fn state_direction(state: u8) -> Option<WavePortDirection> {
+        match state {
+            b'D' | b'U' | b'N' | b'Z' | b'd' | b'u' => Some(WavePortDirection::Input),
+            b'L' | b'H' | b'X' | b'T' | b'l' | b'h' => Some(WavePortDirection::Output),
+            b'F' | b'f' => None,
+            _ => Some(WavePortDirection::Inout),
+        }
+    }
+    /// Check if `state` is a state char of extended vcd
+    pub fn is_state(state: u8) -> bool {
+        b"DUNZduLHXTlh01?FAaBbCcf".contains(&state)
+    }
+    pub fn wires(&self) -> Vec<WireValue> {
+        self.states.iter().map(|s| Self::state_value(*s)).collect()
+    }
+    /// Side driving the port, `None` if no bit is driven
+    pub fn direction(&self) -> Option<WavePortDirection> {
+        self.states
+            .iter()
+            .filter_map(|s| Self::state_direction(*s))
+            .reduce(WavePortDirection::merge)
+    }
 }
 
 impl From<&WaveDataValue> for Option<BigUint> {
@@ -100,6 +226,13 @@ impl WaveDataValue {
                 BigUint::from_bytes_le(v).to_str_radix(radix.to_number() as u32)
             }
             WaveDataValue::Raw(v) => radix_vector_to_string(radix, v),
+            // reals and strings are not affected by radix
+            WaveDataValue::Real(v) => v.to_string(),
+            WaveDataValue::String(v) => v.to_string(),
+            // port states are msb first
+            WaveDataValue::Port(v) => {
+                radix_vector_to_string(radix, &v.wires().into_iter().rev().collect())
+            }
         }
     }
 }
@@ -136,12 +269,14 @@ impl WaveDataItem {
         if match &self.value {
             WaveDataValue::Comp(v) => v.len(),
             WaveDataValue::Raw(v) => v.len(),
+            WaveDataValue::Real(_) | WaveDataValue::String(_) | WaveDataValue::Port(_) => {
+                return Ok(self)
+            }
         } == 0
         {
             return Err(anyhow!("compressing invalid data!"));
         }
         match &self.value {
-            WaveDataValue::Comp(_) => Ok(self),
             WaveDataValue::Raw(v) => {
                 let rev_v = v.iter().rev().map(|x| x.clone()).collect::<Vec<_>>();
                 let ability = !v.iter().any(|i| i == &WireValue::X || i == &WireValue::Z);
@@ -152,6 +287,7 @@ impl WaveDataItem {
                     Ok(self)
                 }
             }
+            _ => Ok(self),
         }
     }
 }
@@ -177,6 +313,10 @@ pub enum WaveSignalType {
     Wire,
     WOr,
     String,
+    /// Vhdl enumeration, value is literal index
+    Enum,
+    /// Port of extended vcd
+    Port,
 }
 impl Display for WaveSignalType {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
@@ -216,6 +356,10 @@ pub enum WaveScopeType {
     Function,
     Begin,
     Fork,
+    /// Vhdl record signal, fields as children
+    Record,
+    /// Vhdl array signal, elements as children
+    Array,
 }
 impl From<ScopeType> for WaveScopeType {
     fn from(value: ScopeType) -> Self {
@@ -261,6 +405,9 @@ pub struct WaveSignalInfo {
     pub name: String,
     pub width: u64,
     pub typ: WaveSignalType,
+    /// Side driving the port in loaded data, for ports of extended vcd
+    #[serde(default)]
+    pub direction: Option<WavePortDirection>,
 }
 impl Display for WaveSignalInfo {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
@@ -274,6 +421,15 @@ impl Display for WaveSignalInfo {
         )
     }
 }
+impl WaveSignalInfo {
+    /// Type name with port direction, e.g. `port in`
+    pub fn type_name(&self) -> String {
+        match self.direction {
+            Some(direction) => format!("{} {}", self.typ, direction),
+            None => self.typ.to_string(),
+        }
+    }
+}
 impl From<&Var> for WaveSignalInfo {
     fn from(value: &Var) -> Self {
         let IdCode(id) = value.code;
@@ -282,11 +438,12 @@ impl From<&Var> for WaveSignalInfo {
             name: value.reference.to_string(),
             width: value.size.into(),
             typ: value.var_type.into(),
+            direction: None,
         }
     }
 }
 
-#[derive(Serialize, Clone, Default, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
 pub enum WaveTreeNode {
     #[default]
     WaveRoot,
@@ -322,15 +479,38 @@ pub struct WaveInfo {
     pub code_signal_info: HashMap<u64, WaveSignalInfo>,
     /// Signal path indexed by id
     pub code_paths: HashMap<u64, Vec<String>>,
+    /// Other paths of signals sharing one id with the signal in `code_paths`,
+    /// e.g. a port and the net connected to it
+    pub code_aliases: HashMap<u64, Vec<Vec<String>>>,
+    /// Enum literal names indexed by id, values are literal indexes
+    pub code_enum_literals: HashMap<u64, Vec<String>>,
     /// Signal scope and vars tree
     pub tree: Tree<WaveTreeNode>,
+    /// Intervals `[start, end)` where dumping is turned off by `$dumpoff` and values are
+    /// not recorded, `end` is `u64::MAX` until dumping is turned on again
+    pub dump_off: Vec<(u64, u64)>,
 }
 
 /// loaded wave data in memory
 #[derive(Clone)]
 pub struct Wave {
     pub info: WaveInfo,
+    /// Value changes indexed by id, stored in compact columns
+    pub data: HashMap<u64, WaveColumn>,
+    /// Ids whose value changes are loaded, `None` if all signals are loaded
+    pub selected: Option<HashSet<u64>>,
+}
+
+/// Wave data parsed from bytes appended to a loaded file
+#[derive(Clone, Default, Debug)]
+pub struct WaveTail {
     pub data: HashMap<u64, Vec<WaveDataItem>>,
+    /// Timestamp range in appended data, `None` if no timestamp appended
+    pub range: Option<(u64, u64)>,
+    /// `$dumpoff` (`false`) and `$dumpon` (`true`) with timestamps
+    pub dumping: Vec<(u64, bool)>,
+    /// Timestamps of `$dumpall`, `$dumpvars` and `$dumpon` blocks, see [WaveInfo::set_dumping]
+    pub checkpoints: BTreeSet<u64>,
 }
 
 impl Display for WaveInfo {
@@ -343,6 +523,77 @@ impl Display for WaveInfo {
     }
 }
 
+impl WaveInfo {
+    /// Empty column to push value changes of signal `id`, see [WaveColumn::for_signal]
+    pub fn new_column(&self, id: u64) -> WaveColumn {
+        WaveColumn::for_signal(self.code_signal_info.get(&id))
+    }
+    /// Get enum literal name of value
+    pub fn enum_literal(&self, id: u64, value: &WaveDataValue) -> Option<&str> {
+        let index: Option<BigUint> = value.into();
+        self.code_enum_literals
+            .get(&id)?
+            .get(index?.to_usize()?)
+            .map(|s| s.as_str())
+    }
+    /// Path of signal `id` followed by paths of its aliases
+    pub fn signal_paths(&self, id: u64) -> impl Iterator<Item = &Vec<String>> {
+        self.code_paths
+            .get(&id)
+            .into_iter()
+            .chain(self.code_aliases.get(&id).into_iter().flatten())
+    }
+    /// Paths of all signals and aliases with ids
+    pub fn all_signal_paths(&self) -> impl Iterator<Item = (u64, &Vec<String>)> {
+        self.code_paths.iter().map(|(id, path)| (*id, path)).chain(
+            self.code_aliases
+                .iter()
+                .flat_map(|(id, paths)| paths.iter().map(|path| (*id, path))),
+        )
+    }
+    /// Set sides driving ports found in loaded data, signals in tree are updated too
+    pub fn set_directions(&mut self, directions: &HashMap<u64, WavePortDirection>) {
+        fn update(
+            mut node: Pin<&mut trees::Node<WaveTreeNode>>,
+            directions: &HashMap<u64, WavePortDirection>,
+        ) {
+            if let WaveTreeNode::WaveVar(var) = node.data_mut() {
+                if let Some(direction) = directions.get(&var.id) {
+                    var.direction = Some(*direction);
+                }
+            }
+            for child in node.iter_mut() {
+                update(child, directions);
+            }
+        }
+        if directions.is_empty() {
+            return;
+        }
+        for (id, direction) in directions {
+            if let Some(info) = self.code_signal_info.get_mut(id) {
+                info.direction = Some(*direction);
+            }
+        }
+        update(self.tree.root_mut(), directions);
+    }
+    /// Turn dumping off or on at `timestamp`, called in time order
+    pub fn set_dumping(&mut self, timestamp: u64, on: bool) {
+        let dumping = !matches!(self.dump_off.last(), Some((_, u64::MAX)));
+        match (on, self.dump_off.last_mut()) {
+            (true, Some((_, end))) if !dumping => *end = timestamp,
+            (false, _) if dumping => self.dump_off.push((timestamp, u64::MAX)),
+            _ => {}
+        }
+    }
+    /// Dump-off interval containing `pos`
+    pub fn dump_off_at(&self, pos: u64) -> Option<(u64, u64)> {
+        self.dump_off
+            .iter()
+            .find(|(start, end)| *start <= pos && pos < *end)
+            .copied()
+    }
+}
+
 impl Display for Wave {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         self.info.fmt(f)
@@ -350,35 +601,114 @@ impl Display for Wave {
 }
 
 impl Wave {
-    /// Find *nearest* value
-    pub fn find_value(&self, id: u64, pos: u64) -> Option<WaveDataItem> {
-        // assert: pos keeps increase
-        if let Some(data) = self.data.get(&id) {
-            match data.binary_search_by_key(&pos, |x| x.timestamp) {
-                Ok(index) => {
-                    if data[index].timestamp == pos {
-                        Some(data[index].clone())
-                    } else {
-                        data.get(index - 1).cloned()
-                    }
+    /// Append data of a growing file and extend range,
+    /// items at the same timestamp of last item replace it, items before it are rejected
+    pub fn append(&mut self, tail: WaveTail) -> Result<()> {
+        for (id, items) in tail.data {
+            if !self.is_loaded(id) {
+                continue;
+            }
+            let info = &self.info;
+            let column = self.data.entry(id).or_insert_with(|| info.new_column(id));
+            for item in items {
+                if tail.checkpoints.contains(&item.timestamp) && column.last_value_is(&item.value) {
+                    continue;
+                }
+                match column.last_timestamp() {
+                    Some(last) if last == item.timestamp => column.replace_last(item)?,
+                    _ => column.push(item)?,
+                }
+            }
+        }
+        for (timestamp, on) in tail.dumping {
+            self.info.set_dumping(timestamp, on);
+        }
+        if let Some((start, stop)) = tail.range {
+            let range = &mut self.info.range;
+            *range = (range.0.min(start), range.1.max(stop));
+        }
+        Ok(())
+    }
+    /// Whether value changes of signal are loaded
+    pub fn is_loaded(&self, id: u64) -> bool {
+        match &self.selected {
+            Some(selected) => selected.contains(&id),
+            None => true,
+        }
+    }
+    /// Merge signals loaded on demand, signals already loaded are kept
+    pub fn merge(&mut self, other: Wave) {
+        let mut directions = HashMap::new();
+        for (id, column) in other.data {
+            if !self.is_loaded(id) {
+                self.data.insert(id, column);
+                if let Some(direction) = other
+                    .info
+                    .code_signal_info
+                    .get(&id)
+                    .and_then(|s| s.direction)
+                {
+                    directions.insert(id, direction);
                 }
-                Err(index) => data.get(index - 1).cloned(),
             }
-        } else {
-            None
+        }
+        self.info.set_directions(&directions);
+        match (&mut self.selected, other.selected) {
+            (Some(selected), Some(other)) => selected.extend(other),
+            (selected, None) => *selected = None,
+            _ => {}
+        }
+    }
+    /// Find *nearest* value, values are `x` from the start of dump-off interval
+    pub fn find_value(&self, id: u64, pos: u64) -> Option<WaveDataItem> {
+        let item = self.data.get(&id)?.find(pos)?;
+        match self.info.dump_off_at(pos) {
+            Some((start, _)) => {
+                let width = self
+                    .info
+                    .code_signal_info
+                    .get(&id)
+                    .map(|s| s.width)
+                    .unwrap_or(1)
+                    .max(1);
+                Some(WaveDataItem {
+                    value: WaveDataValue::Raw(vec![WireValue::X; width as usize]),
+                    timestamp: start,
+                })
+            }
+            None => Some(item),
         }
     }
 }
 
-/// To support other file formats
-pub trait WaveLoader {
-    fn load<F>(
+/// Readers that loaders can seek in, e.g. files, to read file end or jump between blocks
+pub trait WaveSource: Read + Seek {}
+
+impl<T: Read + Seek> WaveSource for T {}
+
+/// To support other file formats, loaders are registered in [loader::WaveLoaders]
+pub trait WaveLoader: Send + Sync {
+    /// Short format name shown to user, e.g. `VCD`
+    fn name(&self) -> &'static str;
+    /// File extensions of this format without dot
+    fn extensions(&self) -> &'static [&'static str];
+    /// Check magic bytes in `head`, at most [loader::WAVE_DETECT_SIZE] bytes at file start
+    fn detect(&self, head: &[u8]) -> bool;
+    /// Load the whole wave from `reader`
+    fn load(
+        &self,
         reader: &mut dyn Read,
-        progress_handler: F,
+        progress_handler: &dyn Fn(f32, u64),
         last_timestamp: Option<u64>,
-    ) -> Result<Wave>
-    where
-        F: Fn(f32, u64);
+    ) -> Result<Wave>;
+    /// Load wave from start of `source`, keeping value changes selected by `filter` only.
+    /// Returns wave and bytes consumed if more data appended to source can be parsed later
+    fn load_source(
+        &self,
+        source: &mut dyn WaveSource,
+        progress_handler: &dyn Fn(f32, u64),
+        filter: &WaveFilter,
+    ) -> Result<(Wave, Option<u64>)>;
 }
 
 /// To support preloader
@@ -401,7 +731,7 @@ mod test {
     #[test]
     fn test_load_wave() -> anyhow::Result<()> {
         let mut input = File::open("data/cpu_ila_commit.vcd")?;
-        let wave = Vcd::load(&mut input, |_, _| {}, None)?;
+        let wave = Vcd.load(&mut input, &|_, _| {}, None)?;
         println!("loaded wave: {wave}");
         // for item in &wave.data {
         //     println!("item: {}", item);
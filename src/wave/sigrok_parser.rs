@@ -0,0 +1,420 @@
+use crate::wave::filter::WaveFilter;
+use crate::wave::storage::WaveColumn;
+use crate::wave::utils::WaveTreeBuilder;
+use crate::wave::vcd_parser::vcd_column_push;
+use crate::wave::{
+    Wave, WaveDataItem, WaveDataValue, WaveInfo, WaveLoader, WaveScopeType, WaveSignalInfo,
+    WaveSignalType, WaveSource, WaveTimescaleUnit,
+};
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use std::io::{Cursor, Read, Seek, SeekFrom};
+use tracing::info;
+use zip::ZipArchive;
+
+/// Loader for sigrok session archives (`.sr`) saved by PulseView or `sigrok-cli`
+pub struct Sigrok;
+
+/// Magic bytes of a zip local file header
+const SIGROK_ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+
+/// Files written first into sigrok session archives
+const SIGROK_FIRST_FILES: &[&[u8]] = &[b"version", b"metadata"];
+
+/// Check if `data` starts with a zip archive whose first file is a sigrok session file
+pub fn is_sigrok(data: &[u8]) -> bool {
+    if !data.starts_with(SIGROK_ZIP_MAGIC) || data.len() < 30 {
+        return false;
+    }
+    let name_length = u16::from_le_bytes([data[26], data[27]]) as usize;
+    data.get(30..30 + name_length)
+        .map(|name| SIGROK_FIRST_FILES.contains(&name))
+        .unwrap_or(false)
+}
+
+/// Parse samplerate like `1 MHz` or `200kHz` into Hz
+pub fn sigrok_samplerate(text: &str) -> Option<u64> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(text.len());
+    let (value, unit) = text.split_at(split);
+    let scale = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "hz" => 1.0,
+        "khz" => 1e3,
+        "mhz" => 1e6,
+        "ghz" => 1e9,
+        _ => return None,
+    };
+    let rate = (value.parse::<f64>().ok()? * scale).round() as u64;
+    (rate > 0).then_some(rate)
+}
+
+/// Units of `unit` in one second
+fn sigrok_units_per_second(unit: &WaveTimescaleUnit) -> u64 {
+    WaveTimescaleUnit::S.femtoseconds() / unit.femtoseconds()
+}
+
+/// Timescale unit for samplerates, the largest unit where sample periods are whole,
+/// or else the largest unit where periods are at least 1000 units so rounding error is small
+pub fn sigrok_timescale_unit(samplerates: &[u64]) -> WaveTimescaleUnit {
+    let mut exact = None;
+    let mut fine = None;
+    let mut unit = Some(WaveTimescaleUnit::S);
+    while let Some(u) = unit {
+        let units = sigrok_units_per_second(&u);
+        if exact.is_none() && samplerates.iter().all(|rate| units % rate == 0) {
+            exact = Some(u);
+        }
+        if fine.is_none() && samplerates.iter().all(|rate| units / rate >= 1000) {
+            fine = Some(u);
+        }
+        unit = u.smaller();
+    }
+    exact.or(fine).unwrap_or(WaveTimescaleUnit::FS)
+}
+
+/// Timestamp of sample `index` in `unit`
+fn sigrok_timestamp(index: u64, samplerate: u64, unit: &WaveTimescaleUnit) -> u64 {
+    let units = sigrok_units_per_second(unit) as u128;
+    ((index as u128 * units + samplerate as u128 / 2) / samplerate as u128) as u64
+}
+
+/// Logic device described by a `[device N]` section of session metadata
+#[derive(Debug, Default, Clone, PartialEq)]
+struct SigrokDevice {
+    name: String,
+    capturefile: String,
+    samplerate: u64,
+    /// Bytes of one sample
+    unitsize: usize,
+    /// Bit index in sample and name of enabled probes
+    probes: Vec<(usize, String)>,
+}
+
+/// Parse `metadata` file of session archive, returns sigrok version and logic devices
+fn sigrok_metadata(text: &str) -> Result<(String, Vec<SigrokDevice>)> {
+    let mut version = String::new();
+    let mut devices: Vec<SigrokDevice> = vec![];
+    for line in text.lines().map(|l| l.trim()) {
+        if let Some(section) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            if section.starts_with("device") {
+                devices.push(SigrokDevice {
+                    name: section.split_whitespace().collect(),
+                    ..Default::default()
+                });
+            }
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let (key, value) = (key.trim(), value.trim());
+        if key == "sigrok version" {
+            version = value.to_string();
+        }
+        let Some(device) = devices.last_mut() else {
+            continue;
+        };
+        match key {
+            "capturefile" => device.capturefile = value.to_string(),
+            "samplerate" => {
+                device.samplerate = sigrok_samplerate(value)
+                    .ok_or_else(|| anyhow!("invalid samplerate {}", value))?
+            }
+            "unitsize" => device.unitsize = value.parse()?,
+            // older sessions have no unitsize
+            "total probes" if device.unitsize == 0 => {
+                device.unitsize = (value.parse::<usize>()? + 7) / 8
+            }
+            _ => {
+                if let Some(index) = key.strip_prefix("probe") {
+                    let index = index.parse::<usize>()?;
+                    if index == 0 {
+                        return Err(anyhow!("invalid probe index {}", key));
+                    }
+                    device.probes.push((index - 1, value.to_string()));
+                }
+            }
+        }
+    }
+    // devices with analog channels only have no logic capture
+    devices.retain(|device| !device.capturefile.is_empty() && !device.probes.is_empty());
+    for device in &devices {
+        if device.samplerate == 0 {
+            return Err(anyhow!("samplerate of {} not found", device.name));
+        }
+        if device
+            .probes
+            .iter()
+            .any(|(bit, _)| *bit >= device.unitsize * 8)
+        {
+            return Err(anyhow!("probe of {} out of unitsize", device.name));
+        }
+    }
+    Ok((version, devices))
+}
+
+/// Logic chunks of `capturefile` in archive, like `logic-1-1`, `logic-1-2`, in order
+fn sigrok_chunks<'a>(names: impl Iterator<Item = &'a str>, capturefile: &str) -> Vec<String> {
+    let mut chunks = names
+        .filter_map(|name| {
+            let index = match name.strip_prefix(capturefile)? {
+                // session version 1 has one chunk
+                "" => 0,
+                rest => rest.strip_prefix('-')?.parse::<u64>().ok()?,
+            };
+            Some((index, name.to_string()))
+        })
+        .collect::<Vec<_>>();
+    chunks.sort();
+    chunks.into_iter().map(|(_, name)| name).collect()
+}
+
+impl Sigrok {
+    /// Load session archive, keeping value changes of probes and time window selected by
+    /// `filter` only
+    pub fn load_filtered<R: Read + Seek>(
+        reader: R,
+        progress_handler: &dyn Fn(f32, u64),
+        filter: &WaveFilter,
+    ) -> Result<Wave> {
+        info!("start parsing sigrok session");
+        #[cfg(not(target_arch = "wasm32"))]
+        let perf_start = std::time::Instant::now();
+        let mut archive = ZipArchive::new(reader)?;
+        let mut metadata = String::new();
+        archive.by_name("metadata")?.read_to_string(&mut metadata)?;
+        let (version, devices) = sigrok_metadata(&metadata)?;
+        let unit = sigrok_timescale_unit(
+            &devices
+                .iter()
+                .map(|device| device.samplerate)
+                .collect::<Vec<_>>(),
+        );
+        // one scope per device, one signal per probe
+        let mut builder = WaveTreeBuilder::new();
+        let mut id = 0u64;
+        let mut device_ids = vec![];
+        for device in &devices {
+            builder.push_scope(device.name.clone(), WaveScopeType::Module);
+            let mut ids = vec![];
+            for (_, name) in &device.probes {
+                builder.add_var(WaveSignalInfo {
+                    id,
+                    name: name.to_string(),
+                    width: 1,
+                    typ: WaveSignalType::Wire,
+                    direction: None,
+                });
+                ids.push(id);
+                id += 1;
+            }
+            builder.pop_scope()?;
+            device_ids.push(ids);
+        }
+        let (code_signal_info, code_paths, code_aliases, tree) = builder.finish();
+        let mut headers = HashMap::new();
+        if !version.is_empty() {
+            headers.insert("version".to_string(), format!("sigrok {version}"));
+        }
+        let mut info = WaveInfo {
+            timescale: (1, unit),
+            range: (0, 0),
+            headers,
+            code_signal_info,
+            code_paths,
+            code_aliases,
+            code_enum_literals: HashMap::new(),
+            tree,
+            dump_off: vec![],
+        };
+        let selected = filter.select(&info)?;
+        let mut window = filter.window(&info.timescale);
+        let mut data: HashMap<u64, WaveColumn> = HashMap::new();
+        let device_chunks = devices
+            .iter()
+            .map(|device| sigrok_chunks(archive.file_names(), &device.capturefile))
+            .collect::<Vec<_>>();
+        let chunk_count = device_chunks.iter().map(|c| c.len()).sum::<usize>().max(1);
+        let mut chunks_loaded = 0;
+        let mut end = 0;
+        for ((device, ids), chunks) in devices.iter().zip(device_ids).zip(device_chunks) {
+            let probes = device
+                .probes
+                .iter()
+                .zip(ids)
+                .filter(|(_, id)| selected.as_ref().map(|s| s.contains(id)).unwrap_or(true))
+                .map(|((bit, _), id)| (*bit, id))
+                .collect::<Vec<_>>();
+            let mut last: Option<Vec<u8>> = None;
+            let mut index = 0u64;
+            // samples split between chunks
+            let mut pending = vec![];
+            for chunk in chunks {
+                archive.by_name(&chunk)?.read_to_end(&mut pending)?;
+                let samples = pending.len() / device.unitsize;
+                for sample in pending.chunks_exact(device.unitsize) {
+                    if last.as_deref() != Some(sample) {
+                        let timestamp = sigrok_timestamp(index, device.samplerate, &unit);
+                        for (bit, id) in &probes {
+                            let value = (sample[bit / 8] >> (bit % 8)) & 1;
+                            if let Some(last) = &last {
+                                if (last[bit / 8] >> (bit % 8)) & 1 == value {
+                                    continue;
+                                }
+                            }
+                            let item = WaveDataItem {
+                                value: WaveDataValue::Comp(vec![value]),
+                                timestamp,
+                            };
+                            vcd_column_push(&mut data, &mut window, &info, *id, item, false)?;
+                        }
+                        last = Some(sample.to_vec());
+                    }
+                    index += 1;
+                }
+                pending.drain(..samples * device.unitsize);
+                chunks_loaded += 1;
+                let timestamp = sigrok_timestamp(index, device.samplerate, &unit);
+                progress_handler(chunks_loaded as f32 / chunk_count as f32, timestamp);
+            }
+            if index > 0 {
+                end = end.max(sigrok_timestamp(index - 1, device.samplerate, &unit));
+            }
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        info!("parse sigrok session use time: {:?}", perf_start.elapsed());
+        info.range = (0, end);
+        if let Some(window) = window {
+            info.range = window.range(info.range);
+            window.finish(&mut data, &info)?;
+        }
+        Ok(Wave {
+            info,
+            data,
+            selected,
+        })
+    }
+}
+
+impl WaveLoader for Sigrok {
+    fn name(&self) -> &'static str {
+        "sigrok"
+    }
+    fn extensions(&self) -> &'static [&'static str] {
+        &["sr"]
+    }
+    fn detect(&self, head: &[u8]) -> bool {
+        is_sigrok(head)
+    }
+    fn load(
+        &self,
+        reader: &mut dyn Read,
+        progress_handler: &dyn Fn(f32, u64),
+        _last_timestamp: Option<u64>,
+    ) -> Result<Wave> {
+        let mut buf = vec![];
+        reader.read_to_end(&mut buf)?;
+        Self::load_filtered(Cursor::new(buf), progress_handler, &WaveFilter::default())
+    }
+    fn load_source(
+        &self,
+        source: &mut dyn WaveSource,
+        progress_handler: &dyn Fn(f32, u64),
+        filter: &WaveFilter,
+    ) -> Result<(Wave, Option<u64>)> {
+        // zip archives are read from the end, decompressing streams can not seek there
+        let wave = match source.seek(SeekFrom::End(0)) {
+            Ok(_) => Self::load_filtered(source, progress_handler, filter)?,
+            Err(_) => {
+                let mut buf = vec![];
+                source.read_to_end(&mut buf)?;
+                Self::load_filtered(Cursor::new(buf), progress_handler, filter)?
+            }
+        };
+        Ok((wave, None))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::wave::filter::WaveFilter;
+    use crate::wave::sigrok_parser::{
+        is_sigrok, sigrok_metadata, sigrok_samplerate, sigrok_timescale_unit, Sigrok,
+    };
+    use crate::wave::{WaveLoader, WaveTimescaleUnit};
+    use anyhow::Result;
+    use std::collections::HashSet;
+    use std::fs::File;
+    use std::io::Read;
+
+    #[test]
+    fn test_sigrok_metadata() -> Result<()> {
+        assert_eq!(sigrok_samplerate("1 MHz"), Some(1_000_000));
+        assert_eq!(sigrok_samplerate("24MHz"), Some(24_000_000));
+        assert_eq!(sigrok_samplerate("1.5 kHz"), Some(1500));
+        assert_eq!(sigrok_samplerate("200"), Some(200));
+        assert_eq!(sigrok_samplerate("fast"), None);
+        use WaveTimescaleUnit::*;
+        assert_eq!(sigrok_timescale_unit(&[1_000_000]), US);
+        assert_eq!(sigrok_timescale_unit(&[100_000_000]), NS);
+        assert_eq!(sigrok_timescale_unit(&[1_000_000, 250_000_000]), NS);
+        assert_eq!(sigrok_timescale_unit(&[1_000_000, 300_000_000]), PS);
+        // not whole in any unit
+        assert_eq!(sigrok_timescale_unit(&[24_000_000]), PS);
+        let (version, devices) = sigrok_metadata(
+            "[global]\nsigrok version=0.5.2\n\n\
+            [device 1]\ncapturefile=logic-1\ntotal probes=16\nsamplerate=24 MHz\n\
+            total analog=1\nprobe1=D0\nprobe10=D9\nanalog17=A0\n",
+        )?;
+        assert_eq!(version, "0.5.2");
+        assert_eq!(devices.len(), 1);
+        assert_eq!(devices[0].name, "device1");
+        assert_eq!(devices[0].unitsize, 2);
+        assert_eq!(
+            devices[0].probes,
+            vec![(0, "D0".to_string()), (9, "D9".to_string())]
+        );
+        assert!(sigrok_metadata("[device 1]\ncapturefile=logic-1\nprobe1=D0\n").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_sigrok() -> Result<()> {
+        let mut data = vec![];
+        File::open("data/counter.sr")?.read_to_end(&mut data)?;
+        assert!(is_sigrok(&data));
+        assert!(!is_sigrok(b"PK\x03\x04"));
+        let wave = Sigrok.load(&mut data.as_slice(), &|_, _| {}, None)?;
+        assert_eq!(wave.info.timescale, (1, WaveTimescaleUnit::US));
+        assert_eq!(wave.info.range, (0, 39));
+        assert_eq!(wave.info.headers.get("version").unwrap(), "sigrok 0.5.2");
+        assert_eq!(
+            wave.info.code_paths.get(&4).unwrap(),
+            &vec!["device1".to_string(), "CLK".to_string()]
+        );
+        let items = |id: u64| {
+            wave.data
+                .get(&id)
+                .unwrap()
+                .iter()
+                .map(|i| i.to_string())
+                .collect::<Vec<_>>()
+        };
+        // clock toggles every sample, count bit 0 every two samples
+        assert_eq!(items(4).len(), 40);
+        assert_eq!(items(0)[..3], ["#0 0", "#2 1", "#4 0"]);
+        // count bit 3 keeps set across the chunk boundary at sample 20
+        assert_eq!(items(3), vec!["#0 0", "#16 1", "#32 0"]);
+        let mut filter = WaveFilter::parse("device1.C3")?;
+        filter.set_window("20", "")?;
+        let (wave, consumed) =
+            Sigrok.load_source(&mut File::open("data/counter.sr")?, &|_, _| {}, &filter)?;
+        assert_eq!(consumed, None);
+        assert_eq!(wave.selected, Some(HashSet::from([3])));
+        assert_eq!(wave.info.range, (20, 39));
+        assert_eq!(wave.data.len(), 1);
+        Ok(())
+    }
+}
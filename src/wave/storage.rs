@@ -0,0 +1,719 @@
+use crate::wave::cache::{read_bytes, read_u64, write_bytes, write_u64};
+use crate::wave::{
+    WaveDataItem, WaveDataValue, WavePortValue, WaveSignalInfo, WaveSignalType, WireValue,
+};
+use anyhow::{anyhow, Result};
+use num_bigint::BigUint;
+use std::io::{Read, Write};
+
+/// Items between two search checkpoints in [WaveColumn]
+pub const WAVE_COLUMN_BLOCK: usize = 64;
+
+/// Value column of one signal
+#[derive(Clone, Default, Debug)]
+enum WaveColumnValues {
+    #[default]
+    Empty,
+    /// 4-state bits, 2 bits per wire value, `width` wire values per item, msb first.
+    /// Scalars take 2 bits per item
+    Wire {
+        width: usize,
+        bits: Vec<u8>,
+    },
+    Real(Vec<f64>),
+    String(Vec<String>),
+    /// Extended vcd ports, `width` state chars and a byte of strengths `s0 << 4 | s1` per item
+    Port {
+        width: usize,
+        states: Vec<u8>,
+        strengths: Vec<u8>,
+    },
+    /// Signal changed value type, e.g. vector signal dumped as real
+    Mixed(Vec<WaveDataValue>),
+}
+
+fn wire_to_bits(value: WireValue) -> u8 {
+    match value {
+        WireValue::V0 => 0,
+        WireValue::V1 => 1,
+        WireValue::X => 2,
+        WireValue::Z => 3,
+    }
+}
+
+fn bits_to_wire(bits: u8) -> WireValue {
+    match bits & 0b11 {
+        0 => WireValue::V0,
+        1 => WireValue::V1,
+        2 => WireValue::X,
+        _ => WireValue::Z,
+    }
+}
+
+fn wire_get(bits: &[u8], pos: usize) -> WireValue {
+    bits_to_wire(bits[pos >> 2] >> ((pos & 3) * 2))
+}
+
+/// Push wire value at `pos`, which must be the number of wire values pushed
+fn wire_push(bits: &mut Vec<u8>, pos: usize, value: WireValue) {
+    if pos & 3 == 0 {
+        bits.push(0);
+    }
+    bits[pos >> 2] |= wire_to_bits(value) << ((pos & 3) * 2);
+}
+
+/// Wire values of vector, msb first
+fn wire_values(value: WaveDataValue) -> Vec<WireValue> {
+    match value {
+        WaveDataValue::Comp(v) => {
+            let value = BigUint::from_bytes_le(&v);
+            (0..value.bits().max(1))
+                .rev()
+                .map(|i| match value.bit(i) {
+                    true => WireValue::V1,
+                    false => WireValue::V0,
+                })
+                .collect()
+        }
+        WaveDataValue::Raw(v) => v,
+        WaveDataValue::Port(v) => v.wires(),
+        WaveDataValue::Real(_) | WaveDataValue::String(_) => vec![],
+    }
+}
+
+/// Extend wire values to `width` like vcd does: `x` and `z` are extended, others extended by `0`
+fn wire_extend(values: &[WireValue], width: usize) -> impl Iterator<Item = WireValue> + '_ {
+    let pad = match values.first() {
+        Some(WireValue::X) => WireValue::X,
+        Some(WireValue::Z) => WireValue::Z,
+        _ => WireValue::V0,
+    };
+    (values.len()..width)
+        .map(move |_| pad)
+        .chain(values.iter().copied())
+}
+
+impl WaveColumnValues {
+    fn push(&mut self, len: usize, value: WaveDataValue) {
+        match (&mut *self, value) {
+            (Self::Empty, WaveDataValue::Real(v)) => *self = Self::Real(vec![v]),
+            (Self::Empty, WaveDataValue::String(v)) => *self = Self::String(vec![v]),
+            (Self::Empty, WaveDataValue::Port(v)) => {
+                *self = Self::Port {
+                    width: v.states.len(),
+                    states: v.states,
+                    strengths: vec![v.strengths.0 << 4 | v.strengths.1],
+                }
+            }
+            (Self::Empty, value) => {
+                *self = Self::Wire {
+                    width: 0,
+                    bits: vec![],
+                };
+                self.push(len, value);
+            }
+            (Self::Real(list), WaveDataValue::Real(v)) => list.push(v),
+            (Self::String(list), WaveDataValue::String(v)) => list.push(v),
+            (
+                Self::Port {
+                    width,
+                    states,
+                    strengths,
+                },
+                WaveDataValue::Port(v),
+            ) if v.states.len() == *width => {
+                states.extend_from_slice(&v.states);
+                strengths.push(v.strengths.0 << 4 | v.strengths.1);
+            }
+            (
+                Self::Wire { width, bits },
+                value @ (WaveDataValue::Comp(_) | WaveDataValue::Raw(_)),
+            ) => {
+                let values = wire_values(value);
+                if values.len() > *width {
+                    // value wider than declared or first value, repack pushed items in new width
+                    let mut widen = vec![];
+                    for index in 0..len {
+                        let item = (0..*width)
+                            .map(|i| wire_get(bits, index * *width + i))
+                            .collect::<Vec<_>>();
+                        for (i, v) in wire_extend(&item, values.len()).enumerate() {
+                            wire_push(&mut widen, index * values.len() + i, v);
+                        }
+                    }
+                    *width = values.len();
+                    *bits = widen;
+                }
+                for (i, v) in wire_extend(&values, *width).enumerate() {
+                    wire_push(bits, len * *width + i, v);
+                }
+            }
+            (Self::Mixed(list), value) => list.push(value),
+            (_, value) => {
+                let mut list = (0..len).map(|i| self.get(i)).collect::<Vec<_>>();
+                list.push(value);
+                *self = Self::Mixed(list);
+            }
+        }
+    }
+    fn get(&self, index: usize) -> WaveDataValue {
+        match self {
+            Self::Empty => WaveDataValue::default(),
+            Self::Wire { width, bits } => {
+                let values = (0..*width)
+                    .map(|i| wire_get(bits, index * width + i))
+                    .collect::<Vec<_>>();
+                if values
+                    .iter()
+                    .any(|v| matches!(v, WireValue::X | WireValue::Z))
+                {
+                    WaveDataValue::Raw(values)
+                } else {
+                    let mut bytes = vec![0u8; (values.len() + 7) >> 3];
+                    for (i, v) in values.iter().rev().enumerate() {
+                        if v == &WireValue::V1 {
+                            bytes[i / 8] |= 1 << (i % 8);
+                        }
+                    }
+                    WaveDataValue::Comp(BigUint::from_bytes_le(&bytes).to_bytes_le())
+                }
+            }
+            Self::Real(list) => WaveDataValue::Real(list[index]),
+            Self::String(list) => WaveDataValue::String(list[index].clone()),
+            Self::Port {
+                width,
+                states,
+                strengths,
+            } => WaveDataValue::Port(WavePortValue {
+                states: states[index * width..(index + 1) * width].to_vec(),
+                strengths: (strengths[index] >> 4, strengths[index] & 0xf),
+            }),
+            Self::Mixed(list) => list[index].clone(),
+        }
+    }
+    fn truncate(&mut self, len: usize) {
+        match self {
+            Self::Empty => {}
+            Self::Wire { width, bits } => {
+                let count = len * *width;
+                bits.truncate((count + 3) >> 2);
+                if count & 3 != 0 {
+                    if let Some(last) = bits.last_mut() {
+                        *last &= (1u8 << ((count & 3) * 2)) - 1;
+                    }
+                }
+            }
+            Self::Real(list) => list.truncate(len),
+            Self::String(list) => list.truncate(len),
+            Self::Port {
+                width,
+                states,
+                strengths,
+            } => {
+                states.truncate(len * *width);
+                strengths.truncate(len);
+            }
+            Self::Mixed(list) => list.truncate(len),
+        }
+    }
+    fn memory_size(&self) -> usize {
+        match self {
+            Self::Empty => 0,
+            Self::Wire { bits, .. } => bits.capacity(),
+            Self::Real(list) => list.capacity() * std::mem::size_of::<f64>(),
+            Self::String(list) => list
+                .iter()
+                .map(|s| s.capacity() + std::mem::size_of::<String>())
+                .sum(),
+            Self::Port {
+                states, strengths, ..
+            } => states.capacity() + strengths.capacity(),
+            Self::Mixed(list) => list.capacity() * std::mem::size_of::<WaveDataValue>(),
+        }
+    }
+}
+
+fn varint_push(buf: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buf.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+/// Read varint at `offset`, returns value and offset of next varint
+fn varint_read(buf: &[u8], mut offset: usize) -> (u64, usize) {
+    let mut value = 0u64;
+    let mut shift = 0;
+    loop {
+        let byte = buf[offset];
+        offset += 1;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return (value, offset);
+        }
+        shift += 7;
+    }
+}
+
+/// Value changes of one signal in columnar layout:
+/// timestamps are delta encoded varints, wire values are packed in 2 bits.
+/// Items must be pushed in timestamp order
+#[derive(Clone, Default, Debug)]
+pub struct WaveColumn {
+    /// Timestamp deltas from previous item
+    times: Vec<u8>,
+    /// Timestamp and offset in `times` of every [WAVE_COLUMN_BLOCK] items
+    blocks: Vec<(u64, usize)>,
+    values: WaveColumnValues,
+    len: usize,
+    last_timestamp: u64,
+    /// Offset in `times` and delta of last item, to replace it
+    last_delta: (usize, u64),
+}
+
+impl WaveColumn {
+    /// Empty column of signal declared by `info`, wire values are packed in declared width
+    /// so that narrower values are extended and pushed items are never repacked.
+    /// Columns of other types or of unknown width take the type of the first value
+    pub fn for_signal(info: Option<&WaveSignalInfo>) -> Self {
+        let values = match info {
+            Some(info)
+                if info.width > 0
+                    && !matches!(
+                        info.typ,
+                        WaveSignalType::Real
+                            | WaveSignalType::String
+                            | WaveSignalType::Enum
+                            | WaveSignalType::Port
+                    ) =>
+            {
+                WaveColumnValues::Wire {
+                    width: info.width as usize,
+                    bits: vec![],
+                }
+            }
+            _ => WaveColumnValues::Empty,
+        };
+        Self {
+            values,
+            ..Default::default()
+        }
+    }
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    pub fn last_timestamp(&self) -> Option<u64> {
+        match self.len {
+            0 => None,
+            _ => Some(self.last_timestamp),
+        }
+    }
+    /// Column of `items` in time order
+    pub fn from_items(items: impl IntoIterator<Item = WaveDataItem>) -> Result<Self> {
+        let mut column = Self::default();
+        for item in items {
+            column.push(item)?;
+        }
+        Ok(column)
+    }
+    /// Push `item` after the last one, timestamps must not go backwards as only deltas
+    /// are stored, an item before the last one is rejected
+    pub fn push(&mut self, item: WaveDataItem) -> Result<()> {
+        if self.len > 0 && item.timestamp < self.last_timestamp {
+            return Err(anyhow!(
+                "value change at #{} before last change at #{}",
+                item.timestamp,
+                self.last_timestamp
+            ));
+        }
+        let delta = match self.len {
+            0 => item.timestamp,
+            _ => item.timestamp - self.last_timestamp,
+        };
+        let timestamp = self.last_timestamp + delta;
+        if self.len == self.blocks.len() * WAVE_COLUMN_BLOCK {
+            self.blocks.push((timestamp, self.times.len()));
+        }
+        self.last_delta = (self.times.len(), delta);
+        varint_push(&mut self.times, delta);
+        self.values.push(self.len, item.value);
+        self.last_timestamp = timestamp;
+        self.len += 1;
+        Ok(())
+    }
+    /// Replace last item, or push if empty
+    pub fn replace_last(&mut self, item: WaveDataItem) -> Result<()> {
+        // timestamp before last item, 0 if only one item
+        let before = self.last_timestamp - self.last_delta.1;
+        if self.len > 0 && item.timestamp < before {
+            return Err(anyhow!(
+                "value change at #{} before last change at #{}",
+                item.timestamp,
+                before
+            ));
+        }
+        if self.len > 0 {
+            self.len -= 1;
+            self.times.truncate(self.last_delta.0);
+            self.last_timestamp -= self.last_delta.1;
+            if self.len == (self.blocks.len() - 1) * WAVE_COLUMN_BLOCK {
+                self.blocks.pop();
+            }
+            self.values.truncate(self.len);
+        }
+        self.push(item)
+    }
+    pub fn get(&self, index: usize) -> Option<WaveDataItem> {
+        self.iter_from(index).next()
+    }
+    /// Whether last value equals `value`, `false` if empty
+    pub fn last_value_is(&self, value: &WaveDataValue) -> bool {
+        match self.len {
+            0 => false,
+            len => self
+                .get(len - 1)
+                .map(|i| &i.value == value)
+                .unwrap_or(false),
+        }
+    }
+    /// Iterate items from `index`, values are decoded while iterating
+    pub fn iter_from(&self, index: usize) -> WaveColumnIter<'_> {
+        let mut iter = WaveColumnIter {
+            column: self,
+            index,
+            offset: 0,
+            timestamp: 0,
+        };
+        if index < self.len {
+            let block = index / WAVE_COLUMN_BLOCK;
+            let (timestamp, offset) = self.blocks[block];
+            (_, iter.offset) = varint_read(&self.times, offset);
+            iter.timestamp = timestamp;
+            for _ in block * WAVE_COLUMN_BLOCK..index {
+                let (delta, offset) = varint_read(&self.times, iter.offset);
+                iter.timestamp += delta;
+                iter.offset = offset;
+            }
+        }
+        iter
+    }
+    pub fn iter(&self) -> WaveColumnIter<'_> {
+        self.iter_from(0)
+    }
+    /// Index of first item at or after `pos`, `len()` if not found
+    pub fn lower_bound(&self, pos: u64) -> usize {
+        let block = self.blocks.partition_point(|(t, _)| *t < pos);
+        let start = block.saturating_sub(1) * WAVE_COLUMN_BLOCK;
+        let mut iter = self.iter_from(start);
+        while iter.index < self.len && iter.timestamp < pos {
+            iter.next();
+        }
+        iter.index.min(self.len)
+    }
+    /// Find *nearest* item at or before `pos`
+    pub fn find(&self, pos: u64) -> Option<WaveDataItem> {
+        match self.lower_bound(pos.saturating_add(1)) {
+            0 => None,
+            index => self.get(index - 1),
+        }
+    }
+    /// Heap bytes used by this column
+    pub fn memory_size(&self) -> usize {
+        self.times.capacity()
+            + self.blocks.capacity() * std::mem::size_of::<(u64, usize)>()
+            + self.values.memory_size()
+    }
+}
+
+impl WaveColumn {
+    /// Write column buffers as they are, for [crate::wave::cache]
+    pub(crate) fn write_to(&self, w: &mut dyn Write) -> Result<()> {
+        write_u64(w, self.len as u64)?;
+        write_u64(w, self.last_timestamp)?;
+        write_u64(w, self.last_delta.0 as u64)?;
+        write_u64(w, self.last_delta.1)?;
+        write_bytes(w, &self.times)?;
+        write_u64(w, self.blocks.len() as u64)?;
+        for (timestamp, offset) in &self.blocks {
+            write_u64(w, *timestamp)?;
+            write_u64(w, *offset as u64)?;
+        }
+        match &self.values {
+            WaveColumnValues::Empty => w.write_all(&[0])?,
+            WaveColumnValues::Wire { width, bits } => {
+                w.write_all(&[1])?;
+                write_u64(w, *width as u64)?;
+                write_bytes(w, bits)?;
+            }
+            WaveColumnValues::Real(list) => {
+                w.write_all(&[2])?;
+                write_u64(w, list.len() as u64)?;
+                for v in list {
+                    w.write_all(&v.to_le_bytes())?;
+                }
+            }
+            WaveColumnValues::String(list) => {
+                w.write_all(&[3])?;
+                write_u64(w, list.len() as u64)?;
+                for v in list {
+                    write_bytes(w, v.as_bytes())?;
+                }
+            }
+            WaveColumnValues::Port {
+                width,
+                states,
+                strengths,
+            } => {
+                w.write_all(&[5])?;
+                write_u64(w, *width as u64)?;
+                write_bytes(w, states)?;
+                write_bytes(w, strengths)?;
+            }
+            WaveColumnValues::Mixed(list) => {
+                w.write_all(&[4])?;
+                write_bytes(w, &serde_json::to_vec(list)?)?;
+            }
+        }
+        Ok(())
+    }
+    /// Read column written by [WaveColumn::write_to]
+    pub(crate) fn read_from(r: &mut dyn Read) -> Result<Self> {
+        let len = read_u64(r)? as usize;
+        let last_timestamp = read_u64(r)?;
+        let last_delta = (read_u64(r)? as usize, read_u64(r)?);
+        let times = read_bytes(r)?;
+        let blocks = (0..read_u64(r)?)
+            .map(|_| Ok((read_u64(r)?, read_u64(r)? as usize)))
+            .collect::<Result<Vec<_>>>()?;
+        let mut tag = [0u8];
+        r.read_exact(&mut tag)?;
+        let values = match tag[0] {
+            0 => WaveColumnValues::Empty,
+            1 => WaveColumnValues::Wire {
+                width: read_u64(r)? as usize,
+                bits: read_bytes(r)?,
+            },
+            2 => WaveColumnValues::Real(
+                (0..read_u64(r)?)
+                    .map(|_| {
+                        let mut buf = [0u8; 8];
+                        r.read_exact(&mut buf)?;
+                        Ok(f64::from_le_bytes(buf))
+                    })
+                    .collect::<Result<Vec<_>>>()?,
+            ),
+            3 => WaveColumnValues::String(
+                (0..read_u64(r)?)
+                    .map(|_| Ok(String::from_utf8(read_bytes(r)?)?))
+                    .collect::<Result<Vec<_>>>()?,
+            ),
+            4 => WaveColumnValues::Mixed(serde_json::from_slice(&read_bytes(r)?)?),
+            5 => WaveColumnValues::Port {
+                width: read_u64(r)? as usize,
+                states: read_bytes(r)?,
+                strengths: read_bytes(r)?,
+            },
+            tag => return Err(anyhow!("invalid column value type {}", tag)),
+        };
+        Ok(Self {
+            times,
+            blocks,
+            values,
+            len,
+            last_timestamp,
+            last_delta,
+        })
+    }
+}
+
+pub struct WaveColumnIter<'a> {
+    column: &'a WaveColumn,
+    index: usize,
+    /// Offset of timestamp delta of next item
+    offset: usize,
+    timestamp: u64,
+}
+
+impl<'a> Iterator for WaveColumnIter<'a> {
+    type Item = WaveDataItem;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.column.len {
+            return None;
+        }
+        let item = WaveDataItem {
+            value: self.column.values.get(self.index),
+            timestamp: self.timestamp,
+        };
+        self.index += 1;
+        if self.index < self.column.len {
+            let (delta, offset) = varint_read(&self.column.times, self.offset);
+            self.timestamp += delta;
+            self.offset = offset;
+        }
+        Some(item)
+    }
+}
+
+impl<'a> IntoIterator for &'a WaveColumn {
+    type Item = WaveDataItem;
+    type IntoIter = WaveColumnIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::wave::storage::{WaveColumn, WaveColumnValues, WAVE_COLUMN_BLOCK};
+    use crate::wave::WaveDataValue::{Comp, Port, Raw, Real, String};
+    use crate::wave::WireValue::{V0, V1, X, Z};
+    use crate::wave::{WaveDataItem, WaveDataValue, WavePortValue, WaveSignalInfo, WaveSignalType};
+
+    fn item(value: WaveDataValue, timestamp: u64) -> WaveDataItem {
+        WaveDataItem { value, timestamp }.compress().unwrap()
+    }
+
+    #[test]
+    fn test_wave_column() -> anyhow::Result<()> {
+        let items = (0..1000u64)
+            .map(|i| match i % 7 {
+                0 => item(Raw(vec![X]), i * 300),
+                3 => item(Raw(vec![Z, V0, V1]), i * 300),
+                _ => item(Raw(vec![V1, V0, V1, V1, V0, V0, V0, V1]), i * 300),
+            })
+            .collect::<Vec<_>>();
+        let column = WaveColumn::from_items(items.iter().cloned())?;
+        assert_eq!(column.len(), items.len());
+        // short `x` is extended to full width like vcd
+        assert_eq!(column.get(0).unwrap().to_string(), "#0 xx");
+        assert_eq!(column.get(3).unwrap().to_string(), "#900 zz");
+        for (i, (a, b)) in column.iter().zip(items.iter()).enumerate() {
+            if i % 7 != 0 && i % 7 != 3 {
+                assert_eq!(a.to_string(), b.to_string());
+            }
+        }
+        assert_eq!(column.lower_bound(0), 0);
+        assert_eq!(column.lower_bound(301), 2);
+        assert_eq!(column.lower_bound(1_000_000), items.len());
+        assert_eq!(column.find(300 * 100 + 299).unwrap().timestamp, 300 * 100);
+        assert_eq!(column.find(300 * 999 + 1).unwrap().timestamp, 300 * 999);
+        let mut column = column;
+        for _ in 0..WAVE_COLUMN_BLOCK + 1 {
+            column.replace_last(item(Raw(vec![V0]), 300 * 999))?;
+        }
+        column.push(item(Raw(vec![V1]), 300 * 1000))?;
+        assert_eq!(column.len(), items.len() + 1);
+        assert_eq!(column.find(300 * 999).unwrap().to_string(), "#299700 0");
+        assert_eq!(column.last_timestamp(), Some(300 * 1000));
+        Ok(())
+    }
+
+    #[test]
+    fn test_wave_column_push_backwards() -> anyhow::Result<()> {
+        let mut column = WaveColumn::default();
+        column.push(item(Raw(vec![V0]), 100))?;
+        column.push(item(Raw(vec![V1]), 200))?;
+        assert!(column.push(item(Raw(vec![V0]), 150)).is_err());
+        assert!(column.replace_last(item(Raw(vec![V0]), 50)).is_err());
+        // rejected items leave column unchanged
+        let values = column.iter().map(|i| i.to_string()).collect::<Vec<_>>();
+        assert_eq!(values, vec!["#100 0", "#200 1"]);
+        column.replace_last(item(Raw(vec![V0]), 100))?;
+        assert_eq!(column.last_timestamp(), Some(100));
+        Ok(())
+    }
+
+    #[test]
+    fn test_wave_column_declared_width() -> anyhow::Result<()> {
+        let info = WaveSignalInfo {
+            width: 8,
+            typ: WaveSignalType::Wire,
+            ..Default::default()
+        };
+        let mut column = WaveColumn::for_signal(Some(&info));
+        column.push(item(Raw(vec![V1]), 0))?;
+        column.push(item(Raw(vec![Z]), 1))?;
+        column.push(item(Raw(vec![V1, V0, V1, V1, V0, V0, V0, V1]), 2))?;
+        // narrow values are packed in declared width, high bits are not lost
+        assert!(matches!(
+            column.values,
+            WaveColumnValues::Wire { width: 8, .. }
+        ));
+        assert_eq!(column.get(0).unwrap().value, Comp(vec![1]));
+        assert_eq!(column.get(1).unwrap().value, Raw(vec![Z; 8]));
+        assert_eq!(column.get(2).unwrap().value, Comp(vec![0xb1]));
+        let info = WaveSignalInfo {
+            width: 64,
+            typ: WaveSignalType::Real,
+            ..Default::default()
+        };
+        let mut column = WaveColumn::for_signal(Some(&info));
+        column.push(item(Real(1.5), 0))?;
+        assert!(matches!(column.values, WaveColumnValues::Real(_)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_wave_column_types() -> anyhow::Result<()> {
+        let mut column = WaveColumn::default();
+        column.push(item(Real(1.5), 0))?;
+        column.push(item(Real(-2.0), 1))?;
+        assert_eq!(column.find(5).unwrap().to_string(), "#1 -2");
+        column.push(item(String("idle".to_string()), 2))?;
+        column.push(item(Raw(vec![V1, X]), 3))?;
+        let values = column.iter().map(|i| i.to_string()).collect::<Vec<_>>();
+        assert_eq!(values, vec!["#0 1.5", "#1 -2", "#2 idle", "#3 x"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_wave_column_port() -> anyhow::Result<()> {
+        let port = |states: &[u8], strengths| {
+            item(
+                Port(WavePortValue {
+                    states: states.to_vec(),
+                    strengths,
+                }),
+                0,
+            )
+        };
+        let mut column = WaveColumn::default();
+        column.push(port(b"DU", (6, 6)))?;
+        column.push(WaveDataItem {
+            timestamp: 5,
+            ..port(b"HX", (0, 5))
+        })?;
+        let mut buf = vec![];
+        column.write_to(&mut buf)?;
+        let column = WaveColumn::read_from(&mut buf.as_slice())?;
+        assert_eq!(column.get(1).unwrap().value, port(b"HX", (0, 5)).value);
+        assert!(column.last_value_is(&port(b"HX", (0, 5)).value));
+        assert_eq!(column.find(3).unwrap().to_string(), "#0 1");
+        Ok(())
+    }
+
+    #[test]
+    fn test_wave_column_memory() -> anyhow::Result<()> {
+        let items = (0..100_000u64)
+            .map(|i| item(Raw(vec![if i % 2 == 0 { V0 } else { V1 }]), i * 5))
+            .collect::<Vec<_>>();
+        let vec_size = items.capacity() * std::mem::size_of::<WaveDataItem>()
+            + items.len() * std::mem::size_of::<u8>();
+        let column = WaveColumn::from_items(items)?;
+        // one byte per timestamp delta, two bits per wire value and one block every 64 items
+        let packed = 100_000
+            + 100_000 / 4
+            + (100_000 + WAVE_COLUMN_BLOCK - 1) / WAVE_COLUMN_BLOCK
+                * std::mem::size_of::<(u64, usize)>();
+        assert!(column.memory_size() >= packed);
+        assert!(column.memory_size() < packed * 2);
+        assert!(column.memory_size() * 10 < vec_size);
+        Ok(())
+    }
+}
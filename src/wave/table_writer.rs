@@ -0,0 +1,331 @@
+use crate::radix::Radix;
+use crate::wave::wavedrom::wavedrom_write;
+use crate::wave::{Wave, WaveDataItem, WaveDataValue, WireValue};
+use anyhow::{anyhow, Result};
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::fmt::{Display, Formatter};
+use std::io::Write;
+
+/// File format of exported table
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TableFormat {
+    #[default]
+    Csv,
+    Json,
+    /// WaveDrom timing diagram, one period per row
+    WaveDrom,
+}
+
+impl Display for TableFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TableFormat::WaveDrom => write!(f, "WaveDrom"),
+            format => write!(f, "{}", format!("{format:?}").to_ascii_uppercase()),
+        }
+    }
+}
+
+impl TableFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            TableFormat::Csv => "csv",
+            TableFormat::Json | TableFormat::WaveDrom => "json",
+        }
+    }
+}
+
+/// Rows of exported table
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub enum TableRows {
+    /// One row per value change with time, signal path and value
+    #[default]
+    Changes,
+    /// One row every given timestamps, one column per signal
+    Step(u64),
+    /// One row at every rising edge of clock signal id, one column per signal,
+    /// with values sampled just before the edge like flip-flops do
+    Edge(u64),
+}
+
+/// Signal in exported table
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TableColumn {
+    pub id: u64,
+    pub path: String,
+    pub radix: Radix,
+    /// Write enum values as literal names
+    pub literals: bool,
+}
+
+/// Value changes of signals in a time range, written as csv or json
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct WaveTable {
+    pub columns: Vec<TableColumn>,
+    pub rows: TableRows,
+    /// Timestamp range, both ends included
+    pub range: (u64, u64),
+    pub format: TableFormat,
+}
+
+/// Quote csv cell if it contains separators, quotes or line breaks
+fn csv_cell(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+/// Whether `value` is a high single bit
+fn value_is_high(value: &WaveDataValue) -> bool {
+    match value {
+        WaveDataValue::Raw(v) => v.last() == Some(&WireValue::V1),
+        WaveDataValue::Port(v) => v.wires().last() == Some(&WireValue::V1),
+        value => Option::<BigUint>::from(value)
+            .map(|v| v.bit(0))
+            .unwrap_or_default(),
+    }
+}
+
+impl WaveTable {
+    /// Value text of `column` in its radix
+    pub(crate) fn value_text(
+        &self,
+        wave: &Wave,
+        column: &TableColumn,
+        value: &WaveDataValue,
+    ) -> String {
+        match wave.info.enum_literal(column.id, value) {
+            Some(literal) if column.literals => literal.to_string(),
+            _ => value.as_radix(column.radix.clone()),
+        }
+    }
+    /// Value changes of signal `id` inside range, value before range start is kept at start
+    fn column_changes(&self, wave: &Wave, id: u64) -> Vec<WaveDataItem> {
+        let (start, end) = self.range;
+        let mut items = wave
+            .find_value(id, start)
+            .map(|item| WaveDataItem {
+                timestamp: start,
+                ..item
+            })
+            .into_iter()
+            .collect::<Vec<_>>();
+        if let Some(column) = wave.data.get(&id) {
+            items.extend(
+                column
+                    .iter_from(column.lower_bound(start.saturating_add(1)))
+                    .take_while(|item| item.timestamp <= end),
+            );
+        }
+        items
+    }
+    /// Timestamps of rows in sampled table, timestamps of any value change for change list
+    pub(crate) fn row_timestamps(&self, wave: &Wave) -> Result<Vec<u64>> {
+        let (start, end) = self.range;
+        Ok(match &self.rows {
+            TableRows::Changes => {
+                let mut timestamps = self
+                    .columns
+                    .iter()
+                    .flat_map(|column| self.column_changes(wave, column.id))
+                    .map(|item| item.timestamp)
+                    .collect::<Vec<_>>();
+                timestamps.sort();
+                timestamps.dedup();
+                timestamps
+            }
+            TableRows::Step(0) => return Err(anyhow!("sample step must be positive")),
+            TableRows::Step(step) => (start..=end).step_by(*step as usize).collect(),
+            TableRows::Edge(clock) => {
+                let mut high = true;
+                let mut timestamps = vec![];
+                for item in self.column_changes(wave, *clock) {
+                    let next = value_is_high(&item.value);
+                    // a clock high at range start is not an edge
+                    if next && !high {
+                        timestamps.push(item.timestamp);
+                    }
+                    high = next;
+                }
+                timestamps
+            }
+        })
+    }
+    /// Time in timescale unit of `wave`
+    fn time(&self, wave: &Wave, timestamp: u64) -> u64 {
+        timestamp * wave.info.timescale.0
+    }
+    /// Write table to `writer` as csv, json or WaveDrom, times are in timescale unit of `wave`
+    pub fn write(&self, wave: &Wave, writer: &mut dyn Write) -> Result<()> {
+        if self.format == TableFormat::WaveDrom {
+            return wavedrom_write(self, wave, writer);
+        }
+        let unit = wave.info.timescale.1.to_string();
+        match &self.rows {
+            TableRows::Changes => {
+                let mut changes = self
+                    .columns
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(index, column)| {
+                        self.column_changes(wave, column.id)
+                            .into_iter()
+                            .map(move |item| (item.timestamp, index, item.value))
+                    })
+                    .collect::<Vec<_>>();
+                changes.sort_by_key(|(timestamp, index, _)| (*timestamp, *index));
+                let changes = changes.into_iter().map(|(timestamp, index, value)| {
+                    let column = &self.columns[index];
+                    let value = self.value_text(wave, column, &value);
+                    (self.time(wave, timestamp), column, value)
+                });
+                match self.format {
+                    TableFormat::Csv => {
+                        writeln!(writer, "time ({unit}),signal,value")?;
+                        for (time, column, value) in changes {
+                            writeln!(
+                                writer,
+                                "{time},{},{}",
+                                csv_cell(&column.path),
+                                csv_cell(&value)
+                            )?;
+                        }
+                    }
+                    TableFormat::Json | TableFormat::WaveDrom => {
+                        let changes = changes
+                            .map(|(time, column, value)| {
+                                json!({"time": time, "signal": column.path, "value": value})
+                            })
+                            .collect::<Vec<_>>();
+                        writeln!(writer, "{}", json!({"unit": unit, "changes": changes}))?;
+                    }
+                }
+            }
+            TableRows::Step(_) | TableRows::Edge(_) => {
+                let rows = self.row_timestamps(wave)?.into_iter().map(|timestamp| {
+                    let sample = match self.rows {
+                        TableRows::Edge(_) => timestamp.saturating_sub(1),
+                        _ => timestamp,
+                    };
+                    let values = self
+                        .columns
+                        .iter()
+                        .map(|column| {
+                            wave.find_value(column.id, sample)
+                                .map(|item| self.value_text(wave, column, &item.value))
+                                .unwrap_or_default()
+                        })
+                        .collect::<Vec<_>>();
+                    (self.time(wave, timestamp), values)
+                });
+                match self.format {
+                    TableFormat::Csv => {
+                        write!(writer, "time ({unit})")?;
+                        for column in &self.columns {
+                            write!(writer, ",{}", csv_cell(&column.path))?;
+                        }
+                        writeln!(writer)?;
+                        for (time, values) in rows {
+                            write!(writer, "{time}")?;
+                            for value in values {
+                                write!(writer, ",{}", csv_cell(&value))?;
+                            }
+                            writeln!(writer)?;
+                        }
+                    }
+                    TableFormat::Json | TableFormat::WaveDrom => {
+                        let signals = self
+                            .columns
+                            .iter()
+                            .map(|column| column.path.to_string())
+                            .collect::<Vec<_>>();
+                        let rows = rows
+                            .map(|(time, values)| {
+                                let mut row = Map::new();
+                                row.insert("time".to_string(), json!(time));
+                                for (signal, value) in signals.iter().zip(values) {
+                                    row.insert(signal.to_string(), json!(value));
+                                }
+                                Value::Object(row)
+                            })
+                            .collect::<Vec<_>>();
+                        writeln!(
+                            writer,
+                            "{}",
+                            json!({"unit": unit, "signals": signals, "rows": rows})
+                        )?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::radix::Radix;
+    use crate::wave::table_writer::{TableColumn, TableFormat, TableRows, WaveTable};
+    use crate::wave::vcd_parser::Vcd;
+    use crate::wave::WaveLoader;
+    use anyhow::Result;
+    use std::fs::File;
+
+    #[test]
+    fn test_table_write() -> Result<()> {
+        let wave = Vcd.load(&mut File::open("data/counter.vcd")?, &|_, _| {}, None)?;
+        let columns = [
+            (2, "top.counter.cnt", Radix::Dec),
+            (3, "top.counter.en", Radix::Hex),
+        ]
+        .into_iter()
+        .map(|(id, path, radix)| TableColumn {
+            id,
+            path: path.to_string(),
+            radix,
+            literals: true,
+        })
+        .collect::<Vec<_>>();
+        let mut table = WaveTable {
+            columns,
+            rows: TableRows::Changes,
+            range: (11, 25),
+            format: TableFormat::Csv,
+        };
+        let write = |table: &WaveTable| -> Result<String> {
+            let mut text = vec![];
+            table.write(&wave, &mut text)?;
+            Ok(String::from_utf8(text)?)
+        };
+        assert_eq!(
+            write(&table)?,
+            "time (ns),signal,value\n\
+            11,top.counter.cnt,0\n\
+            11,top.counter.en,1\n\
+            15,top.counter.cnt,1\n\
+            25,top.counter.cnt,2\n"
+        );
+        table.rows = TableRows::Edge(0);
+        assert_eq!(
+            write(&table)?,
+            "time (ns),top.counter.cnt,top.counter.en\n15,0,1\n25,1,1\n"
+        );
+        table.rows = TableRows::Step(10);
+        table.format = TableFormat::Json;
+        let json: serde_json::Value = serde_json::from_str(&write(&table)?)?;
+        assert_eq!(json["unit"], "ns");
+        assert_eq!(
+            json["rows"],
+            serde_json::json!([
+                {"time": 11, "top.counter.cnt": "0", "top.counter.en": "1"},
+                {"time": 21, "top.counter.cnt": "1", "top.counter.en": "1"},
+            ])
+        );
+        table.rows = TableRows::Step(0);
+        assert!(write(&table).is_err());
+        Ok(())
+    }
+}
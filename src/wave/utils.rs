@@ -1,5 +1,12 @@
+use crate::wave::WaveTreeNode::{WaveRoot, WaveScope, WaveVar};
+use crate::wave::{WaveScopeInfo, WaveScopeType, WaveSignalInfo, WaveTreeNode};
+use anyhow::{anyhow, Result};
 use serde::{ser::SerializeTuple, Serialize, Serializer};
+use std::collections::hash_map::{DefaultHasher, Entry};
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
 use std::ops::Deref;
+use trees::Tree;
 
 pub struct Node<'a, T>(pub &'a trees::Node<T>);
 
@@ -27,6 +34,128 @@ impl<'a, T: Serialize> Serialize for Node<'a, T> {
     }
 }
 
+/// Scope node used by [WaveTreeBuilder]
+#[derive(Default)]
+struct BuilderScope {
+    info: Option<WaveScopeInfo>,
+    /// Position in parent when merging scopes with same name
+    index: Option<usize>,
+    scopes: Vec<BuilderScope>,
+    vars: Vec<WaveSignalInfo>,
+}
+
+impl BuilderScope {
+    /// Vars are placed after scopes, same as vcd tree
+    fn into_tree(self) -> Tree<WaveTreeNode> {
+        let mut tree = Tree::new(match self.info {
+            Some(info) => WaveScope(info),
+            None => WaveRoot,
+        });
+        for scope in self.scopes {
+            tree.push_back(scope.into_tree());
+        }
+        for var in self.vars {
+            tree.push_back(Tree::new(WaveVar(var)));
+        }
+        tree
+    }
+}
+
+/// Build scope tree, code info and code paths from scope / var events,
+/// for loaders that do not read hierarchy with [vcd::Parser]
+#[derive(Default)]
+pub struct WaveTreeBuilder {
+    stack: Vec<BuilderScope>,
+    path: Vec<String>,
+    code_info: HashMap<u64, WaveSignalInfo>,
+    code_paths: HashMap<u64, Vec<String>>,
+    code_aliases: HashMap<u64, Vec<Vec<String>>>,
+}
+
+pub type WaveHierarchy = (
+    HashMap<u64, WaveSignalInfo>,
+    HashMap<u64, Vec<String>>,
+    HashMap<u64, Vec<Vec<String>>>,
+    Tree<WaveTreeNode>,
+);
+
+impl WaveTreeBuilder {
+    pub fn new() -> Self {
+        Self {
+            stack: vec![BuilderScope::default()],
+            ..Default::default()
+        }
+    }
+
+    /// Enter scope, scopes with same name in one parent are merged
+    pub fn push_scope(&mut self, name: String, typ: WaveScopeType) {
+        let parent = self.stack.last_mut().unwrap();
+        // same scope id generation as vcd tree
+        let scope_id = parent.info.as_ref().map(|i| i.id + 1).unwrap_or(0);
+        let mut hasher = DefaultHasher::new();
+        name.hash(&mut hasher);
+        scope_id.hash(&mut hasher);
+        let id = hasher.finish();
+        self.path.push(name.to_string());
+        let exists = parent
+            .scopes
+            .iter()
+            .position(|s| s.info.as_ref().map(|i| i.name == name).unwrap_or(false));
+        let scope = match exists {
+            Some(index) => BuilderScope {
+                index: Some(index),
+                ..parent.scopes.remove(index)
+            },
+            None => BuilderScope {
+                info: Some(WaveScopeInfo { id, name, typ }),
+                ..Default::default()
+            },
+        };
+        self.stack.push(scope);
+    }
+
+    /// Leave current scope
+    pub fn pop_scope(&mut self) -> Result<()> {
+        if self.stack.len() <= 1 {
+            return Err(anyhow!("unexpected upscope in hierarchy"));
+        }
+        let mut scope = self.stack.pop().unwrap();
+        let parent = self.stack.last_mut().unwrap();
+        match scope.index.take() {
+            Some(index) => parent.scopes.insert(index, scope),
+            None => parent.scopes.push(scope),
+        }
+        self.path.pop();
+        Ok(())
+    }
+
+    /// Add var to current scope, the first var wins for aliased ids
+    /// and paths of later ones are kept as aliases
+    pub fn add_var(&mut self, info: WaveSignalInfo) {
+        self.code_info
+            .entry(info.id)
+            .or_insert_with(|| info.clone());
+        let mut path = self.path.clone();
+        path.push(info.name.to_string());
+        match self.code_paths.entry(info.id) {
+            Entry::Occupied(_) => self.code_aliases.entry(info.id).or_default().push(path),
+            Entry::Vacant(entry) => {
+                entry.insert(path);
+            }
+        }
+        self.stack.last_mut().unwrap().vars.push(info);
+    }
+
+    /// Close all remaining scopes and get code info, code paths, aliases and scope tree
+    pub fn finish(mut self) -> WaveHierarchy {
+        while self.stack.len() > 1 {
+            self.pop_scope().unwrap();
+        }
+        let tree = self.stack.pop().unwrap().into_tree();
+        (self.code_info, self.code_paths, self.code_aliases, tree)
+    }
+}
+
 // fn main() {
 //     use trees::tr;
 //
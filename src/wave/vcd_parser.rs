@@ -1,20 +1,23 @@
+use crate::wave::filter::{WaveFilter, WaveWindow};
+use crate::wave::storage::WaveColumn;
 use crate::wave::WaveDataValue::Raw;
 use crate::wave::WaveTreeNode::WaveRoot;
 use crate::wave::{
-    Wave, WaveDataItem, WaveInfo, WaveLoader, WavePreLoader, WaveScopeInfo,
-    WaveSignalInfo, WaveTimescaleUnit, WaveTreeNode, WireValue,
+    Wave, WaveDataItem, WaveDataValue, WaveInfo, WaveLoader, WavePreLoader, WaveScopeInfo,
+    WaveSignalInfo, WaveSource, WaveTail, WaveTimescaleUnit, WaveTreeNode, WireValue,
 };
 use anyhow::{anyhow, Result};
-use queues::{IsQueue, Queue};
 use regex::Regex;
 use std::collections::hash_map::DefaultHasher;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::hash::{Hash, Hasher};
-use std::io::{BufReader, Read, Seek};
+use std::io::{BufReader, Read, Seek, SeekFrom};
 use std::slice::Iter;
 use tracing::info;
 use trees::Tree;
-use vcd::{Command, Header, IdCode, Scope, ScopeItem, TimescaleUnit, Value, Var};
+use vcd::{
+    Command, Header, IdCode, Scope, ScopeItem, SimulationCommand, TimescaleUnit, Value, Var,
+};
 
 pub struct Vcd;
 
@@ -58,34 +61,12 @@ pub fn vcd_tree_show(header: &Header) {
     header.items.iter().for_each(|item| show(item, 0));
 }
 
-type OnScopeType =
-    fn(result: &mut HashMap<IdCode, Vec<String>>, &mut Queue<String>, &Scope) -> Result<()>;
-
-type OnVarType =
-    fn(result: &mut HashMap<IdCode, Vec<String>>, &mut Queue<String>, &Var) -> Result<()>;
-
-fn vcd_iterate_path(
-    result: &mut HashMap<IdCode, Vec<String>>,
-    path: &mut Queue<String>,
-    items: &[ScopeItem],
-    on_scope: OnScopeType,
-    on_var: OnVarType,
-) -> Result<()> {
-    for item in items.iter() {
-        match item {
-            ScopeItem::Scope(scope) => on_scope(result, path, scope)?,
-            ScopeItem::Var(var) => on_var(result, path, var)?,
-            _ => {}
-        }
-    }
-    Ok(())
-}
-
 pub fn vcd_code_name(header: &Header) -> HashMap<IdCode, WaveSignalInfo> {
     fn add_to_map(m: &mut HashMap<IdCode, WaveSignalInfo>, it: Iter<'_, ScopeItem>) {
         it.for_each(|c| {
-            iterate(c).iter().for_each(|(k, s)| {
-                m.insert(*k, s.clone());
+            iterate(c).into_iter().for_each(|(k, s)| {
+                // the first var wins for aliased ids, same as code paths
+                m.entry(k).or_insert(s);
             })
         });
     }
@@ -183,51 +164,33 @@ pub fn vcd_tree(header: &Header) -> Result<Tree<WaveTreeNode>> {
     Ok(result)
 }
 
-pub fn vcd_code_path(header: &Header) -> Result<HashMap<IdCode, Vec<String>>> {
-    let mut map: HashMap<IdCode, Vec<String>> = HashMap::new();
-    /*
-        1 <-- header.items
-       / \
-      2   3 <-- scope.children
-     / | | \
-    4  5 6  7
-    */
-    let mut path: Queue<String> = Queue::new();
-    fn on_var(
-        result: &mut HashMap<IdCode, Vec<String>>,
-        path: &mut Queue<String>,
-        var: &Var,
-    ) -> Result<()> {
-        let mut vec = vec![];
-        while path.size() > 0 {
-            let v = path.remove().map_err(|e| anyhow!("cannot pop: {}", e))?;
-            vec.push(v);
-        }
-        for v in vec.iter() {
-            path.add(v.to_string())
-                .map_err(|e| anyhow!("cannot push: {}", e))?;
-        }
-        vec.push(var.reference.to_string());
-        result.insert(var.code, vec);
-        Ok(())
+/// Paths of all vars indexed by id code, in definition order.
+/// Vars sharing one id code are aliases, e.g. a port and the net connected to it
+pub fn vcd_code_path(header: &Header) -> HashMap<IdCode, Vec<Vec<String>>> {
+    fn iterate(
+        result: &mut HashMap<IdCode, Vec<Vec<String>>>,
+        path: &mut Vec<String>,
+        items: &[ScopeItem],
+    ) {
+        for item in items {
+            match item {
+                ScopeItem::Scope(scope) => {
+                    path.push(scope.identifier.to_string());
+                    iterate(result, path, scope.children.as_slice());
+                    path.pop();
+                }
+                ScopeItem::Var(var) => {
+                    let mut var_path = path.clone();
+                    var_path.push(var.reference.to_string());
+                    result.entry(var.code).or_default().push(var_path);
+                }
+                _ => {}
+            }
+        }
     }
-    fn on_scope(
-        result: &mut HashMap<IdCode, Vec<String>>,
-        path: &mut Queue<String>,
-        scope: &Scope,
-    ) -> Result<()> {
-        path.add(scope.identifier.to_string())
-            .map_err(|e| anyhow!("cannot add path queue: {}", e))?;
-        vcd_iterate_path(result, path, scope.children.as_slice(), on_scope, on_var)
-    }
-    vcd_iterate_path(
-        &mut map,
-        &mut path,
-        header.items.as_slice(),
-        on_scope,
-        on_var,
-    )?;
-    Ok(map)
+    let mut map = HashMap::new();
+    iterate(&mut map, &mut vec![], header.items.as_slice());
+    map
 }
 
 impl From<Value> for WireValue {
@@ -291,6 +254,8 @@ where
                 if let Some(number) = cap.get(1) {
                     result = number.as_str().parse().ok();
                 }
+                // lines are read backwards, the first timestamp found is the last one
+                break;
             }
             if cnt >= limit_lines {
                 break;
@@ -309,6 +274,452 @@ where
     }
 }
 
+/// Last timestamp read from end of `source` before parsing, `None` if source cannot seek,
+/// e.g. decompressed data. Source is rewound to start
+pub(crate) fn vcd_source_last_timestamp(source: &mut dyn WaveSource) -> Result<Option<u64>> {
+    let last_timestamp = match source.seek(SeekFrom::End(0)) {
+        Ok(_) => vcd_get_last_timestamp(BufReader::new(&mut *source)).0,
+        Err(_) => None,
+    };
+    source.seek(SeekFrom::Start(0))?;
+    Ok(last_timestamp)
+}
+
+/// Bytes searched backwards at once for line ends in [vcd_source_end]
+const VCD_TAIL_SEARCH_SIZE: u64 = 64 * 1024;
+
+/// Position of last `pattern` found searching backwards from `size` in `source`
+fn vcd_source_rfind(source: &mut dyn WaveSource, size: u64, pattern: &[u8]) -> Result<Option<u64>> {
+    let mut stop = size;
+    while stop > 0 {
+        let start = stop.saturating_sub(VCD_TAIL_SEARCH_SIZE);
+        let mut block = vec![0; (stop - start) as usize];
+        source.seek(SeekFrom::Start(start))?;
+        source.read_exact(&mut block)?;
+        if let Some(position) = block.windows(pattern.len()).rposition(|w| w == pattern) {
+            return Ok(Some(start + position as u64));
+        }
+        if start == 0 {
+            break;
+        }
+        // blocks overlap, so that patterns across them are found
+        stop = start + pattern.len() as u64 - 1;
+    }
+    Ok(None)
+}
+
+/// Where to stop parsing `source` that may be still being written, and where to continue
+/// when data is appended, `None` if source cannot seek. Source is rewound to start.
+///
+/// Parsing stops at the end of last complete line, a partly written line is never parsed.
+/// Appended data is parsed from [vcd_tail_end], so that the last timestamp block, which
+/// may be partly written, is read again as a whole, see [Wave::append]
+pub(crate) fn vcd_source_end(source: &mut dyn WaveSource) -> Result<Option<(u64, u64)>> {
+    let size = match source.seek(SeekFrom::End(0)) {
+        Ok(size) => size,
+        Err(_) => {
+            source.seek(SeekFrom::Start(0))?;
+            return Ok(None);
+        }
+    };
+    let line_end = vcd_source_rfind(source, size, b"\n")?
+        .map(|position| position + 1)
+        .unwrap_or(size);
+    let tail_end = vcd_source_rfind(source, line_end, b"\n#")?
+        .map(|position| position + 1)
+        .unwrap_or(line_end);
+    source.seek(SeekFrom::Start(0))?;
+    Ok(Some((line_end, tail_end)))
+}
+
+/// Convert value change command to signal id and item, other commands are ignored.
+/// Changes of signals not in `selected` are dropped
+fn vcd_data_item(
+    command: Command,
+    timestamp: u64,
+    selected: Option<&HashSet<u64>>,
+) -> Result<Option<(u64, WaveDataItem)>> {
+    let (id, value) = match command {
+        Command::ChangeScalar(IdCode(id), v) => (id, Raw(vec![v.into()])),
+        Command::ChangeVector(IdCode(id), v) => {
+            (id, Raw(v.into_iter().map(|x| x.into()).collect()))
+        }
+        Command::ChangeReal(IdCode(id), v) => (id, WaveDataValue::Real(v)),
+        Command::ChangeString(IdCode(id), v) => (id, WaveDataValue::String(v)),
+        _ => return Ok(None),
+    };
+    if selected.map(|s| !s.contains(&id)).unwrap_or(false) {
+        return Ok(None);
+    }
+    Ok(Some((id, WaveDataItem { value, timestamp }.compress()?)))
+}
+
+/// Push value change command to wave data or tail data, see [vcd_data_item]
+fn vcd_data_push(
+    data: &mut HashMap<u64, Vec<WaveDataItem>>,
+    command: Command,
+    timestamp: u64,
+    selected: Option<&HashSet<u64>>,
+) -> Result<()> {
+    if let Some((id, item)) = vcd_data_item(command, timestamp, selected)? {
+        data.entry(id).or_default().push(item);
+    }
+    Ok(())
+}
+
+/// Push value change to wave data, clipped by `window` if loading a time window.
+///
+/// `checkpoint` is set for values listed in checkpoint blocks like `$dumpall` or `$dumpon`,
+/// which are current values of all signals, so values same as previous ones are not changes
+pub(crate) fn vcd_column_push(
+    data: &mut HashMap<u64, WaveColumn>,
+    window: &mut Option<WaveWindow>,
+    info: &WaveInfo,
+    id: u64,
+    item: WaveDataItem,
+    checkpoint: bool,
+) -> Result<()> {
+    let repeated = |data: &HashMap<u64, WaveColumn>| {
+        data.get(&id)
+            .map(|c| c.last_value_is(&item.value))
+            .unwrap_or(false)
+    };
+    if checkpoint
+        && match window {
+            Some(window) => window
+                .last_value_is(&id, &item.value)
+                .unwrap_or_else(|| repeated(data)),
+            None => repeated(data),
+        }
+    {
+        return Ok(());
+    }
+    match window {
+        Some(window) => window.push(data, info, id, item),
+        None => data
+            .entry(id)
+            .or_insert_with(|| info.new_column(id))
+            .push(item),
+    }
+}
+
+/// Find where complete timestamps end in appended vcd data, which is the start of last
+/// `#timestamp` line, so that `$dumpvars ... $end` blocks are never split.
+pub fn vcd_tail_end(data: &[u8]) -> Option<usize> {
+    data.windows(2)
+        .rposition(|w| w == b"\n#")
+        .map(|position| position + 1)
+}
+
+/// End of complete lines in appended vcd `data` and start of the last `#timestamp` line
+/// before it, `0` if `data` starts in the last timestamp block, like [vcd_source_end]
+pub fn vcd_lines_end(data: &[u8]) -> Option<(usize, usize)> {
+    let line_end = data.iter().rposition(|c| *c == b'\n')? + 1;
+    let tail_end = vcd_tail_end(&data[..line_end]).unwrap_or(0);
+    Some((line_end, tail_end))
+}
+
+impl Vcd {
+    /// Parse vcd commands appended to a file that is still being written.
+    ///
+    /// * `reader`: starts at the last consumed byte and ends at [vcd_tail_end] or at the end
+    ///   of complete lines, see [vcd_lines_end]
+    /// * `timestamp`: last timestamp of loaded wave, for changes before first `#` in `reader`
+    pub fn load_tail(reader: &mut dyn Read, timestamp: u64) -> Result<WaveTail> {
+        Self::load_tail_selected(reader, timestamp, None)
+    }
+    /// Same as [Vcd::load_tail], but only keeps value changes of `selected` signals
+    fn load_tail_selected(
+        reader: &mut dyn Read,
+        timestamp: u64,
+        selected: Option<&HashSet<u64>>,
+    ) -> Result<WaveTail> {
+        let parser = vcd::Parser::new(reader);
+        let mut tail = WaveTail::default();
+        let mut timestamp = timestamp;
+        for command_result in parser {
+            match command_result? {
+                Command::Timestamp(t) => {
+                    tail.range = Some(match tail.range {
+                        Some((start, stop)) => (start.min(t), stop.max(t)),
+                        None => (t, t),
+                    });
+                    timestamp = t;
+                }
+                Command::Begin(command) => {
+                    match command {
+                        SimulationCommand::Dumpoff => tail.dumping.push((timestamp, false)),
+                        SimulationCommand::Dumpon => tail.dumping.push((timestamp, true)),
+                        _ => {}
+                    }
+                    if command != SimulationCommand::Dumpoff {
+                        tail.checkpoints.insert(timestamp);
+                    }
+                }
+                command => vcd_data_push(&mut tail.data, command, timestamp, selected)?,
+            }
+        }
+        Ok(tail)
+    }
+}
+
+/// Incremental vcd parser for streams that cannot seek, e.g. stdin, named pipes or sockets
+#[derive(Default)]
+pub struct VcdStream {
+    /// Received bytes not parsed yet
+    buffer: Vec<u8>,
+    header_parsed: bool,
+    /// Last timestamp parsed
+    timestamp: u64,
+}
+
+impl VcdStream {
+    pub fn feed(&mut self, data: &[u8]) {
+        self.buffer.extend_from_slice(data);
+    }
+    pub fn header_parsed(&self) -> bool {
+        self.header_parsed
+    }
+    /// Parse header once `$enddefinitions $end` is received, returns wave without value changes
+    pub fn header(&mut self) -> Result<Option<Wave>> {
+        if self.header_parsed {
+            return Ok(None);
+        }
+        let find = |data: &[u8], pattern: &[u8]| {
+            data.windows(pattern.len())
+                .position(|w| w == pattern)
+                .map(|position| position + pattern.len())
+        };
+        let end = find(&self.buffer, b"$enddefinitions")
+            .and_then(|start| find(&self.buffer[start..], b"$end").map(|end| start + end));
+        let end = match end {
+            Some(end) => end,
+            None => return Ok(None),
+        };
+        let mut wave = Vcd.load(&mut &self.buffer[..end], &|_, _| {}, None)?;
+        wave.info.range = (0, 0);
+        self.buffer.drain(..end);
+        self.header_parsed = true;
+        Ok(Some(wave))
+    }
+    /// Parse complete timestamps received, see [vcd_tail_end]
+    pub fn tail(&mut self) -> Result<Option<WaveTail>> {
+        if !self.header_parsed {
+            return Ok(None);
+        }
+        match vcd_tail_end(&self.buffer) {
+            Some(end) => self.parse(end),
+            None => Ok(None),
+        }
+    }
+    /// Take complete timestamps received to parse elsewhere, see [vcd_tail_end]
+    pub fn take(&mut self) -> Option<Vec<u8>> {
+        if !self.header_parsed {
+            return None;
+        }
+        vcd_tail_end(&self.buffer).map(|end| {
+            let rest = self.buffer.split_off(end);
+            std::mem::replace(&mut self.buffer, rest)
+        })
+    }
+    /// Take all bytes left
+    pub fn take_all(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.buffer)
+    }
+    /// Parse all bytes left when stream is closed
+    pub fn finish(&mut self) -> Result<Option<WaveTail>> {
+        if !self.header_parsed {
+            return Err(anyhow!("stream closed before $enddefinitions"));
+        }
+        self.parse(self.buffer.len())
+    }
+    fn parse(&mut self, end: usize) -> Result<Option<WaveTail>> {
+        let tail = Vcd::load_tail(&mut &self.buffer[..end], self.timestamp)?;
+        self.buffer.drain(..end);
+        if let Some((_, stop)) = tail.range {
+            self.timestamp = stop;
+        }
+        if tail.data.is_empty() && tail.range.is_none() {
+            Ok(None)
+        } else {
+            Ok(Some(tail))
+        }
+    }
+}
+
+/// Timestamp of chunk starting with a `#timestamp` line, see [VcdStream::take]
+#[cfg(not(target_arch = "wasm32"))]
+fn vcd_chunk_timestamp(chunk: &[u8]) -> Option<u64> {
+    let digits = chunk.strip_prefix(b"#")?;
+    let end = digits
+        .iter()
+        .position(|c| !c.is_ascii_digit())
+        .unwrap_or(digits.len());
+    std::str::from_utf8(&digits[..end]).ok()?.parse().ok()
+}
+
+/// Bytes read at once and parsed by a worker thread in [Vcd::load_parallel]
+#[cfg(not(target_arch = "wasm32"))]
+pub const VCD_PARALLEL_CHUNK_SIZE: usize = 4 * 1024 * 1024;
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Vcd {
+    /// Load vcd with value changes parsed on worker threads, gives the same wave as [WaveLoader::load].
+    ///
+    /// Value changes are read in chunks of about `chunk_size` bytes, split at `#timestamp` lines,
+    /// so that every chunk can be parsed independently and merged in order.
+    ///
+    /// * `threads`: number of worker threads, `0` to use all cores
+    /// * `filter`: signals to keep value changes, see [Vcd::load_filtered]
+    pub fn load_parallel<F>(
+        reader: &mut dyn Read,
+        progress_handler: F,
+        last_timestamp: Option<u64>,
+        threads: usize,
+        chunk_size: usize,
+        filter: &WaveFilter,
+    ) -> Result<Wave>
+    where
+        F: Fn(f32, u64),
+    {
+        use std::collections::BTreeMap;
+        use std::sync::{mpsc, Arc, Mutex};
+        info!("start parsing vcd file in parallel");
+        let perf_start = std::time::Instant::now();
+        let threads = match threads {
+            0 => std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1),
+            n => n,
+        };
+        let mut read_block = |stream: &mut VcdStream| -> Result<bool> {
+            let mut block = vec![];
+            let sz = (&mut *reader)
+                .take(chunk_size as u64)
+                .read_to_end(&mut block)?;
+            stream.feed(&block);
+            Ok(sz == 0)
+        };
+        let mut stream = VcdStream::default();
+        let mut wave = loop {
+            let eof = read_block(&mut stream)?;
+            if let Some(wave) = stream.header()? {
+                break wave;
+            }
+            if eof {
+                return Err(anyhow!("file ends before $enddefinitions"));
+            }
+        };
+        let selected = filter.select(&wave.info)?;
+        let mut window = filter.window(&wave.info.timescale);
+        let window_end = window.as_ref().map(|w| w.end);
+        let mut data: HashMap<u64, WaveColumn> = HashMap::new();
+        let mut time_start = 0xfffffffffffffu64;
+        let mut time_stop = 0u64;
+        let mut merge = |tail: WaveTail| -> Result<()> {
+            for (id, items) in tail.data {
+                for item in items {
+                    let checkpoint = tail.checkpoints.contains(&item.timestamp);
+                    vcd_column_push(&mut data, &mut window, &wave.info, id, item, checkpoint)?;
+                }
+            }
+            for (timestamp, on) in tail.dumping {
+                wave.info.set_dumping(timestamp, on);
+            }
+            if let Some((start, stop)) = tail.range {
+                time_start = time_start.min(start);
+                time_stop = time_stop.max(stop);
+                if let Some(last) = last_timestamp {
+                    progress_handler((stop - time_start) as f32 / last as f32, stop);
+                }
+            }
+            Ok(())
+        };
+        std::thread::scope(|scope| -> Result<()> {
+            // bounded, so that only a few chunks are held in memory
+            let (job_tx, job_rx) = mpsc::sync_channel::<(usize, Vec<u8>)>(threads * 2);
+            let job_rx = Arc::new(Mutex::new(job_rx));
+            let (result_tx, result_rx) = mpsc::channel();
+            for _ in 0..threads {
+                let job_rx = job_rx.clone();
+                let result_tx = result_tx.clone();
+                let selected = selected.as_ref();
+                scope.spawn(move || loop {
+                    let job = job_rx.lock().unwrap().recv();
+                    match job {
+                        // chunks after the first start with `#`, so initial timestamp is 0
+                        Ok((index, chunk)) => {
+                            let tail = Vcd::load_tail_selected(&mut chunk.as_slice(), 0, selected);
+                            // merging stopped on error, results are not needed
+                            if result_tx.send((index, tail)).is_err() {
+                                break;
+                            }
+                        }
+                        Err(_) => break,
+                    }
+                });
+            }
+            drop(result_tx);
+            // merge parsed chunks in order
+            let mut pending = BTreeMap::new();
+            let mut next = 0;
+            let mut receive = |index: usize, tail: Result<WaveTail>| -> Result<()> {
+                pending.insert(index, tail?);
+                while let Some(tail) = pending.remove(&next) {
+                    merge(tail)?;
+                    next += 1;
+                }
+                Ok(())
+            };
+            let mut index = 0;
+            loop {
+                let eof = read_block(&mut stream)?;
+                let chunk = match eof {
+                    true => Some(stream.take_all()),
+                    false => stream.take(),
+                };
+                if let Some(chunk) = chunk {
+                    // chunks after window are not needed, like in [Vcd::load_filtered]
+                    if matches!(
+                        (window_end, vcd_chunk_timestamp(&chunk)),
+                        (Some(end), Some(t)) if t > end
+                    ) {
+                        break;
+                    }
+                    job_tx
+                        .send((index, chunk))
+                        .map_err(|e| anyhow!("cannot send chunk: {}", e))?;
+                    index += 1;
+                }
+                while let Ok((index, tail)) = result_rx.try_recv() {
+                    receive(index, tail)?;
+                }
+                if eof {
+                    break;
+                }
+            }
+            drop(job_tx);
+            for (index, tail) in result_rx {
+                receive(index, tail)?;
+            }
+            Ok(())
+        })?;
+        wave.info.range = (time_start, time_stop);
+        if let Some(window) = window {
+            wave.info.range = window.range(wave.info.range);
+            window.finish(&mut data, &wave.info)?;
+        }
+        wave.data = data;
+        wave.selected = selected;
+        info!(
+            "parse vcd in {} threads use time: {:?}",
+            threads,
+            std::time::Instant::now() - perf_start
+        );
+        Ok(wave)
+    }
+}
+
 impl WavePreLoader for Vcd {
     fn last_timestamp<T>(reader: BufReader<T>) -> (Option<u64>, std::io::Result<BufReader<T>>)
     where
@@ -318,11 +729,13 @@ impl WavePreLoader for Vcd {
     }
 }
 
-impl WaveLoader for Vcd {
-    fn load<F>(
+impl Vcd {
+    /// Load vcd, keeping value changes of signals and time window selected by `filter` only
+    pub fn load_filtered<F>(
         reader: &mut dyn Read,
         progress_handler: F,
         last_timestamp: Option<u64>,
+        filter: &WaveFilter,
     ) -> Result<Wave>
     where
         F: Fn(f32, u64),
@@ -339,13 +752,15 @@ impl WaveLoader for Vcd {
                 (id, i.1)
             })
             .collect();
-        let code_paths = vcd_code_path(&header)?
-            .into_iter()
-            .map(|(i, path)| {
-                let IdCode(id) = i;
-                (id, path)
-            })
-            .collect();
+        let mut code_paths = HashMap::new();
+        let mut code_aliases = HashMap::new();
+        for (IdCode(id), mut paths) in vcd_code_path(&header) {
+            let aliases = paths.split_off(1);
+            code_paths.insert(id, paths.remove(0));
+            if !aliases.is_empty() {
+                code_aliases.insert(id, aliases);
+            }
+        }
         let tree = vcd_tree(&header)?;
         let mut headers: HashMap<String, String> = HashMap::new();
         if let Some(c) = header.comment.as_ref() {
@@ -362,7 +777,22 @@ impl WaveLoader for Vcd {
         } else {
             (1, WaveTimescaleUnit::default())
         };
-        let mut data: HashMap<u64, Vec<WaveDataItem>> = HashMap::new();
+        let mut info = WaveInfo {
+            timescale,
+            range: (0, 0),
+            headers,
+            code_signal_info: code_info,
+            code_paths,
+            code_aliases,
+            code_enum_literals: HashMap::new(),
+            tree,
+            dump_off: vec![],
+        };
+        let selected = filter.select(&info)?;
+        let mut window = filter.window(&info.timescale);
+        // timestamp of last `$dumpall`, `$dumpvars` or `$dumpon`
+        let mut checkpoint = None;
+        let mut data: HashMap<u64, WaveColumn> = HashMap::new();
         let mut timestamp = 0u64;
         let mut time_start = 0xfffffffffffffu64;
         let mut time_stop = 0u64;
@@ -383,6 +813,15 @@ impl WaveLoader for Vcd {
                         time_stop = t;
                     }
                     timestamp = t;
+                    // range ends at last timestamp read from tail, changes are not needed
+                    if let (true, Some(last)) = (filter.header_only, last_timestamp) {
+                        time_stop = time_stop.max(last);
+                        break;
+                    }
+                    // changes after window are not needed
+                    if matches!(&window, Some(window) if t > window.end) {
+                        break;
+                    }
                     if timestamp_skip > 0 && timestamp > time_start {
                         if let Some(last_timestamp) = last_timestamp {
                             if timestamp_notified + timestamp_skip < timestamp {
@@ -394,35 +833,23 @@ impl WaveLoader for Vcd {
                         }
                     }
                 }
-                Command::ChangeScalar(i, v) => {
-                    let IdCode(id) = i;
-                    let item = WaveDataItem {
-                        value: Raw(vec![v.into()]),
-                        timestamp,
+                Command::Begin(command) => {
+                    match command {
+                        SimulationCommand::Dumpoff => info.set_dumping(timestamp, false),
+                        SimulationCommand::Dumpon => info.set_dumping(timestamp, true),
+                        _ => {}
                     }
-                    .compress()?;
-                    if let Some(list) = data.get_mut(&id) {
-                        list.push(item);
-                    } else {
-                        data.insert(id, vec![item]);
+                    if command != SimulationCommand::Dumpoff {
+                        checkpoint = Some(timestamp);
                     }
                 }
-                Command::ChangeVector(i, v) => {
-                    let IdCode(id) = i;
-                    let item = WaveDataItem {
-                        value: Raw(v.into_iter().map(|x| x.into()).collect()),
-                        timestamp,
-                    }
-                    .compress()?;
-                    if let Some(list) = data.get_mut(&id) {
-                        list.push(item);
-                    } else {
-                        data.insert(id, vec![item]);
+                command => {
+                    if let Some((id, item)) = vcd_data_item(command, timestamp, selected.as_ref())?
+                    {
+                        let is_checkpoint = checkpoint == Some(timestamp);
+                        vcd_column_push(&mut data, &mut window, &info, id, item, is_checkpoint)?;
                     }
                 }
-                Command::ChangeReal(_, _) => {}
-                Command::ChangeString(_, _) => {}
-                _ => {}
             }
         }
         #[cfg(not(target_arch = "wasm32"))]
@@ -430,32 +857,118 @@ impl WaveLoader for Vcd {
             let perf_stop = std::time::Instant::now();
             info!("parse vcd use time: {:?}", perf_stop - perf_start);
         }
+        info.range = (time_start, time_stop);
+        if let Some(window) = window {
+            info.range = window.range(info.range);
+            window.finish(&mut data, &info)?;
+        }
         Ok(Wave {
-            info: WaveInfo {
-                timescale,
-                range: (time_start, time_stop),
-                headers,
-                code_signal_info: code_info,
-                code_paths,
-                tree,
-            },
+            info,
             data,
+            selected,
         })
     }
 }
 
+/// Check if `data` starts with a vcd declaration command like `$date`, vcd has no magic bytes
+pub fn is_vcd(data: &[u8]) -> bool {
+    data.iter().find(|c| !c.is_ascii_whitespace()) == Some(&b'$')
+}
+
+impl WaveLoader for Vcd {
+    fn name(&self) -> &'static str {
+        "VCD"
+    }
+    fn extensions(&self) -> &'static [&'static str] {
+        &["vcd"]
+    }
+    fn detect(&self, head: &[u8]) -> bool {
+        is_vcd(head)
+    }
+    fn load(
+        &self,
+        reader: &mut dyn Read,
+        progress_handler: &dyn Fn(f32, u64),
+        last_timestamp: Option<u64>,
+    ) -> Result<Wave> {
+        Self::load_filtered(
+            reader,
+            progress_handler,
+            last_timestamp,
+            &WaveFilter::default(),
+        )
+    }
+    fn load_source(
+        &self,
+        source: &mut dyn WaveSource,
+        progress_handler: &dyn Fn(f32, u64),
+        filter: &WaveFilter,
+    ) -> Result<(Wave, Option<u64>)> {
+        let last_timestamp = vcd_source_last_timestamp(source)?;
+        let end = vcd_source_end(source)?;
+        let mut limited = (&mut *source).take(end.map(|e| e.0).unwrap_or(u64::MAX));
+        let mut reader = BufReader::new(&mut limited);
+        #[cfg(not(target_arch = "wasm32"))]
+        let wave = match filter.header_only {
+            // header is parsed serially, no chunks to split
+            true => Self::load_filtered(&mut reader, progress_handler, last_timestamp, filter)?,
+            false => Self::load_parallel(
+                &mut reader,
+                progress_handler,
+                last_timestamp,
+                0,
+                VCD_PARALLEL_CHUNK_SIZE,
+                filter,
+            )?,
+        };
+        #[cfg(target_arch = "wasm32")]
+        let wave = Self::load_filtered(&mut reader, progress_handler, last_timestamp, filter)?;
+        drop(reader);
+        let consumed = match end {
+            Some((_, tail_end)) => tail_end,
+            None => source.stream_position()?,
+        };
+        // wave loaded in a time window ending before source end or without value changes
+        // is not followed
+        let follow = filter.end.is_none() && !filter.header_only;
+        Ok((wave, follow.then_some(consumed)))
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use crate::radix::radix_vector_to_string_n;
-    use crate::wave::vcd_parser::{vcd_code_name, vcd_header_show, vcd_tree_show, Vcd};
-    use crate::wave::WaveLoader;
+    use crate::radix::{radix_vector_to_string_n, Radix};
+    use crate::wave::filter::WaveFilter;
+    use crate::wave::vcd_parser::{
+        vcd_code_name, vcd_header_show, vcd_lines_end, vcd_tail_end, vcd_tree_show, Vcd, VcdStream,
+        VCD_PARALLEL_CHUNK_SIZE,
+    };
+    use crate::wave::{Wave, WaveDataValue, WaveLoader};
     use anyhow::Result;
+    use std::collections::HashSet;
     use std::fs::File;
-    use std::io::Read;
+    use std::io::{Cursor, Read};
     use tracing::{info, warn};
     use vcd::Command::{ChangeScalar, ChangeVector, Timestamp};
     use vcd::IdCode;
 
+    /// Value changes of signal `id` like `#5 1`, to compare loaded waves
+    fn column_items(wave: &Wave, id: u64) -> Vec<String> {
+        wave.data
+            .get(&id)
+            .map(|column| column.iter().map(|i| i.to_string()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Value changes of all loaded signals sorted by id, see [column_items]
+    fn wave_items(wave: &Wave) -> Vec<(u64, Vec<String>)> {
+        let mut ids = wave.data.keys().copied().collect::<Vec<_>>();
+        ids.sort();
+        ids.into_iter()
+            .map(|id| (id, column_items(wave, id)))
+            .collect()
+    }
+
     fn init() {
         std::env::set_var("RUST_LOG", "debug");
         tracing_subscriber::fmt::init();
@@ -501,7 +1014,7 @@ mod test {
     fn testing_vcd_parser(path: &str) -> Result<()> {
         info!("optimize_vcd_parser({})", path);
         if let Ok(mut input) = File::open(path) {
-            let v = Vcd::load(&mut input, |_, _| {}, None)?;
+            let v = Vcd.load(&mut input, &|_, _| {}, None)?;
             info!("code path: {:?}", v.info.code_paths);
         } else {
             warn!("file not found: {}", path);
@@ -509,6 +1022,349 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn test_vcd_real_string() -> Result<()> {
+        let mut input = File::open("data/real_string.vcd")?;
+        let wave = Vcd.load(&mut input, &|_, _| {}, None)?;
+        let value = |id: u64, pos: u64| wave.find_value(id, pos).unwrap().value;
+        assert!(matches!(value(1, 0), WaveDataValue::Real(v) if v == 0.0));
+        assert!(matches!(value(1, 7), WaveDataValue::Real(v) if v == 1.5));
+        assert_eq!(value(1, 12).to_string(), "-2.25");
+        assert!(matches!(value(2, 3), WaveDataValue::String(ref v) if v == "idle"));
+        assert_eq!(value(2, 10).to_string(), "busy");
+        assert_eq!(value(2, 20).as_radix(Radix::Bin), "done");
+        assert_eq!(wave.data.get(&2).unwrap().len(), 3);
+        Ok(())
+    }
+
+    /// Load a vcd file in two parts, as if it is still being written
+    #[test]
+    fn test_vcd_load_tail() -> Result<()> {
+        let mut data = vec![];
+        File::open("data/counter.vcd")?.read_to_end(&mut data)?;
+        let full = Vcd.load(&mut data.as_slice(), &|_, _| {}, None)?;
+        let text = String::from_utf8(data.clone())?;
+        let body = text.find("#0").unwrap();
+        assert_eq!(vcd_tail_end(&data[..body + 10]), Some(body));
+        assert_eq!(vcd_tail_end(&data[body + 1..body + 10]), None);
+        assert_eq!(vcd_lines_end(&data[body..body + 1]), None);
+        // split at every line after `$dumpvars ... $end`
+        let dumpvars_end = text.find("#5").unwrap();
+        let line_ends = data
+            .iter()
+            .enumerate()
+            .filter(|(i, c)| *i >= dumpvars_end && **c == b'\n')
+            .map(|(i, _)| i + 1);
+        for position in line_ends {
+            let mut wave = Vcd.load(&mut &data[..position], &|_, _| {}, None)?;
+            let tail = Vcd::load_tail(&mut &data[position..], wave.info.range.1)?;
+            wave.append(tail)?;
+            assert_eq!(wave.info.range, full.info.range, "split at {position}");
+            assert_eq!(wave_items(&wave), wave_items(&full), "split at {position}");
+        }
+        // follow a growing file, the last timestamp block is read again on every tick
+        let mut position = dumpvars_end;
+        let mut wave = Vcd.load(&mut &data[..position], &|_, _| {}, None)?;
+        for size in (dumpvars_end + 1)..=data.len() {
+            let Some((line_end, tail_end)) = vcd_lines_end(&data[position..size]) else {
+                continue;
+            };
+            let tail =
+                Vcd::load_tail(&mut &data[position..position + line_end], wave.info.range.1)?;
+            wave.append(tail)?;
+            position += tail_end;
+            let expect = Vcd.load(
+                &mut &data[..position + line_end - tail_end],
+                &|_, _| {},
+                None,
+            )?;
+            assert_eq!(wave.info.range, expect.info.range, "size {size}");
+            assert_eq!(wave_items(&wave), wave_items(&expect), "size {size}");
+        }
+        Ok(())
+    }
+
+    /// Load a vcd file cut in the middle of a line, then follow the rest appended
+    #[test]
+    fn test_vcd_load_source_partial() -> Result<()> {
+        let mut data = vec![];
+        File::open("data/counter.vcd")?.read_to_end(&mut data)?;
+        let (full, consumed) =
+            Vcd.load_source(&mut Cursor::new(&data), &|_, _| {}, &WaveFilter::default())?;
+        // complete file is parsed to the end, following starts at last timestamp
+        let last = String::from_utf8(data.clone())?.rfind("\n#").unwrap() + 1;
+        assert_eq!(consumed, Some(last as u64));
+        let body = String::from_utf8(data.clone())?.find("#0").unwrap();
+        for position in (body + 1..data.len()).filter(|p| data[p - 1] != b'\n') {
+            let (mut wave, consumed) = Vcd.load_source(
+                &mut Cursor::new(&data[..position]),
+                &|_, _| {},
+                &WaveFilter::default(),
+            )?;
+            let consumed = consumed.unwrap() as usize;
+            assert_eq!(data[consumed], b'#', "cut at {position}");
+            assert!(consumed < position, "cut at {position}");
+            let tail = Vcd::load_tail(&mut &data[consumed..], wave.info.range.1)?;
+            wave.append(tail)?;
+            assert_eq!(wave.info.range, full.info.range, "cut at {position}");
+            assert_eq!(wave_items(&wave), wave_items(&full), "cut at {position}");
+        }
+        Ok(())
+    }
+
+    /// Feed a vcd file in small chunks like a stream
+    #[test]
+    fn test_vcd_stream() -> Result<()> {
+        let mut data = vec![];
+        File::open("data/counter.vcd")?.read_to_end(&mut data)?;
+        let full = Vcd.load(&mut data.as_slice(), &|_, _| {}, None)?;
+        for chunk_size in [1, 7, 64, data.len()] {
+            let mut stream = VcdStream::default();
+            let mut wave = None;
+            for chunk in data.chunks(chunk_size) {
+                stream.feed(chunk);
+                if let Some(header) = stream.header()? {
+                    assert!(header.data.is_empty());
+                    wave = Some(header);
+                }
+                if let Some(tail) = stream.tail()? {
+                    wave.as_mut().unwrap().append(tail)?;
+                }
+            }
+            assert!(stream.header_parsed());
+            let mut wave = wave.unwrap();
+            if let Some(tail) = stream.finish()? {
+                wave.append(tail)?;
+            }
+            assert_eq!(wave.info.range, full.info.range);
+            assert_eq!(wave.info.code_paths, full.info.code_paths);
+            assert_eq!(wave_items(&wave), wave_items(&full));
+        }
+        let mut stream = VcdStream::default();
+        stream.feed(&data[..40]);
+        assert!(stream.header()?.is_none());
+        assert!(stream.finish().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_vcd_load_parallel() -> Result<()> {
+        for file in [
+            "data/counter.vcd",
+            "data/real_string.vcd",
+            "data/cpu_ila_commit.vcd",
+        ] {
+            let full = Vcd.load(&mut File::open(file)?, &|_, _| {}, None)?;
+            for (threads, chunk_size) in [(1, 1), (4, 16), (3, 100), (0, VCD_PARALLEL_CHUNK_SIZE)] {
+                let wave = Vcd::load_parallel(
+                    &mut File::open(file)?,
+                    |_, _| {},
+                    None,
+                    threads,
+                    chunk_size,
+                    &WaveFilter::default(),
+                )?;
+                assert_eq!(wave.info.range, full.info.range, "{file}");
+                assert_eq!(wave.info.code_paths, full.info.code_paths, "{file}");
+                assert_eq!(wave_items(&wave), wave_items(&full), "{file}");
+            }
+        }
+        assert!(Vcd::load_parallel(
+            &mut &b"$var"[..],
+            |_, _| {},
+            None,
+            2,
+            16,
+            &WaveFilter::default()
+        )
+        .is_err());
+        Ok(())
+    }
+
+    /// Workers still parsing chunks after the merger returned on error must not panic
+    #[test]
+    fn test_vcd_load_parallel_error() -> Result<()> {
+        let mut text = "$var wire 1 ! clk $end\n$enddefinitions $end\n#0\n?invalid\n".to_string();
+        // chunks are still queued when the invalid one is merged
+        for t in 1..10000 {
+            text.push_str(&format!("#{t}\n{}!\n", t % 2));
+        }
+        let data = text.into_bytes();
+        for threads in [1, 2, 4] {
+            let wave = Vcd::load_parallel(
+                &mut data.as_slice(),
+                |_, _| {},
+                None,
+                threads,
+                16,
+                &WaveFilter::default(),
+            );
+            assert!(wave.is_err(), "{threads} threads");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_vcd_load_filtered() -> Result<()> {
+        let file = "data/counter.vcd";
+        let full = Vcd.load(&mut File::open(file)?, &|_, _| {}, None)?;
+        let filter = WaveFilter::parse("top.counter")?;
+        let sequential = Vcd::load_filtered(&mut File::open(file)?, |_, _| {}, None, &filter)?;
+        let parallel = Vcd::load_parallel(&mut File::open(file)?, |_, _| {}, None, 2, 16, &filter)?;
+        for mut wave in [sequential, parallel] {
+            // tree is kept for signals not loaded
+            assert_eq!(wave.info.code_paths, full.info.code_paths);
+            assert_eq!(wave.info.range, full.info.range);
+            assert_eq!(wave.selected, Some(HashSet::from([2, 3])));
+            let mut ids = wave.data.keys().copied().collect::<Vec<_>>();
+            ids.sort();
+            assert_eq!(ids, vec![2, 3]);
+            assert_eq!(
+                wave.find_value(2, 27).unwrap().value.to_string(),
+                full.find_value(2, 27).unwrap().value.to_string()
+            );
+            assert!(!wave.is_loaded(0));
+            // load signal `clk` on demand
+            let more = Vcd::load_filtered(
+                &mut File::open(file)?,
+                |_, _| {},
+                None,
+                &WaveFilter::from_ids([0]),
+            )?;
+            wave.merge(more);
+            assert!(wave.is_loaded(0));
+            assert_eq!(
+                wave.data.get(&0).unwrap().len(),
+                full.data.get(&0).unwrap().len()
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_vcd_load_header_only() -> Result<()> {
+        let mut data = std::fs::read("data/counter.vcd")?;
+        let full = Vcd.load(&mut &data[..], &|_, _| {}, None)?;
+        // value changes after the first timestamp are not parsed
+        let first = data.windows(2).position(|w| w == b"\n#").unwrap() + 1;
+        let line_end = first + data[first..].iter().position(|c| *c == b'\n').unwrap() + 1;
+        data.splice(line_end..line_end, b"?invalid\n".iter().copied());
+        assert!(Vcd.load(&mut &data[..], &|_, _| {}, None).is_err());
+        let filter = WaveFilter::header_only();
+        let (wave, consumed) = Vcd.load_source(&mut Cursor::new(&data), &|_, _| {}, &filter)?;
+        assert_eq!(wave.info.code_paths, full.info.code_paths);
+        assert_eq!(wave.info.range, full.info.range);
+        assert!(wave.data.is_empty());
+        assert_eq!(wave.selected, Some(HashSet::new()));
+        assert_eq!(consumed, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_vcd_load_window() -> Result<()> {
+        let file = "data/counter.vcd";
+        let mut filter = WaveFilter::default();
+        filter.set_window("12ns", "30")?;
+        let sequential = Vcd::load_filtered(&mut File::open(file)?, |_, _| {}, None, &filter)?;
+        let parallel = Vcd::load_parallel(&mut File::open(file)?, |_, _| {}, None, 2, 16, &filter)?;
+        for wave in [sequential, parallel] {
+            assert_eq!(wave.info.range, (12, 30));
+            let items = |id: u64| column_items(&wave, id);
+            // last values before window are moved to window start
+            assert_eq!(items(2), vec!["#12 0", "#15 1", "#25 2"]);
+            assert_eq!(items(3), vec!["#12 1", "#30 0"]);
+            assert_eq!(items(1), vec!["#12 0"]);
+            assert_eq!(items(0).len(), 5);
+        }
+        // chunks after window end are not read
+        let mut data = vec![];
+        File::open(file)?.read_to_end(&mut data)?;
+        let mut reader = data.as_slice();
+        Vcd::load_parallel(&mut reader, |_, _| {}, None, 2, 16, &filter)?;
+        assert!(!reader.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_vcd_dump_off() -> Result<()> {
+        let text = "$timescale 1ns $end\n\
+            $scope module top $end\n\
+            $var wire 1 ! clk $end\n\
+            $var wire 4 \" cnt $end\n\
+            $upscope $end\n\
+            $enddefinitions $end\n\
+            #0\n$dumpvars\n0!\nb0000 \"\n$end\n\
+            #5\n1!\nb0001 \"\n\
+            #10\n$dumpoff\nx!\nbxxxx \"\n$end\n\
+            #20\n$dumpon\n1!\nb0001 \"\n$end\n\
+            #25\n0!\n\
+            #30\n$dumpall\n0!\nb0001 \"\n$end\n\
+            #35\nb0010 \"\n";
+        let data = text.as_bytes();
+        let sequential = Vcd.load(&mut &data[..], &|_, _| {}, None)?;
+        let parallel = Vcd::load_parallel(
+            &mut &data[..],
+            |_, _| {},
+            None,
+            2,
+            16,
+            &WaveFilter::default(),
+        )?;
+        // split inside dump-off interval and before `$dumpall`
+        let split = |at: &str| -> Result<Wave> {
+            let position = text.find(at).unwrap();
+            let mut wave = Vcd.load(&mut &data[..position], &|_, _| {}, None)?;
+            wave.append(Vcd::load_tail(&mut &data[position..], wave.info.range.1)?)?;
+            Ok(wave)
+        };
+        for wave in [sequential, parallel, split("#20")?, split("#30")?] {
+            assert_eq!(wave.info.range, (0, 35));
+            assert_eq!(wave.info.dump_off, vec![(10, 20)]);
+            let items = |id: u64| column_items(&wave, id);
+            // values repeated by `$dumpon` and `$dumpall` are not changes
+            assert_eq!(items(1), vec!["#0 0", "#5 1", "#10 x", "#20 1", "#35 2"]);
+            assert_eq!(items(0), vec!["#0 0", "#5 1", "#10 x", "#20 1", "#25 0"]);
+            let value = |id: u64, pos: u64| wave.find_value(id, pos).unwrap();
+            assert_eq!(value(1, 15).value.to_string(), "x");
+            assert_eq!(value(1, 15).timestamp, 10);
+            assert_eq!(value(1, 20).value.to_string(), "1");
+            assert_eq!(value(1, 32).timestamp, 20);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_vcd_aliases() -> Result<()> {
+        let text = "$scope module top $end\n\
+            $var wire 1 ! clk $end\n\
+            $scope module a $end\n\
+            $var wire 1 ! clk_in $end\n\
+            $var wire 1 \" x $end\n\
+            $upscope $end\n\
+            $scope module b $end\n\
+            $var wire 1 \" y $end\n\
+            $upscope $end\n\
+            $upscope $end\n\
+            $enddefinitions $end\n\
+            #0\n0!\n1\"\n";
+        let wave = Vcd.load(&mut text.as_bytes(), &|_, _| {}, None)?;
+        let paths = |id: u64| {
+            wave.info
+                .signal_paths(id)
+                .map(|p| p.join("."))
+                .collect::<Vec<_>>()
+        };
+        // the first var is the signal, later ones are aliases
+        assert_eq!(paths(0), vec!["top.clk", "top.a.clk_in"]);
+        assert_eq!(paths(1), vec!["top.a.x", "top.b.y"]);
+        assert_eq!(wave.info.code_signal_info.get(&0).unwrap().name, "clk");
+        assert_eq!(wave.info.all_signal_paths().count(), 4);
+        let select = |text: &str| WaveFilter::parse(text)?.select(&wave.info);
+        assert_eq!(select("top.b")?, Some(HashSet::from([1])));
+        assert_eq!(select("*.clk_in")?, Some(HashSet::from([0])));
+        Ok(())
+    }
+
     #[test]
     pub fn test_vcd_parser() {
         tracing_subscriber::fmt::init();
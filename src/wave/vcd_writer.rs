@@ -0,0 +1,308 @@
+use crate::wave::filter::WaveFilter;
+use crate::wave::{
+    Wave, WaveDataValue, WaveScopeType, WaveSignalInfo, WaveSignalType, WaveTreeNode, WireValue,
+};
+use anyhow::Result;
+use num_bigint::BigUint;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::io::Write;
+use vcd::IdCode;
+
+/// Written to `$version` of exported vcd
+const VCD_WRITER_VERSION: &str = concat!("rvcd ", env!("CARGO_PKG_VERSION"));
+
+/// Var type keyword in vcd, types without keyword are written as the closest one
+fn vcd_var_type(typ: &WaveSignalType) -> String {
+    match typ {
+        // literal names are lost, values are literal indexes
+        WaveSignalType::Enum => "reg".to_string(),
+        // ports of extended vcd are written as plain wires
+        WaveSignalType::Port => "wire".to_string(),
+        typ => typ.to_string(),
+    }
+}
+
+/// Scope type keyword in vcd, vhdl records and arrays are written as modules
+fn vcd_scope_type(typ: &WaveScopeType) -> String {
+    match typ {
+        WaveScopeType::Record | WaveScopeType::Array => "module".to_string(),
+        typ => typ.to_string(),
+    }
+}
+
+/// Name without whitespace, which separates vcd tokens
+fn vcd_identifier(name: &str) -> String {
+    match name.split_whitespace().collect::<Vec<_>>().join("_") {
+        name if name.is_empty() => "_".to_string(),
+        name => name,
+    }
+}
+
+/// Value change of `signal` in vcd text, like `1!` or `b1010 "`
+fn vcd_value_change(value: &WaveDataValue, signal: &WaveSignalInfo, code: &IdCode) -> String {
+    let bits: String = match value {
+        WaveDataValue::Comp(v) => format!(
+            "{:0>width$}",
+            BigUint::from_bytes_le(v).to_str_radix(2),
+            width = signal.width as usize
+        ),
+        // raw wires are msb first
+        WaveDataValue::Raw(v) => v.iter().map(|w| w.to_string()).collect(),
+        WaveDataValue::Port(v) => v.wires().iter().map(|w| w.to_string()).collect(),
+        WaveDataValue::Real(v) => return format!("r{v} {code}"),
+        WaveDataValue::String(v) => return format!("s{} {code}", vcd_identifier(v)),
+    };
+    match bits.len() {
+        0 => format!("x{code}"),
+        1 if signal.width <= 1 => format!("{bits}{code}"),
+        _ => format!("b{bits} {code}"),
+    }
+}
+
+/// Value of `signal` while dumping is off
+fn vcd_value_off(signal: &WaveSignalInfo) -> Option<WaveDataValue> {
+    match signal.typ {
+        WaveSignalType::Real | WaveSignalType::String => None,
+        _ => Some(WaveDataValue::Raw(vec![
+            WireValue::X;
+            signal.width.max(1) as usize
+        ])),
+    }
+}
+
+/// Check if vars of `ids` are under `node`
+fn vcd_tree_contains(node: &trees::Node<WaveTreeNode>, ids: &HashSet<u64>) -> bool {
+    node.iter().any(|child| match child.data() {
+        WaveTreeNode::WaveVar(var) => ids.contains(&var.id),
+        WaveTreeNode::WaveScope(_) => vcd_tree_contains(child, ids),
+        _ => false,
+    })
+}
+
+/// Write scopes and vars of `ids` under `node`, aliases are written as vars of the same code
+fn vcd_write_tree(
+    writer: &mut dyn Write,
+    node: &trees::Node<WaveTreeNode>,
+    ids: &HashSet<u64>,
+) -> Result<()> {
+    for child in node.iter() {
+        match child.data() {
+            WaveTreeNode::WaveScope(scope) if vcd_tree_contains(child, ids) => {
+                writeln!(
+                    writer,
+                    "$scope {} {} $end",
+                    vcd_scope_type(&scope.typ),
+                    vcd_identifier(&scope.name)
+                )?;
+                vcd_write_tree(writer, child, ids)?;
+                writeln!(writer, "$upscope $end")?;
+            }
+            WaveTreeNode::WaveVar(var) if ids.contains(&var.id) => writeln!(
+                writer,
+                "$var {} {} {} {} $end",
+                vcd_var_type(&var.typ),
+                var.width,
+                IdCode(var.id),
+                vcd_identifier(&var.name)
+            )?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Write value changes of signals and time window selected by `filter` in `wave` as vcd,
+/// keeping hierarchy, timescale and id codes. Scopes without selected signals and signals
+/// whose value changes are not loaded are skipped
+pub fn vcd_write(wave: &Wave, filter: &WaveFilter, writer: &mut dyn Write) -> Result<()> {
+    let info = &wave.info;
+    let selected = filter.select(info)?;
+    let ids = info
+        .code_signal_info
+        .keys()
+        .copied()
+        .filter(|id| selected.as_ref().map(|s| s.contains(id)).unwrap_or(true))
+        .filter(|id| wave.is_loaded(*id))
+        .collect::<HashSet<_>>();
+    let mut sorted = ids.iter().copied().collect::<Vec<_>>();
+    sorted.sort();
+    let signals = sorted
+        .iter()
+        .filter_map(|id| Some((*id, info.code_signal_info.get(id)?, IdCode(*id))))
+        .collect::<Vec<_>>();
+    let (start, end) = match filter.window(&info.timescale) {
+        Some(window) => window.range(info.range),
+        None => info.range,
+    };
+
+    if let Some(date) = info.headers.get("date") {
+        writeln!(writer, "$date\n\t{date}\n$end")?;
+    }
+    writeln!(writer, "$version\n\t{VCD_WRITER_VERSION}\n$end")?;
+    writeln!(
+        writer,
+        "$timescale\n\t{}{}\n$end",
+        info.timescale.0, info.timescale.1
+    )?;
+    vcd_write_tree(writer, info.tree.root(), &ids)?;
+    writeln!(writer, "$enddefinitions $end")?;
+
+    writeln!(writer, "#{start}\n$dumpvars")?;
+    for (id, signal, code) in &signals {
+        if let Some(item) = wave.find_value(*id, start) {
+            writeln!(writer, "{}", vcd_value_change(&item.value, signal, code))?;
+        }
+    }
+    writeln!(writer, "$end")?;
+
+    let mut changes: BTreeMap<u64, Vec<(usize, WaveDataValue)>> = BTreeMap::new();
+    for (index, (id, _, _)) in signals.iter().enumerate() {
+        if let Some(column) = wave.data.get(id) {
+            for item in column
+                .iter_from(column.lower_bound(start.saturating_add(1)))
+                .take_while(|item| item.timestamp <= end)
+            {
+                changes
+                    .entry(item.timestamp)
+                    .or_default()
+                    .push((index, item.value));
+            }
+        }
+    }
+    // dump-off intervals clipped by window
+    let mut dump_off = BTreeSet::new();
+    let mut dump_on = BTreeSet::new();
+    for (off, on) in &info.dump_off {
+        if start < *off && *off <= end {
+            dump_off.insert(*off);
+            changes.entry(*off).or_default();
+        }
+        if start < *on && *on <= end {
+            dump_on.insert(*on);
+            changes.entry(*on).or_default();
+        }
+    }
+    let mut last = start;
+    for (timestamp, items) in changes {
+        writeln!(writer, "#{timestamp}")?;
+        last = timestamp;
+        if dump_off.contains(&timestamp) {
+            writeln!(writer, "$dumpoff")?;
+            for (_, signal, code) in &signals {
+                if let Some(value) = vcd_value_off(signal) {
+                    writeln!(writer, "{}", vcd_value_change(&value, signal, code))?;
+                }
+            }
+            writeln!(writer, "$end")?;
+        }
+        if dump_on.contains(&timestamp) {
+            // values after dumping on are current values of all signals
+            writeln!(writer, "$dumpon")?;
+            for (id, signal, code) in &signals {
+                if let Some(item) = wave.find_value(*id, timestamp) {
+                    writeln!(writer, "{}", vcd_value_change(&item.value, signal, code))?;
+                }
+            }
+            writeln!(writer, "$end")?;
+            continue;
+        }
+        if info.dump_off_at(timestamp).is_some() {
+            continue;
+        }
+        for (index, value) in items {
+            let (_, signal, code) = &signals[index];
+            writeln!(writer, "{}", vcd_value_change(&value, signal, code))?;
+        }
+    }
+    if end > last {
+        writeln!(writer, "#{end}")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::wave::filter::WaveFilter;
+    use crate::wave::vcd_parser::Vcd;
+    use crate::wave::vcd_writer::vcd_write;
+    use crate::wave::{Wave, WaveLoader};
+    use anyhow::Result;
+    use std::collections::HashSet;
+    use std::fs::File;
+
+    fn items(wave: &Wave, id: u64) -> Vec<String> {
+        wave.data
+            .get(&id)
+            .map(|column| column.iter().map(|i| i.to_string()).collect())
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn test_vcd_write() -> Result<()> {
+        for file in ["data/counter.vcd", "data/real_string.vcd"] {
+            let wave = Vcd.load(&mut File::open(file)?, &|_, _| {}, None)?;
+            let mut text = vec![];
+            vcd_write(&wave, &WaveFilter::default(), &mut text)?;
+            let written = Vcd.load(&mut text.as_slice(), &|_, _| {}, None)?;
+            assert_eq!(written.info.timescale, wave.info.timescale);
+            assert_eq!(written.info.range, wave.info.range);
+            // same hierarchy and id codes
+            assert_eq!(written.info.code_paths, wave.info.code_paths);
+            assert_eq!(written.info.code_signal_info, wave.info.code_signal_info);
+            for id in wave.info.code_signal_info.keys() {
+                assert_eq!(items(&written, *id), items(&wave, *id), "{file} {id}");
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_vcd_write_filtered() -> Result<()> {
+        let wave = Vcd.load(&mut File::open("data/counter.vcd")?, &|_, _| {}, None)?;
+        let mut filter = WaveFilter::parse("top.counter.cnt")?;
+        filter.set_window("11", "25")?;
+        let mut text = vec![];
+        vcd_write(&wave, &filter, &mut text)?;
+        let written = Vcd.load(&mut text.as_slice(), &|_, _| {}, None)?;
+        assert_eq!(
+            written.info.code_paths.keys().collect::<HashSet<_>>(),
+            HashSet::from([&2])
+        );
+        assert_eq!(written.info.range, (11, 25));
+        // value before window is kept at window start
+        let loaded = Vcd::load_filtered(
+            &mut File::open("data/counter.vcd")?,
+            |_, _| {},
+            None,
+            &filter,
+        )?;
+        assert_eq!(items(&written, 2), items(&loaded, 2));
+        assert_eq!(items(&written, 2), vec!["#11 0", "#15 1", "#25 2"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_vcd_write_dump_off() -> Result<()> {
+        let vcd = "$timescale 1ns $end\n\
+            $scope module top $end\n\
+            $var wire 1 ! a $end\n\
+            $var wire 4 \" b $end\n\
+            $var wire 4 \" b_alias $end\n\
+            $upscope $end\n\
+            $enddefinitions $end\n\
+            #0\n$dumpvars\n0!\nb0001 \"\n$end\n\
+            #10\n1!\n\
+            #20\n$dumpoff\nx!\nbxxxx \"\n$end\n\
+            #30\n$dumpon\n0!\nb0010 \"\n$end\n\
+            #40\n1!\n";
+        let wave = Vcd.load(&mut vcd.as_bytes(), &|_, _| {}, None)?;
+        let mut text = vec![];
+        vcd_write(&wave, &WaveFilter::default(), &mut text)?;
+        let written = Vcd.load(&mut text.as_slice(), &|_, _| {}, None)?;
+        assert_eq!(written.info.dump_off, vec![(20, 30)]);
+        assert_eq!(written.info.code_aliases, wave.info.code_aliases);
+        assert_eq!(items(&written, 0), items(&wave, 0));
+        assert_eq!(items(&written, 1), items(&wave, 1));
+        Ok(())
+    }
+}
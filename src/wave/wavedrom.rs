@@ -0,0 +1,697 @@
+use crate::wave::filter::WaveFilter;
+use crate::wave::table_writer::{TableRows, WaveTable};
+use crate::wave::utils::WaveTreeBuilder;
+use crate::wave::vcd_parser::vcd_column_push;
+use crate::wave::{
+    Wave, WaveDataItem, WaveDataValue, WaveInfo, WaveLoader, WaveScopeType, WaveSignalInfo,
+    WaveSignalType, WaveSource, WaveTimescaleUnit, WireValue,
+};
+use anyhow::{anyhow, Result};
+use num_bigint::BigUint;
+use num_traits::Num;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::iter::Peekable;
+use std::str::Chars;
+use tracing::info;
+
+/// Timestamps of one WaveDrom period when loading, in [WAVEDROM_TIMESCALE]
+pub const WAVEDROM_PERIOD: u64 = 10;
+pub const WAVEDROM_TIMESCALE: (u64, WaveTimescaleUnit) = (1, WaveTimescaleUnit::NS);
+/// Scope of signals not in any group
+const WAVEDROM_SCOPE: &str = "wavedrom";
+
+/// Check if `data` starts a WaveDrom object with a `signal` list, comments before it are skipped
+pub fn is_wavedrom(data: &[u8]) -> bool {
+    let text = String::from_utf8_lossy(data);
+    let mut parser = WaveDromJson {
+        chars: text.chars().peekable(),
+    };
+    parser.skip_blank();
+    parser.chars.peek() == Some(&'{')
+        && text.match_indices("signal").any(|(i, _)| {
+            text[i + "signal".len()..]
+                .trim_start_matches(['"', '\''])
+                .trim_start()
+                .starts_with(':')
+        })
+}
+
+/// Parser of WaveDrom sources, which are json5 usually:
+/// keys can be unquoted, strings can be single quoted, trailing commas and comments are allowed
+struct WaveDromJson<'a> {
+    chars: Peekable<Chars<'a>>,
+}
+
+impl<'a> WaveDromJson<'a> {
+    fn skip_blank(&mut self) {
+        while let Some(c) = self.chars.peek() {
+            match c {
+                c if c.is_whitespace() => {
+                    self.chars.next();
+                }
+                '/' => {
+                    self.chars.next();
+                    match self.chars.next() {
+                        Some('/') => {
+                            for c in self.chars.by_ref() {
+                                if c == '\n' {
+                                    break;
+                                }
+                            }
+                        }
+                        Some('*') => {
+                            let mut last = ' ';
+                            for c in self.chars.by_ref() {
+                                if last == '*' && c == '/' {
+                                    break;
+                                }
+                                last = c;
+                            }
+                        }
+                        _ => return,
+                    }
+                }
+                _ => return,
+            }
+        }
+    }
+    fn expect(&mut self, expected: char) -> Result<()> {
+        self.skip_blank();
+        match self.chars.next() {
+            Some(c) if c == expected => Ok(()),
+            c => Err(anyhow!("expect {expected:?}, found {c:?}")),
+        }
+    }
+    fn string(&mut self, quote: char) -> Result<String> {
+        let mut text = String::new();
+        loop {
+            match self.chars.next() {
+                Some(c) if c == quote => return Ok(text),
+                Some('\\') => match self.chars.next() {
+                    Some('n') => text.push('\n'),
+                    Some('t') => text.push('\t'),
+                    Some('r') => text.push('\r'),
+                    Some('u') => {
+                        let code = self.chars.by_ref().take(4).collect::<String>();
+                        let c = u32::from_str_radix(&code, 16)
+                            .ok()
+                            .and_then(char::from_u32)
+                            .ok_or_else(|| anyhow!("invalid escape \\u{code}"))?;
+                        text.push(c);
+                    }
+                    Some(c) => text.push(c),
+                    None => break,
+                },
+                Some(c) => text.push(c),
+                None => break,
+            }
+        }
+        Err(anyhow!("unterminated string"))
+    }
+    /// Unquoted key, number or literal
+    fn word(&mut self) -> String {
+        let mut word = String::new();
+        while let Some(c) = self.chars.peek() {
+            if c.is_alphanumeric() || matches!(c, '_' | '$' | '.' | '-' | '+') {
+                word.push(*c);
+                self.chars.next();
+            } else {
+                break;
+            }
+        }
+        word
+    }
+    fn key(&mut self) -> Result<String> {
+        self.skip_blank();
+        match self.chars.peek() {
+            Some(quote @ ('"' | '\'')) => {
+                let quote = *quote;
+                self.chars.next();
+                self.string(quote)
+            }
+            _ => match self.word() {
+                word if word.is_empty() => Err(anyhow!("expect key")),
+                word => Ok(word),
+            },
+        }
+    }
+    fn value(&mut self) -> Result<Value> {
+        self.skip_blank();
+        match self.chars.peek() {
+            Some('{') => {
+                self.chars.next();
+                let mut object = Map::new();
+                loop {
+                    self.skip_blank();
+                    if self.chars.peek() == Some(&'}') {
+                        self.chars.next();
+                        return Ok(Value::Object(object));
+                    }
+                    let key = self.key()?;
+                    self.expect(':')?;
+                    object.insert(key, self.value()?);
+                    self.skip_blank();
+                    match self.chars.next() {
+                        Some(',') => {}
+                        Some('}') => return Ok(Value::Object(object)),
+                        c => return Err(anyhow!("expect ',' or '}}', found {c:?}")),
+                    }
+                }
+            }
+            Some('[') => {
+                self.chars.next();
+                let mut array = vec![];
+                loop {
+                    self.skip_blank();
+                    if self.chars.peek() == Some(&']') {
+                        self.chars.next();
+                        return Ok(Value::Array(array));
+                    }
+                    array.push(self.value()?);
+                    self.skip_blank();
+                    match self.chars.next() {
+                        Some(',') => {}
+                        Some(']') => return Ok(Value::Array(array)),
+                        c => return Err(anyhow!("expect ',' or ']', found {c:?}")),
+                    }
+                }
+            }
+            Some(quote @ ('"' | '\'')) => {
+                let quote = *quote;
+                self.chars.next();
+                Ok(Value::String(self.string(quote)?))
+            }
+            _ => match self.word().as_str() {
+                "true" => Ok(Value::Bool(true)),
+                "false" => Ok(Value::Bool(false)),
+                "null" => Ok(Value::Null),
+                word => serde_json::from_str::<Value>(word)
+                    .ok()
+                    .filter(Value::is_number)
+                    .ok_or_else(|| anyhow!("unexpected {word:?}")),
+            },
+        }
+    }
+}
+
+/// Parse WaveDrom source in json or json5
+pub fn wavedrom_parse(text: &str) -> Result<Value> {
+    let mut parser = WaveDromJson {
+        chars: text.chars().peekable(),
+    };
+    let value = parser.value()?;
+    parser.skip_blank();
+    match parser.chars.next() {
+        None => Ok(value),
+        Some(c) => Err(anyhow!("unexpected {c:?} after WaveDrom object")),
+    }
+}
+
+/// Level of a WaveDrom signal in one period
+#[derive(Debug, Clone, PartialEq)]
+enum WaveDromLevel {
+    Bit(WireValue),
+    Label(String),
+}
+
+/// Levels of wave string `wave` with labels `data` at timestamps, clocks toggle in half periods
+fn wavedrom_levels(wave: &str, data: &[String], period: u64) -> Vec<(u64, WaveDromLevel)> {
+    use WaveDromLevel::*;
+    let mut levels = vec![];
+    let mut labels = data.iter();
+    let mut clock: Option<(WireValue, WireValue)> = None;
+    for (index, c) in wave.chars().enumerate() {
+        let timestamp = index as u64 * period;
+        let level = match c {
+            '0' | 'l' | 'L' | 'd' => Bit(WireValue::V0),
+            '1' | 'h' | 'H' | 'u' => Bit(WireValue::V1),
+            'x' => Bit(WireValue::X),
+            'z' => Bit(WireValue::Z),
+            'p' | 'P' => {
+                clock = Some((WireValue::V1, WireValue::V0));
+                Bit(WireValue::V1)
+            }
+            'n' | 'N' => {
+                clock = Some((WireValue::V0, WireValue::V1));
+                Bit(WireValue::V0)
+            }
+            '=' | '2'..='9' => Label(labels.next().cloned().unwrap_or_default()),
+            // `.`, gaps and unknown characters continue last level
+            _ => match clock {
+                Some((first, _)) => Bit(first),
+                None => continue,
+            },
+        };
+        if !matches!(c, 'p' | 'P' | 'n' | 'N' | '.' | '|') {
+            clock = None;
+        }
+        levels.push((timestamp, level));
+        if let Some((_, second)) = clock {
+            levels.push((timestamp + period / 2, Bit(second)));
+        }
+    }
+    levels.dedup_by(|next, last| next.1 == last.1);
+    levels
+}
+
+/// Number in label like `10`, `0x1f` or `0b101`
+fn wavedrom_label_number(label: &str) -> Option<BigUint> {
+    let label = label.trim();
+    match label.get(..2).map(|p| p.to_ascii_lowercase()).as_deref() {
+        Some("0x") => BigUint::from_str_radix(&label[2..], 16).ok(),
+        Some("0b") => BigUint::from_str_radix(&label[2..], 2).ok(),
+        _ => BigUint::from_str_radix(label, 10).ok(),
+    }
+}
+
+/// Signal type, width and values of levels: single bits are wires,
+/// buses with numeric labels are regs and other buses are strings
+fn wavedrom_values(
+    levels: &[(u64, WaveDromLevel)],
+) -> (WaveSignalType, u64, Vec<(u64, WaveDataValue)>) {
+    use WaveDromLevel::*;
+    let labels = levels
+        .iter()
+        .filter_map(|(_, level)| match level {
+            Label(label) => Some(wavedrom_label_number(label)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    if labels.is_empty() {
+        let values = levels
+            .iter()
+            .map(|(t, level)| match level {
+                Bit(WireValue::V0) => (*t, WaveDataValue::Comp(vec![0])),
+                Bit(WireValue::V1) => (*t, WaveDataValue::Comp(vec![1])),
+                Bit(v) => (*t, WaveDataValue::Raw(vec![*v])),
+                Label(_) => unreachable!(),
+            })
+            .collect();
+        return (WaveSignalType::Wire, 1, values);
+    }
+    if labels.iter().all(Option::is_some) {
+        let width = labels
+            .iter()
+            .flatten()
+            .map(|n| n.bits())
+            .max()
+            .unwrap_or_default()
+            .max(1);
+        let values = levels
+            .iter()
+            .map(|(t, level)| match level {
+                Label(label) => {
+                    let n = wavedrom_label_number(label).unwrap_or_default();
+                    (*t, WaveDataValue::Comp(n.to_bytes_le()))
+                }
+                Bit(v) => (*t, WaveDataValue::Raw(vec![*v; width as usize])),
+            })
+            .collect();
+        return (WaveSignalType::Reg, width, values);
+    }
+    let values = levels
+        .iter()
+        .map(|(t, level)| match level {
+            Label(label) => (*t, WaveDataValue::String(label.to_string())),
+            Bit(v) => (*t, WaveDataValue::String(v.to_string())),
+        })
+        .collect();
+    (WaveSignalType::String, 1, values)
+}
+
+/// Labels in `data` field, a list or a string separated by whitespaces
+fn wavedrom_data(value: Option<&Value>) -> Vec<String> {
+    match value {
+        Some(Value::Array(items)) => items
+            .iter()
+            .map(|item| match item {
+                Value::String(s) => s.to_string(),
+                item => item.to_string(),
+            })
+            .collect(),
+        Some(Value::String(s)) => s.split_whitespace().map(|s| s.to_string()).collect(),
+        _ => vec![],
+    }
+}
+
+/// Add signals in WaveDrom signal list `items` to `builder`, groups are scopes
+fn wavedrom_signals(
+    items: &[Value],
+    builder: &mut WaveTreeBuilder,
+    signals: &mut Vec<(u64, Vec<(u64, WaveDataValue)>)>,
+) -> Result<()> {
+    for item in items {
+        match item {
+            Value::Object(object) => {
+                let Some(wave) = object.get("wave").and_then(Value::as_str) else {
+                    // spacer
+                    continue;
+                };
+                let name = match object.get("name") {
+                    Some(Value::String(name)) if !name.trim().is_empty() => name.to_string(),
+                    _ => format!("signal{}", signals.len()),
+                };
+                let scale = |key: &str| object.get(key).and_then(Value::as_f64).unwrap_or(0.0);
+                let period = match scale("period") {
+                    p if p > 0.0 => (p * WAVEDROM_PERIOD as f64).round() as u64,
+                    _ => WAVEDROM_PERIOD,
+                };
+                // phase moves the wave left
+                let phase = (scale("phase") * WAVEDROM_PERIOD as f64).round() as i64;
+                let data = wavedrom_data(object.get("data"));
+                let levels = wavedrom_levels(wave, &data, period.max(1))
+                    .into_iter()
+                    .map(|(t, level)| ((t as i64 - phase).max(0) as u64, level))
+                    .collect::<Vec<_>>();
+                let (typ, width, values) = wavedrom_values(&levels);
+                let id = signals.len() as u64;
+                builder.add_var(WaveSignalInfo {
+                    id,
+                    name,
+                    width,
+                    typ,
+                    direction: None,
+                });
+                signals.push((id, values));
+            }
+            Value::Array(group) => {
+                let (name, items) = match group.split_first() {
+                    Some((Value::String(name), items)) => (name.to_string(), items),
+                    _ => ("".to_string(), group.as_slice()),
+                };
+                let name = match name.trim() {
+                    "" => format!("group{}", signals.len()),
+                    name => name.to_string(),
+                };
+                builder.push_scope(name, WaveScopeType::Module);
+                wavedrom_signals(items, builder, signals)?;
+                builder.pop_scope()?;
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// WaveDrom timing diagram loaded as wave, one period is [WAVEDROM_PERIOD] timestamps
+#[derive(Default, Debug, Clone, Copy)]
+pub struct WaveDrom;
+
+impl WaveDrom {
+    pub fn load_filtered(reader: &mut dyn Read, filter: &WaveFilter) -> Result<Wave> {
+        let mut text = String::new();
+        reader.read_to_string(&mut text)?;
+        let source = wavedrom_parse(&text)?;
+        let items = source
+            .get("signal")
+            .and_then(Value::as_array)
+            .ok_or_else(|| anyhow!("no signal list in WaveDrom source"))?;
+        let mut builder = WaveTreeBuilder::new();
+        let mut signals = vec![];
+        builder.push_scope(WAVEDROM_SCOPE.to_string(), WaveScopeType::Module);
+        wavedrom_signals(items, &mut builder, &mut signals)?;
+        builder.pop_scope()?;
+        info!("loaded {} WaveDrom signals", signals.len());
+        let (code_signal_info, code_paths, code_aliases, tree) = builder.finish();
+        let mut headers = HashMap::new();
+        if let Some(title) = source
+            .get("head")
+            .and_then(|head| head.get("text"))
+            .and_then(Value::as_str)
+        {
+            headers.insert("title".to_string(), title.to_string());
+        }
+        let mut info = WaveInfo {
+            timescale: WAVEDROM_TIMESCALE,
+            range: (0, 0),
+            headers,
+            code_signal_info,
+            code_paths,
+            code_aliases,
+            code_enum_literals: HashMap::new(),
+            tree,
+            dump_off: vec![],
+        };
+        let selected = filter.select(&info)?;
+        let mut window = filter.window(&info.timescale);
+        let mut data = HashMap::new();
+        let mut end = 0;
+        for (id, values) in signals {
+            let periods = items_end(&values);
+            end = end.max(periods);
+            if !selected.as_ref().map(|s| s.contains(&id)).unwrap_or(true) {
+                continue;
+            }
+            for (timestamp, value) in values {
+                let item = WaveDataItem { value, timestamp };
+                vcd_column_push(&mut data, &mut window, &info, id, item, false)?;
+            }
+        }
+        info.range = (0, end);
+        if let Some(window) = window {
+            info.range = window.range(info.range);
+            window.finish(&mut data, &info)?;
+        }
+        Ok(Wave {
+            info,
+            data,
+            selected,
+        })
+    }
+}
+
+/// End of wave made of `values`, one period after the last change
+fn items_end(values: &[(u64, WaveDataValue)]) -> u64 {
+    values
+        .last()
+        .map(|(t, _)| t - t % WAVEDROM_PERIOD + WAVEDROM_PERIOD)
+        .unwrap_or_default()
+}
+
+impl WaveLoader for WaveDrom {
+    fn name(&self) -> &'static str {
+        "WaveDrom"
+    }
+    fn extensions(&self) -> &'static [&'static str] {
+        &["json", "json5"]
+    }
+    fn detect(&self, head: &[u8]) -> bool {
+        is_wavedrom(head)
+    }
+    fn load(
+        &self,
+        reader: &mut dyn Read,
+        _progress_handler: &dyn Fn(f32, u64),
+        _last_timestamp: Option<u64>,
+    ) -> Result<Wave> {
+        Self::load_filtered(reader, &WaveFilter::default())
+    }
+    fn load_source(
+        &self,
+        mut source: &mut dyn WaveSource,
+        _progress_handler: &dyn Fn(f32, u64),
+        filter: &WaveFilter,
+    ) -> Result<(Wave, Option<u64>)> {
+        Ok((Self::load_filtered(&mut source, filter)?, None))
+    }
+}
+
+/// Character of one period and label of bus value
+fn wavedrom_char(
+    table: &WaveTable,
+    wave: &Wave,
+    column: usize,
+    value: Option<&WaveDataValue>,
+) -> (char, Option<String>) {
+    let Some(value) = value else {
+        return ('x', None);
+    };
+    let signal = &table.columns[column];
+    let width = wave
+        .info
+        .code_signal_info
+        .get(&signal.id)
+        .map(|info| info.width)
+        .unwrap_or(1);
+    let wires = match value {
+        WaveDataValue::Raw(v) => v.clone(),
+        WaveDataValue::Port(v) => v.wires(),
+        WaveDataValue::Comp(v) if width <= 1 => {
+            let bit = v.first().map(|b| b & 1).unwrap_or_default();
+            vec![[WireValue::V0, WireValue::V1][bit as usize]]
+        }
+        _ => vec![],
+    };
+    // single bits and buses all x or z are levels, other bus values are labelled
+    match wires.first() {
+        Some(first)
+            if wires.iter().all(|w| w == first) && (width <= 1 || *first >= WireValue::X) =>
+        {
+            (first.to_string().chars().next().unwrap(), None)
+        }
+        _ => ('=', Some(table.value_text(wave, signal, value))),
+    }
+}
+
+/// Write columns of `table` as WaveDrom source, one period per row,
+/// the clock of [TableRows::Edge] rows is written as `p` clock and other signals take values
+/// changed at the edge, as WaveDrom draws them right after it
+pub fn wavedrom_write(table: &WaveTable, wave: &Wave, writer: &mut dyn Write) -> Result<()> {
+    let timestamps = table.row_timestamps(wave)?;
+    writeln!(writer, "{{\"signal\": [")?;
+    for (index, column) in table.columns.iter().enumerate() {
+        let mut chars = String::new();
+        let mut labels = vec![];
+        if table.rows == TableRows::Edge(column.id) {
+            chars = "p".to_string() + &".".repeat(timestamps.len().saturating_sub(1));
+        } else {
+            let mut last = None;
+            for timestamp in &timestamps {
+                let item = wave.find_value(column.id, *timestamp);
+                let next = wavedrom_char(table, wave, index, item.as_ref().map(|i| &i.value));
+                if last.as_ref() == Some(&next) {
+                    chars.push('.');
+                    continue;
+                }
+                chars.push(next.0);
+                labels.extend(next.1.clone());
+                last = Some(next);
+            }
+        }
+        let name = column.path.rsplit('.').next().unwrap_or_default();
+        // keys in WaveDrom order
+        let data = match labels.is_empty() {
+            true => "".to_string(),
+            false => format!(", \"data\": {}", json!(labels)),
+        };
+        let separator = if index + 1 < table.columns.len() {
+            ","
+        } else {
+            ""
+        };
+        writeln!(
+            writer,
+            "  {{\"name\": {}, \"wave\": {}{data}}}{separator}",
+            json!(name),
+            json!(chars)
+        )?;
+    }
+    writeln!(writer, "]}}")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::radix::Radix;
+    use crate::wave::table_writer::{TableColumn, TableFormat, TableRows, WaveTable};
+    use crate::wave::vcd_parser::Vcd;
+    use crate::wave::wavedrom::{is_wavedrom, wavedrom_parse, WaveDrom, WAVEDROM_PERIOD};
+    use crate::wave::{WaveDataValue, WaveLoader, WaveSignalType};
+    use anyhow::Result;
+    use std::fs::File;
+
+    #[test]
+    fn test_wavedrom_parse() -> Result<()> {
+        let value = wavedrom_parse(
+            "{ signal: [ // comment\n  { name: 'clk', wave: \"p..\", }, /* spacer */ {},\n], }",
+        )?;
+        assert_eq!(value["signal"][0]["name"], "clk");
+        assert_eq!(value["signal"][0]["wave"], "p..");
+        assert_eq!(value["signal"][1], serde_json::json!({}));
+        assert!(wavedrom_parse("{signal: [}").is_err());
+        assert!(is_wavedrom(b"{ signal: [] }"));
+        assert!(is_wavedrom(b"{\"signal\" : []}"));
+        assert!(is_wavedrom(b"// spec\n/* diagram */ { signal: [] }"));
+        assert!(!is_wavedrom(b"{\"signals\": []}"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_wavedrom_load() -> Result<()> {
+        let source = "{signal: [
+            {name: 'clk', wave: 'p...'},
+            ['bus',
+              {name: 'addr', wave: 'x=.=', data: ['0x10', '12']},
+              {name: 'op', wave: '2.3x', data: 'read write'},
+            ],
+            {name: 'en', wave: '01.0', phase: 0.5},
+        ]}";
+        let wave = WaveDrom.load(&mut source.as_bytes(), &|_, _| {}, None)?;
+        let id = |path: &str| {
+            wave.info
+                .code_paths
+                .iter()
+                .find(|(_, p)| p.join(".") == path)
+                .map(|(id, _)| *id)
+                .unwrap()
+        };
+        let p = WAVEDROM_PERIOD;
+        assert_eq!(wave.info.range, (0, 4 * p));
+        let clk = wave.data.get(&id("wavedrom.clk")).unwrap();
+        assert_eq!(clk.iter().count(), 8);
+        let addr = id("wavedrom.bus.addr");
+        assert_eq!(wave.info.code_signal_info[&addr].width, 5);
+        assert_eq!(
+            wave.find_value(addr, p).unwrap().value,
+            WaveDataValue::Comp(vec![0x10])
+        );
+        assert_eq!(
+            wave.find_value(addr, 3 * p).unwrap().value,
+            WaveDataValue::Comp(vec![12])
+        );
+        let op = id("wavedrom.bus.op");
+        assert_eq!(wave.info.code_signal_info[&op].typ, WaveSignalType::String);
+        assert_eq!(
+            wave.find_value(op, 2 * p).unwrap().value,
+            WaveDataValue::String("write".to_string())
+        );
+        let en = id("wavedrom.en");
+        assert_eq!(
+            wave.find_value(en, p / 2).unwrap().value,
+            WaveDataValue::Comp(vec![1])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_wavedrom_write() -> Result<()> {
+        let wave = Vcd.load(&mut File::open("data/counter.vcd")?, &|_, _| {}, None)?;
+        let columns = [
+            (0, "top.clk"),
+            (2, "top.counter.cnt"),
+            (3, "top.counter.en"),
+        ]
+        .into_iter()
+        .map(|(id, path)| TableColumn {
+            id,
+            path: path.to_string(),
+            radix: Radix::Dec,
+            literals: true,
+        })
+        .collect();
+        let table = WaveTable {
+            columns,
+            rows: TableRows::Edge(0),
+            range: (0, 45),
+            format: TableFormat::WaveDrom,
+        };
+        let mut text = vec![];
+        table.write(&wave, &mut text)?;
+        let source = wavedrom_parse(&String::from_utf8(text.clone())?)?;
+        // rising edges at 5, 15, 25, 35 and 45
+        assert_eq!(source["signal"][0]["wave"], "p....");
+        assert_eq!(source["signal"][1]["wave"], "x==.=");
+        assert_eq!(
+            source["signal"][1]["data"],
+            serde_json::json!(["1", "2", "3"])
+        );
+        assert_eq!(source["signal"][2]["wave"], "z1.0.");
+        assert!(String::from_utf8(text.clone())?.contains(r#"{"name": "cnt", "wave": "x==.=""#));
+        // written diagram loads back
+        let loaded = WaveDrom.load(&mut text.as_slice(), &|_, _| {}, None)?;
+        assert_eq!(loaded.info.code_paths.len(), 3);
+        Ok(())
+    }
+}